@@ -0,0 +1,97 @@
+//! Parse-throughput benchmark.
+//!
+//! Times `tomlp::parse` and `tomlp::parse_borrowed` on the ripgrep manifest used by the test
+//! suite and on a synthetic ~50 MB document written to the system temp directory.
+//!
+//! Run with `cargo bench --bench parse`.
+//!
+//! Moving from grapheme-cluster segments to the byte-oriented lexer took (release build, same
+//! machine):
+//!
+//! | input             | graphemes             | lexer                  |
+//! | ----------------- | --------------------- | ---------------------- |
+//! | ripgrep.toml      | 615.6µs (5.21 MiB/s)  | 58.2µs (55.13 MiB/s)   |
+//! | synthetic (50 MB) | 11.61s (4.31 MiB/s)   | 1.56s (32.11 MiB/s)    |
+//!
+//! Borrowing strings from the source (`parse_borrowed`) instead of copying them (`parse`):
+//!
+//! | input             | parse                 | parse_borrowed         |
+//! | ----------------- | --------------------- | ---------------------- |
+//! | ripgrep.toml      | 82.7µs (38.81 MiB/s)  | 48.7µs (65.85 MiB/s)   |
+//! | synthetic (50 MB) | 2.02s (24.70 MiB/s)   | 1.54s (32.40 MiB/s)    |
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const SYNTHETIC_SIZE: usize = 50 * 1024 * 1024;
+
+fn main() {
+    let ripgrep =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tomlparse/test_resources/ripgrep.toml");
+    bench("ripgrep.toml", &ripgrep, 2000, parse_owned);
+    bench("ripgrep.toml (ref)", &ripgrep, 2000, parse_borrowed);
+
+    let synthetic = write_synthetic();
+    bench("synthetic (50 MB)", &synthetic, 3, parse_owned);
+    bench("synthetic (ref)", &synthetic, 3, parse_borrowed);
+    let _ = std::fs::remove_file(synthetic);
+}
+
+fn parse_owned(path: &str) {
+    drop(tomlp::parse(path).expect("benchmark input parses"));
+}
+
+fn parse_borrowed(path: &str) {
+    let source = std::fs::read_to_string(path).expect("benchmark input exists");
+    drop(tomlp::parse_borrowed(&source).expect("benchmark input parses"));
+}
+
+/// Parses `file` `iterations` times and reports the mean wall time and throughput.
+fn bench(label: &str, file: &Path, iterations: u32, parse: fn(&str)) {
+    let path = file.to_str().expect("benchmark paths are valid UTF-8");
+    let size = std::fs::metadata(file)
+        .expect("benchmark input exists")
+        .len() as f64;
+
+    // warm the page cache
+    parse(path);
+
+    let mut total = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        parse(path);
+        total += start.elapsed();
+    }
+    let mean = total / iterations;
+    let throughput = size / mean.as_secs_f64() / (1024.0 * 1024.0);
+    println!("{label:<20} {mean:>12.3?}/iter {throughput:>10.2} MiB/s ({iterations} iterations)");
+}
+
+/// Writes a document of roughly `SYNTHETIC_SIZE` bytes exercising every value type.
+fn write_synthetic() -> PathBuf {
+    let mut doc = String::with_capacity(SYNTHETIC_SIZE + 4096);
+    doc.push_str("# synthetic benchmark document\ntitle = \"synthetic\"\n\n");
+    let mut i = 0usize;
+    while doc.len() < SYNTHETIC_SIZE {
+        let _ = write!(
+            doc,
+            "[[record]]\n\
+             id = {i}\n\
+             name = \"record-{i} \\u00E9\\t\\\"quoted\\\"\"\n\
+             path = 'C:\\data\\{i}'\n\
+             ratio = {i}.5e-3\n\
+             mask = 0xDEAD_BEEF\n\
+             enabled = true # trailing comment\n\
+             created = 1979-05-27T07:32:00Z\n\
+             tags = [\"alpha\", \"beta\", \"gamma\", {i}]\n\
+             point = {{ x = {i}, y = -{i}, label.text = \"p\" }}\n\
+             notes = \"\"\"\n\
+             line one\n\
+             line two\"\"\"\n\n"
+        );
+        i += 1;
+    }
+    let file = std::env::temp_dir().join(format!("tomlp-bench-{}.toml", std::process::id()));
+    std::fs::write(&file, doc).expect("temp dir is writable");
+    file
+}
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // invalid UTF-8 is reported by the reader
+    for event in tomlp::events(data) {
+        if event.is_err() {
+            break;
+        }
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        for tok in tomlp::lexer::tokenize(source).flatten() {
+            let _ = tok.text(source);
+        }
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        if let Ok(parsed) = tomlp::parse_str(source) {
+            // the tree printout walks every value
+            let _ = parsed.to_string();
+        }
+    }
+});
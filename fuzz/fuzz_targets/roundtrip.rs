@@ -0,0 +1,50 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use tomlp::{syntax::Document, FormatOptions, TOMLTable, TOMLType};
+
+/// Whether two values are the same, counting NaNs as equal to each other.
+fn same(a: &TOMLType, b: &TOMLType) -> bool {
+    match (a, b) {
+        (TOMLType::Float(a), TOMLType::Float(b)) => a == b || a.is_nan() && b.is_nan(),
+        (TOMLType::Array(a), TOMLType::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        }
+        (TOMLType::AoT(a), TOMLType::AoT(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_table(a, b))
+        }
+        (TOMLType::HTable(a), TOMLType::HTable(b))
+        | (TOMLType::DKTable(a), TOMLType::DKTable(b))
+        | (TOMLType::InlineTable(a), TOMLType::InlineTable(b)) => same_table(a, b),
+        _ => a == b,
+    }
+}
+
+fn same_table(a: &TOMLTable, b: &TOMLTable) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .all(|(key, a)| b.get(key).is_some_and(|b| same(a, b)))
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(parsed) = tomlp::parse_str(source) else {
+        return;
+    };
+    // the syntax tree is lossless
+    let doc = Document::parse(source).expect("the syntax tree rejected a valid document");
+    assert_eq!(doc.to_string(), source);
+
+    let formatted = tomlp::format(source, &FormatOptions::default())
+        .expect("the formatter rejected a valid document");
+    let reparsed = tomlp::parse_str(&formatted).expect("the formatter wrote an invalid document");
+    assert!(
+        same_table(parsed.table(), reparsed.table()),
+        "formatting changed the document:\n{}",
+        formatted
+    );
+    // formatting twice changes nothing more
+    let again = tomlp::format(&formatted, &FormatOptions::default()).expect("reformatting failed");
+    assert_eq!(again, formatted);
+});
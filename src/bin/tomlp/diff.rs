@@ -0,0 +1,154 @@
+// diff.rs
+// Unified diffs between two versions of a file
+
+/// Lines of unchanged context around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// A unified diff from `old` to `new`, both named `name`, or an empty string if they're the
+/// same.
+pub fn unified(name: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = edits(&old, &new);
+    if edits.iter().all(|edit| *edit == Edit::Keep) {
+        return String::new();
+    }
+    // lines of `old` and `new` before each edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut o, mut n) = (0, 0);
+    for edit in &edits {
+        positions.push((o, n));
+        match edit {
+            Edit::Keep => (o, n) = (o + 1, n + 1),
+            Edit::Delete => o += 1,
+            Edit::Insert => n += 1,
+        }
+    }
+    positions.push((o, n));
+
+    let mut out = format!("--- {}\n+++ {}\n", name, name);
+    let changed = |i: &usize| edits[*i] != Edit::Keep;
+    let mut i = 0;
+    while let Some(first) = (i..edits.len()).find(changed) {
+        // extend the hunk while the next change is close enough to share context
+        let mut last = first;
+        while let Some(next) = (last + 1..edits.len()).find(changed) {
+            if next - last > 2 * CONTEXT + 1 {
+                break;
+            }
+            last = next;
+        }
+        let start = first.saturating_sub(CONTEXT).max(i);
+        let end = (last + 1 + CONTEXT).min(edits.len());
+        let ((o_start, n_start), (o_end, n_end)) = (positions[start], positions[end]);
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(o_start, o_end - o_start),
+            range(n_start, n_end - n_start)
+        ));
+        for (edit, (o, n)) in edits[start..end].iter().zip(&positions[start..end]) {
+            let (sign, line) = match edit {
+                Edit::Keep => (' ', old[*o]),
+                Edit::Delete => ('-', old[*o]),
+                Edit::Insert => ('+', new[*n]),
+            };
+            out.push(sign);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        i = end;
+    }
+    out
+}
+
+/// A hunk range: the first line (counting from 1) and the number of lines.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// The shortest edits from `old` to `new`, by Myers' algorithm.
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let offset = n + m + 1;
+    // the furthest `x` reached on each diagonal `k = x - y`
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (offset + k) as usize;
+    // `v` before each round, for retracing the path
+    let mut trace = Vec::new();
+    'search: for d in 0..=n + m {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]);
+            let mut x = if down { v[at(k + 1)] } else { v[at(k - 1)] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let (d, k) = (d as isize, x - y);
+        let down = k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]);
+        let prev_k = if down { k + 1 } else { k - 1 };
+        let prev_x = v[at(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(if x == prev_x {
+                Edit::Insert
+            } else {
+                Edit::Delete
+            });
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    edits.reverse();
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unified;
+
+    #[test]
+    fn hunks() {
+        assert_eq!(unified("a.toml", "x\ny\n", "x\ny\n"), "");
+        let old = "a=1\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm=2\n";
+        let new = "a = 1\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm = 2\nn";
+        assert_eq!(
+            unified("a.toml", old, new),
+            "--- a.toml\n+++ a.toml\n\
+             @@ -1,4 +1,4 @@\n-a=1\n+a = 1\n b\n c\n d\n\
+             @@ -10,4 +10,5 @@\n j\n k\n l\n-m=2\n+m = 2\n+n\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            unified("a.toml", "a\n", ""),
+            "--- a.toml\n+++ a.toml\n@@ -1 +0,0 @@\n-a\n"
+        );
+    }
+}
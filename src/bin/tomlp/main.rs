@@ -0,0 +1,365 @@
+// main.rs
+// The command-line tool
+mod diff;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use tomlp::diff::DiffOptions;
+use tomlp::lint::{self, LintOptions, Report, Severity};
+use tomlp::schema::{DatetimeTypes, RustOptions, Schema};
+use tomlp::{FormatOptions, SortOptions};
+
+const USAGE: &str = "\
+Usage: tomlp <command> [options] <files>
+
+Commands:
+    infer-schema [--format toml|json] <files>...
+        Prints a schema that all of the files match, as a TOML schema (the default) or a
+        JSON Schema.
+
+    codegen [--lang rust] [--root <name>] [--derive <traits>] [--datetime tomlp|chrono]
+            [--schema <schema>] <files>...
+        Prints Rust types for documents like the example files, or for the given schema
+        (TOML or JSON). Keys missing from some examples are `Option`s. `--derive` takes a
+        comma-separated list, replacing the default serde derives, which need tomlp's
+        `serde` feature.
+
+    fmt [--check] [--config <file>] <files>...
+        Formats the files in place. With `--check`, changes nothing, but prints a diff for
+        each file that isn't formatted and fails if there are any. Settings are read from
+        the `[fmt]` table of the config file, which defaults to the nearest `tomlp.toml` in
+        the current directory or its parents.
+
+    sort [--tables <tables>] [--arrays] [--check] <files>...
+        Sorts the keys of the given tables in place, keeping comments with their keys.
+        `--tables` takes a comma-separated list of dotted keys, where `*` matches any key,
+        and defaults to the dependency tables of a `Cargo.toml`. `--arrays` sorts arrays of
+        strings within the tables too. `--check` works as it does for `fmt`.
+
+    lint [--format text|json|sarif] [--config <file>] <files>...
+        Checks the files against the lint rules, and fails if any finding is an error.
+        Rules and their severities are set in the `[lint]` table of the config file, found
+        as for `fmt`. Rules: inline-table-depth, unnecessary-quotes, split-dotted-table,
+        duplicate-array-values, multiline-string.
+
+    diff [--key <key>] [--format text|json] <old> <new>
+        Prints the keys added, removed, and changed between two files, ignoring formatting
+        and key order. Arrays of tables are compared table by table, by position, or with
+        `--key`, by the value of that key in each table (ex. `--key name`).
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("infer-schema") => infer_schema(&args[1..]),
+        Some("codegen") => codegen(&args[1..]),
+        Some("fmt") => fmt(&args[1..]),
+        Some("sort") => sort(&args[1..]),
+        Some("lint") => lint(&args[1..]),
+        Some("diff") => diff(&args[1..]),
+        Some("-h" | "--help" | "help") => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(format!("Unknown command `{}`.\n\n{}", other, USAGE)),
+        None => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err.trim_end());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn infer_schema(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--format"], &[])?;
+    let schema = infer(&args.files)?;
+    match args.value("--format").unwrap_or("toml") {
+        "toml" => print!("{}", schema.to_toml()),
+        "json" => print!("{}", schema.to_json_schema()),
+        other => return Err(format!("Unknown format `{}`.", other)),
+    }
+    Ok(())
+}
+
+fn codegen(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &["--lang", "--root", "--derive", "--datetime", "--schema"],
+        &[],
+    )?;
+    match args.value("--lang").unwrap_or("rust") {
+        "rust" => (),
+        other => {
+            return Err(format!(
+                "Unknown language `{}`; only `rust` is supported.",
+                other
+            ))
+        }
+    }
+    let schema = match args.value("--schema") {
+        Some(file) if args.files.is_empty() => {
+            Schema::load(file).map_err(|err| format!("{}: {}", file, err))?
+        }
+        Some(_) => return Err("Give either example files or `--schema`, not both.".to_string()),
+        None => infer(&args.files)?,
+    };
+    let mut options = RustOptions::default();
+    if let Some(root) = args.value("--root") {
+        options.root = root.to_string();
+    }
+    if let Some(derives) = args.value("--derive") {
+        options.derives = derives
+            .split(',')
+            .map(str::trim)
+            .filter(|derive| !derive.is_empty())
+            .map(String::from)
+            .collect();
+    }
+    options.datetimes = match args.value("--datetime").unwrap_or("tomlp") {
+        "tomlp" => DatetimeTypes::Tomlp,
+        "chrono" => DatetimeTypes::Chrono,
+        other => return Err(format!("Unknown datetime types `{}`.", other)),
+    };
+    print!("{}", schema.to_rust(&options));
+    Ok(())
+}
+
+fn fmt(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--config"], &["--check"])?;
+    if args.files.is_empty() {
+        return Err(format!("No files given.\n\n{}", USAGE));
+    }
+    let options = config(&args, |file| FormatOptions::load(file))?;
+    let unformatted = rewrite(&args, |source| tomlp::format(source, &options))?;
+    match unformatted {
+        0 => Ok(()),
+        1 => Err("1 file isn't formatted.".to_string()),
+        n => Err(format!("{} files aren't formatted.", n)),
+    }
+}
+
+fn sort(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--tables"], &["--arrays", "--check"])?;
+    if args.files.is_empty() {
+        return Err(format!("No files given.\n\n{}", USAGE));
+    }
+    let mut options = SortOptions {
+        arrays: args.flag("--arrays"),
+        ..SortOptions::default()
+    };
+    if let Some(tables) = args.value("--tables") {
+        options.tables = tables
+            .split(',')
+            .map(str::trim)
+            .filter(|table| !table.is_empty())
+            .map(String::from)
+            .collect();
+    }
+    let unsorted = rewrite(&args, |source| tomlp::sort(source, &options))?;
+    match unsorted {
+        0 => Ok(()),
+        1 => Err("1 file isn't sorted.".to_string()),
+        n => Err(format!("{} files aren't sorted.", n)),
+    }
+}
+
+/// Rewrites each of the files with `change`, in place, or with `--check`, prints a diff of the
+/// change instead. Returns the number of files that `--check` found would change.
+fn rewrite<F>(args: &Args, change: F) -> Result<usize, String>
+where
+    F: Fn(&str) -> Result<String, String>,
+{
+    let mut changed = 0;
+    for file in &args.files {
+        let source = std::fs::read_to_string(file)
+            .map_err(|err| format!("{}: File Read Error: {}", file, err.kind()))?;
+        let rewritten = change(&source).map_err(|err| format!("{}: {}", file, err))?;
+        if rewritten == source {
+            continue;
+        }
+        if args.flag("--check") {
+            print!("{}", diff::unified(file, &source, &rewritten));
+            changed += 1;
+        } else {
+            std::fs::write(file, rewritten)
+                .map_err(|err| format!("{}: File Write Error: {}", file, err.kind()))?;
+        }
+    }
+    Ok(changed)
+}
+
+fn lint(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--config", "--format"], &[])?;
+    if args.files.is_empty() {
+        return Err(format!("No files given.\n\n{}", USAGE));
+    }
+    let options = config(&args, |file| LintOptions::load(file))?;
+    let format = args.value("--format").unwrap_or("text");
+    if !["text", "json", "sarif"].contains(&format) {
+        return Err(format!("Unknown format `{}`.", format));
+    }
+    let mut reports = Vec::new();
+    let mut invalid = 0;
+    for file in &args.files {
+        let findings = std::fs::read_to_string(file)
+            .map_err(|err| format!("File Read Error: {}", err.kind()))
+            .and_then(|source| lint::check(&source, &options));
+        match findings {
+            Ok(findings) => reports.push(Report {
+                file: file.clone(),
+                findings,
+            }),
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                invalid += 1;
+            }
+        }
+    }
+    match format {
+        "json" => print!("{}", lint::to_json(&reports)),
+        "sarif" => print!("{}", lint::to_sarif(&reports)),
+        _ => {
+            for report in &reports {
+                for finding in &report.findings {
+                    println!(
+                        "{}:{}: {}: {} [{}]",
+                        report.file, finding.line, finding.severity, finding.message, finding.rule
+                    );
+                }
+            }
+        }
+    }
+    let errors = reports
+        .iter()
+        .flat_map(|report| &report.findings)
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    let mut problems = Vec::new();
+    match errors {
+        0 => (),
+        1 => problems.push("1 error".to_string()),
+        n => problems.push(format!("{} errors", n)),
+    }
+    match invalid {
+        0 => (),
+        1 => problems.push("1 invalid file".to_string()),
+        n => problems.push(format!("{} invalid files", n)),
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Found {}.", problems.join(" and ")))
+    }
+}
+
+fn diff(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--key", "--format"], &[])?;
+    let [old, new] = args.files.as_slice() else {
+        return Err(format!("Give two files to compare.\n\n{}", USAGE));
+    };
+    let parse = |file: &String| tomlp::parse(file).map_err(|err| format!("{}: {}", file, err));
+    let (old, new) = (parse(old)?, parse(new)?);
+    let options = DiffOptions {
+        aot_key: args.value("--key").map(String::from),
+    };
+    let changes = tomlp::diff::compare(old.table(), new.table(), &options);
+    match args.value("--format").unwrap_or("text") {
+        "text" => changes.iter().for_each(|change| println!("{}", change)),
+        "json" => print!("{}", tomlp::diff::to_json(&changes)),
+        other => return Err(format!("Unknown format `{}`.", other)),
+    }
+    Ok(())
+}
+
+/// Reads settings from `--config`, or else the nearest `tomlp.toml` in the current directory
+/// or its parents, if there is one.
+fn config<T, F>(args: &Args, load: F) -> Result<T, String>
+where
+    T: Default,
+    F: Fn(&Path) -> Result<T, String>,
+{
+    let file = match args.value("--config") {
+        Some(file) => PathBuf::from(file),
+        None => {
+            let dir = std::env::current_dir().map_err(|err| err.to_string())?;
+            match dir
+                .ancestors()
+                .map(|dir| dir.join("tomlp.toml"))
+                .find(|file| file.is_file())
+            {
+                Some(file) => file,
+                None => return Ok(T::default()),
+            }
+        }
+    };
+    load(&file).map_err(|err| format!("{}: {}", file.display(), err))
+}
+
+/// Infers a schema from sample files.
+fn infer(files: &[String]) -> Result<Schema, String> {
+    if files.is_empty() {
+        return Err(format!("No files given.\n\n{}", USAGE));
+    }
+    let parsed = files
+        .iter()
+        .map(|file| tomlp::parse(file).map_err(|err| format!("{}: {}", file, err)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Schema::infer(parsed.iter().map(|parsed| parsed.table())))
+}
+
+/// The options and files of a command.
+struct Args {
+    values: HashMap<String, String>,
+    flags: Vec<String>,
+    files: Vec<String>,
+}
+
+impl Args {
+    /// Splits `args` into options and files. `valued` are the options that take a value,
+    /// written either `--name value` or `--name=value`, and `flags` are the ones that don't.
+    fn parse(args: &[String], valued: &[&str], flags: &[&str]) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut set = Vec::new();
+        let mut files = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                files.push(arg.clone());
+                continue;
+            }
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, val)) => (name, Some(val.to_string())),
+                None => (arg.as_str(), None),
+            };
+            if flags.contains(&name) && inline.is_none() {
+                set.push(name.to_string());
+                continue;
+            }
+            if !valued.contains(&name) {
+                return Err(format!("Unknown option `{}`.", name));
+            }
+            let val = inline
+                .or_else(|| args.next().cloned())
+                .ok_or_else(|| format!("Option `{}` needs a value.", name))?;
+            values.insert(name.to_string(), val);
+        }
+        Ok(Self {
+            values,
+            flags: set,
+            files,
+        })
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+}
@@ -0,0 +1,571 @@
+//! TOML's date and time values, kept exactly as written.
+//!
+//! Conversions to and from the types of the `chrono` and `time` crates are available through
+//! the cargo features of the same names, as is serialization with `serde`.
+
+#[cfg(feature = "chrono")]
+mod with_chrono;
+#[cfg(feature = "serde")]
+mod with_serde;
+#[cfg(feature = "time")]
+mod with_time;
+
+// stdlib imports
+use std::fmt;
+use std::str::FromStr;
+
+/// An offset date-time, local date-time, local date, or local time.
+///
+/// Which of these it is follows from the parts present:
+///
+/// | kind              | `date` | `time` | `offset` |
+/// | ----------------- | ------ | ------ | -------- |
+/// | offset date-time  | yes    | yes    | yes      |
+/// | local date-time   | yes    | yes    | no       |
+/// | local date        | yes    | no     | no       |
+/// | local time        | no     | yes    | no       |
+///
+/// `Display` produces RFC 3339 (ex. `1979-05-27T07:32:00Z`); a local date or time prints only
+/// the part it has.
+///
+/// ```
+/// let dt: tomlp::Datetime = "1979-05-27 00:32:00.999999-07:00".parse()?;
+/// assert_eq!(dt.date.map(|date| date.year), Some(1979));
+/// assert_eq!(dt.to_string(), "1979-05-27T00:32:00.999999-07:00");
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Datetime {
+    pub date: Option<Date>,
+    pub time: Option<Time>,
+    /// Only present along with both a date and a time.
+    pub offset: Option<Offset>,
+}
+impl Datetime {
+    /// Which of the four forms this is.
+    pub fn kind(&self) -> DatetimeKind {
+        match (&self.date, &self.time, &self.offset) {
+            (Some(_), Some(_), Some(_)) => DatetimeKind::OffsetDatetime,
+            (Some(_), Some(_), None) => DatetimeKind::LocalDatetime,
+            (Some(_), None, _) => DatetimeKind::LocalDate,
+            (None, _, _) => DatetimeKind::LocalTime,
+        }
+    }
+
+    /// The error for converting to a type of another kind (ex. `"a local date"`).
+    #[allow(dead_code)] // only used by the conversion features
+    fn not_a(&self, kind: &str) -> String {
+        format!("`{}` is not {}.", self, kind)
+    }
+}
+
+/// The four forms of [`Datetime`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DatetimeKind {
+    OffsetDatetime,
+    LocalDatetime,
+    LocalDate,
+    LocalTime,
+}
+impl fmt::Display for DatetimeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OffsetDatetime => "offset date-time",
+            Self::LocalDatetime => "local date-time",
+            Self::LocalDate => "local date",
+            Self::LocalTime => "local time",
+        })
+    }
+}
+
+/// A calendar date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Date {
+    pub year: u16,
+    /// 1 to 12
+    pub month: u8,
+    /// 1 to the last day of the month
+    pub day: u8,
+}
+
+/// A time of day.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Time {
+    /// 0 to 23
+    pub hour: u8,
+    /// 0 to 59
+    pub minute: u8,
+    /// 0 to 59, or 60 for a leap second
+    pub second: u8,
+    /// The digits after the decimal point, if any, without truncation (ex. `"999999"`).
+    pub fraction: Option<String>,
+}
+impl Time {
+    /// The fractional second in nanoseconds. Digits beyond the ninth are truncated.
+    pub fn nanosecond(&self) -> u32 {
+        let digits = self.fraction.as_deref().unwrap_or_default();
+        digits
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(9)
+            .fold(0, |nanos, digit| nanos * 10 + u32::from(digit - b'0'))
+    }
+
+    /// Builds a time from a nanosecond count, which may exceed a second for a leap second.
+    #[allow(dead_code)] // only used by the conversion features
+    fn from_nanos(hour: u8, minute: u8, second: u8, nanosecond: u32) -> Self {
+        let (second, nanosecond) = if nanosecond >= 1_000_000_000 {
+            (second + 1, nanosecond - 1_000_000_000)
+        } else {
+            (second, nanosecond)
+        };
+        let fraction = (nanosecond != 0).then(|| {
+            format!("{:09}", nanosecond)
+                .trim_end_matches('0')
+                .to_string()
+        });
+        Self {
+            hour,
+            minute,
+            second,
+            fraction,
+        }
+    }
+}
+
+/// The offset of a date-time from UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Offset {
+    /// `Z`
+    Z,
+    /// `+HH:MM` or `-HH:MM`
+    Custom { minutes: i16 },
+    /// `-00:00`: the time is in UTC, but the local offset is unknown (RFC 3339, section 4.3).
+    Unknown,
+}
+impl Offset {
+    /// Minutes east of UTC.
+    pub fn minutes(self) -> i16 {
+        match self {
+            Self::Z | Self::Unknown => 0,
+            Self::Custom { minutes } => minutes,
+        }
+    }
+}
+
+//////////////////
+// Display
+//////////////////
+
+impl fmt::Display for Datetime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(date) = &self.date {
+            write!(f, "{}", date)?;
+        }
+        if let Some(time) = &self.time {
+            if self.date.is_some() {
+                write!(f, "T")?;
+            }
+            write!(f, "{}", time)?;
+        }
+        if let Some(offset) = &self.offset {
+            write!(f, "{}", offset)?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        if let Some(fraction) = &self.fraction {
+            write!(f, ".{}", fraction)?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for Offset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Z => write!(f, "Z"),
+            Self::Unknown => write!(f, "-00:00"),
+            Self::Custom { minutes } => {
+                let sign = if minutes < 0 { '-' } else { '+' };
+                let minutes = minutes.unsigned_abs();
+                write!(f, "{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+            }
+        }
+    }
+}
+
+//////////////////
+// Parsing
+//////////////////
+
+impl FromStr for Datetime {
+    type Err = String;
+
+    /// Parses any of TOML's four date and time forms, following RFC 3339 as the TOML spec
+    /// describes:
+    ///
+    /// - the `T` between the date and time may be lowercase or a space
+    /// - `Z` may be lowercase
+    /// - seconds are required, and may have a fraction of any length
+    /// - a leap second (`60`) is allowed in the last minute of an hour in UTC (ex.
+    ///   `05:29:60+05:30`)
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut cursor = Cursor(s.as_bytes());
+        let datetime = cursor.datetime().and_then(|datetime| match cursor.0 {
+            [] => Ok(datetime),
+            [b'+' | b'-' | b'Z' | b'z', ..] => Err("a local time cannot have an offset"),
+            _ => Err("unexpected characters at the end"),
+        });
+        datetime.map_err(|reason| format!("Invalid datetime `{}`: {}.", s, reason))
+    }
+}
+impl FromStr for Date {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.parse::<Datetime>()? {
+            Datetime {
+                date: Some(date),
+                time: None,
+                ..
+            } => Ok(date),
+            _ => Err(format!("`{}` is not a local date.", s)),
+        }
+    }
+}
+impl FromStr for Time {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.parse::<Datetime>()? {
+            Datetime {
+                date: None,
+                time: Some(time),
+                ..
+            } => Ok(time),
+            _ => Err(format!("`{}` is not a local time.", s)),
+        }
+    }
+}
+
+/// The unparsed remainder of a datetime. Each rule returns the reason it failed.
+struct Cursor<'s>(&'s [u8]);
+impl Cursor<'_> {
+    fn eat(&mut self, byte: u8) -> bool {
+        match self.0.split_first() {
+            Some((first, rest)) if *first == byte => {
+                self.0 = rest;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, byte: u8, reason: &'static str) -> Result<(), &'static str> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(reason)
+        }
+    }
+
+    /// Consumes exactly `n` digits.
+    fn digits(&mut self, n: usize, reason: &'static str) -> Result<u16, &'static str> {
+        match self.0.get(..n) {
+            Some(digits) if digits.iter().all(u8::is_ascii_digit) => {
+                self.0 = &self.0[n..];
+                Ok(digits
+                    .iter()
+                    .fold(0, |val, digit| val * 10 + u16::from(digit - b'0')))
+            }
+            _ => Err(reason),
+        }
+    }
+
+    /// A date, a time, or a date followed by a time and an optional offset.
+    fn datetime(&mut self) -> Result<Datetime, &'static str> {
+        // A local time's first field ends with `:` rather than `-`.
+        if self.0.iter().find(|b| !b.is_ascii_digit()) == Some(&b':') {
+            let time = self.time()?;
+            leap_second(&time, 0)?;
+            return Ok(Datetime {
+                date: None,
+                time: Some(time),
+                offset: None,
+            });
+        }
+        let date = self.date()?;
+        let mut datetime = Datetime {
+            date: Some(date),
+            time: None,
+            offset: None,
+        };
+        if self.eat(b'T') || self.eat(b't') || self.eat(b' ') {
+            let time = self.time()?;
+            if !self.0.is_empty() {
+                datetime.offset = Some(self.offset()?);
+            }
+            let minutes = match datetime.offset {
+                Some(Offset::Custom { minutes }) => minutes,
+                _ => 0,
+            };
+            leap_second(&time, minutes)?;
+            datetime.time = Some(time);
+        }
+        Ok(datetime)
+    }
+
+    /// `YYYY-MM-DD`
+    fn date(&mut self) -> Result<Date, &'static str> {
+        const FORMAT: &str = "a date must be written `YYYY-MM-DD`";
+        let year = self.digits(4, FORMAT)?;
+        self.expect(b'-', FORMAT)?;
+        let month = self.digits(2, FORMAT)? as u8;
+        self.expect(b'-', FORMAT)?;
+        let day = self.digits(2, FORMAT)? as u8;
+        if !(1..=12).contains(&month) {
+            return Err("the month must be 01 to 12");
+        }
+        if day == 0 || day > days_in_month(year, month) {
+            return Err(match (month, day) {
+                (2, 29) => "February 29th only exists in leap years",
+                _ => "the day does not exist in that month",
+            });
+        }
+        Ok(Date { year, month, day })
+    }
+
+    /// `HH:MM:SS` with an optional fraction
+    fn time(&mut self) -> Result<Time, &'static str> {
+        const FORMAT: &str = "a time must be written `HH:MM:SS`";
+        let hour = self.digits(2, FORMAT)? as u8;
+        self.expect(b':', FORMAT)?;
+        let minute = self.digits(2, FORMAT)? as u8;
+        self.expect(b':', FORMAT)?;
+        let second = self.digits(2, FORMAT)? as u8;
+        let fraction = if self.eat(b'.') {
+            let len = self.0.iter().take_while(|b| b.is_ascii_digit()).count();
+            if len == 0 {
+                return Err("the decimal point must be followed by a digit");
+            }
+            let (digits, rest) = self.0.split_at(len);
+            self.0 = rest;
+            Some(String::from_utf8_lossy(digits).into_owned())
+        } else {
+            None
+        };
+        if hour > 23 {
+            return Err("the hour must be 00 to 23");
+        }
+        if minute > 59 {
+            return Err("the minute must be 00 to 59");
+        }
+        if second > 60 {
+            return Err("the second must be 00 to 59, or 60 for a leap second");
+        }
+        Ok(Time {
+            hour,
+            minute,
+            second,
+            fraction,
+        })
+    }
+
+    /// `Z` or `±HH:MM`
+    fn offset(&mut self) -> Result<Offset, &'static str> {
+        const FORMAT: &str = "an offset must be `Z` or written `+HH:MM` or `-HH:MM`";
+        if self.eat(b'Z') || self.eat(b'z') {
+            return Ok(Offset::Z);
+        }
+        let negative = if self.eat(b'-') {
+            true
+        } else {
+            self.expect(b'+', FORMAT)?;
+            false
+        };
+        let hours = self.digits(2, FORMAT)?;
+        self.expect(b':', FORMAT)?;
+        let minutes = self.digits(2, FORMAT)?;
+        if hours > 23 {
+            return Err("the offset hour must be 00 to 23");
+        }
+        if minutes > 59 {
+            return Err("the offset minute must be 00 to 59");
+        }
+        let total = (hours * 60 + minutes) as i16;
+        Ok(match (negative, total) {
+            (true, 0) => Offset::Unknown,
+            (true, total) => Offset::Custom { minutes: -total },
+            (false, total) => Offset::Custom { minutes: total },
+        })
+    }
+}
+
+/// Checks that a leap second in `time`, which is `offset` minutes ahead of UTC, falls in the
+/// last minute of an hour in UTC.
+fn leap_second(time: &Time, offset: i16) -> Result<(), &'static str> {
+    if time.second == 60 && (i16::from(time.minute) - offset).rem_euclid(60) != 59 {
+        return Err("a leap second must be in the last minute of an hour in UTC");
+    }
+    Ok(())
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Date, Datetime, DatetimeKind, Offset, Time};
+
+    /// Parses `text`, checking that it prints back as `rfc3339`.
+    fn check(text: &str, rfc3339: &str) -> Result<Datetime, String> {
+        let dt: Datetime = text.parse()?;
+        assert_eq!(dt.to_string(), rfc3339, "{}", text);
+        Ok(dt)
+    }
+
+    fn reason(text: &str) -> String {
+        match text.parse::<Datetime>() {
+            Ok(dt) => panic!("`{}` parsed as {:?}", text, dt),
+            Err(msg) => msg,
+        }
+    }
+
+    // Each test follows a rule of the spec's sections on the four datetime kinds.
+
+    #[test]
+    fn offset_datetime() -> Result<(), String> {
+        check("1979-05-27T07:32:00Z", "1979-05-27T07:32:00Z")?;
+        let dt = check("1979-05-27T00:32:00-07:00", "1979-05-27T00:32:00-07:00")?;
+        assert_eq!(dt.offset, Some(Offset::Custom { minutes: -420 }));
+        assert_eq!(dt.kind(), DatetimeKind::OffsetDatetime);
+        check(
+            "1979-05-27T00:32:00.999999-07:00",
+            "1979-05-27T00:32:00.999999-07:00",
+        )?;
+        // `-00:00` and `+00:00` are kept apart from `Z`
+        let dt = check("1979-05-27T00:32:00-00:00", "1979-05-27T00:32:00-00:00")?;
+        assert_eq!(dt.offset, Some(Offset::Unknown));
+        check("1979-05-27T00:32:00+00:00", "1979-05-27T00:32:00+00:00")?;
+        check("1979-05-27T00:32:00+05:45", "1979-05-27T00:32:00+05:45")?;
+        assert!(reason("1979-05-27T00:32:00+24:00").contains("offset hour"));
+        assert!(reason("1979-05-27T00:32:00+05:60").contains("offset minute"));
+        assert!(reason("1979-05-27T00:32:00+0700").contains("`+HH:MM`"));
+        assert!(reason("1979-05-27T00:32:00+7:00").contains("`+HH:MM`"));
+        assert!(reason("1979-05-27T00:32:00 Z").contains("`+HH:MM`"));
+        Ok(())
+    }
+
+    #[test]
+    fn delimiters() -> Result<(), String> {
+        // the `T` may be replaced with a space, and both it and `Z` may be lowercase
+        check("1979-05-27 07:32:00Z", "1979-05-27T07:32:00Z")?;
+        check("1979-05-27t07:32:00z", "1979-05-27T07:32:00Z")?;
+        check("1979-05-27 00:32:00-07:00", "1979-05-27T00:32:00-07:00")?;
+        check("1979-05-27 07:32:00", "1979-05-27T07:32:00")?;
+        assert!(reason("1979-05-27X07:32:00").contains("unexpected"));
+        assert!(reason("1979-05-27T").contains("`HH:MM:SS`"));
+        Ok(())
+    }
+
+    #[test]
+    fn local_datetime() -> Result<(), String> {
+        let dt = check("1979-05-27T07:32:00", "1979-05-27T07:32:00")?;
+        assert_eq!(dt.offset, None);
+        assert_eq!(dt.kind(), DatetimeKind::LocalDatetime);
+        check("1979-05-27T00:32:00.999999", "1979-05-27T00:32:00.999999")?;
+        Ok(())
+    }
+
+    #[test]
+    fn local_date() -> Result<(), String> {
+        let dt = check("1979-05-27", "1979-05-27")?;
+        let date = Date {
+            year: 1979,
+            month: 5,
+            day: 27,
+        };
+        assert_eq!(dt.date, Some(date));
+        assert_eq!(dt.kind(), DatetimeKind::LocalDate);
+        assert_eq!("1979-05-27".parse::<Date>()?, date);
+        check("0000-01-01", "0000-01-01")?;
+        assert!(reason("1979-5-27").contains("`YYYY-MM-DD`"));
+        assert!(reason("79-05-27").contains("`YYYY-MM-DD`"));
+        Ok(())
+    }
+
+    #[test]
+    fn local_time() -> Result<(), String> {
+        let dt = check("07:32:00", "07:32:00")?;
+        assert_eq!(dt.date, None);
+        assert_eq!(dt.kind().to_string(), "local time");
+        check("00:32:00.999999", "00:32:00.999999")?;
+        assert!("07:32:00".parse::<Time>().is_ok());
+        assert!(reason("07:32:00Z").contains("cannot have an offset"));
+        assert!(reason("07:32:00-07:00").contains("cannot have an offset"));
+        // seconds are required
+        assert!(reason("07:32").contains("`HH:MM:SS`"));
+        assert!(reason("7:32:00").contains("`HH:MM:SS`"));
+        Ok(())
+    }
+
+    #[test]
+    fn fractional_seconds() -> Result<(), String> {
+        // precision beyond nanoseconds is kept, and truncated when converted
+        let dt = check(
+            "1979-05-27T00:32:00.123456789999",
+            "1979-05-27T00:32:00.123456789999",
+        )?;
+        assert_eq!(dt.time.map(|time| time.nanosecond()), Some(123_456_789));
+        let dt = check("07:32:00.5", "07:32:00.5")?;
+        assert_eq!(dt.time.map(|time| time.nanosecond()), Some(500_000_000));
+        assert!(reason("07:32:00.").contains("followed by a digit"));
+        assert!(reason("07:32:00.Z").contains("followed by a digit"));
+        Ok(())
+    }
+
+    #[test]
+    fn ranges() -> Result<(), String> {
+        assert!(reason("1979-00-27").contains("month"));
+        assert!(reason("1979-13-27").contains("month"));
+        assert!(reason("1979-05-00").contains("day"));
+        assert!(reason("1979-05-32").contains("day"));
+        assert!(reason("1979-04-31").contains("day"));
+        assert!(reason("1979-02-30").contains("day"));
+        // leap years
+        assert!(reason("1979-02-29").contains("leap years"));
+        assert!(reason("1900-02-29").contains("leap years"));
+        check("2000-02-29", "2000-02-29")?;
+        check("2024-02-29", "2024-02-29")?;
+
+        assert!(reason("24:00:00").contains("hour"));
+        assert!(reason("23:60:00").contains("minute"));
+        assert!(reason("23:59:61").contains("second"));
+        // leap seconds
+        check("1990-12-31T23:59:60Z", "1990-12-31T23:59:60Z")?;
+        check("1990-12-31T15:59:60-08:00", "1990-12-31T15:59:60-08:00")?;
+        check("1990-12-31T05:29:60+05:30", "1990-12-31T05:29:60+05:30")?;
+        check("1990-12-31T18:14:60-05:45", "1990-12-31T18:14:60-05:45")?;
+        check("1990-12-31T23:59:60-00:00", "1990-12-31T23:59:60-00:00")?;
+        assert!(reason("23:58:60").contains("leap second"));
+        assert!(reason("1990-12-31T05:59:60+05:30").contains("leap second"));
+        assert!(reason("1990-12-31T23:59:60.5+00:01").contains("leap second"));
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Conversions between [`Datetime`] and the types of the `chrono` crate.
+
+// third-party imports
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+// my imports
+use super::{Date, Datetime, Offset, Time};
+
+fn to_naive_date(date: &Date) -> Result<NaiveDate, String> {
+    NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
+        .ok_or_else(|| format!("`{}` is not a valid date.", date))
+}
+
+fn to_naive_time(time: &Time) -> Result<NaiveTime, String> {
+    // chrono represents a leap second as an extra second's worth of nanoseconds.
+    let (second, nanosecond) = match time.second {
+        60 => (59, time.nanosecond() + 1_000_000_000),
+        second => (second, time.nanosecond()),
+    };
+    NaiveTime::from_hms_nano_opt(
+        time.hour.into(),
+        time.minute.into(),
+        second.into(),
+        nanosecond,
+    )
+    .ok_or_else(|| format!("`{}` is not a valid time.", time))
+}
+
+fn from_naive_date(date: NaiveDate) -> Result<Date, String> {
+    match u16::try_from(date.year()) {
+        Ok(year) if year <= 9999 => Ok(Date {
+            year,
+            month: date.month() as u8,
+            day: date.day() as u8,
+        }),
+        _ => Err(format!("The year of `{}` is outside 0000 to 9999.", date)),
+    }
+}
+
+fn from_naive_time(time: NaiveTime) -> Time {
+    Time::from_nanos(
+        time.hour() as u8,
+        time.minute() as u8,
+        time.second() as u8,
+        time.nanosecond(),
+    )
+}
+
+impl TryFrom<&Datetime> for NaiveDate {
+    type Error = String;
+    fn try_from(dt: &Datetime) -> Result<Self, String> {
+        match dt {
+            Datetime {
+                date: Some(date),
+                time: None,
+                offset: None,
+            } => to_naive_date(date),
+            _ => Err(dt.not_a("a local date")),
+        }
+    }
+}
+impl TryFrom<&Datetime> for NaiveTime {
+    type Error = String;
+    fn try_from(dt: &Datetime) -> Result<Self, String> {
+        match dt {
+            Datetime {
+                date: None,
+                time: Some(time),
+                offset: None,
+            } => to_naive_time(time),
+            _ => Err(dt.not_a("a local time")),
+        }
+    }
+}
+impl TryFrom<&Datetime> for NaiveDateTime {
+    type Error = String;
+    fn try_from(dt: &Datetime) -> Result<Self, String> {
+        match dt {
+            Datetime {
+                date: Some(date),
+                time: Some(time),
+                offset: None,
+            } => Ok(to_naive_date(date)?.and_time(to_naive_time(time)?)),
+            _ => Err(dt.not_a("a local date-time")),
+        }
+    }
+}
+impl TryFrom<&Datetime> for DateTime<FixedOffset> {
+    type Error = String;
+    fn try_from(dt: &Datetime) -> Result<Self, String> {
+        let (date, time, offset) = match dt {
+            Datetime {
+                date: Some(date),
+                time: Some(time),
+                offset: Some(offset),
+            } => (date, time, *offset),
+            _ => return Err(dt.not_a("an offset date-time")),
+        };
+        let naive = to_naive_date(date)?.and_time(to_naive_time(time)?);
+        FixedOffset::east_opt(i32::from(offset.minutes()) * 60)
+            .and_then(|offset| naive.and_local_timezone(offset).single())
+            .ok_or_else(|| format!("`{}` is not a valid date-time.", dt))
+    }
+}
+
+impl TryFrom<NaiveDate> for Datetime {
+    type Error = String;
+    fn try_from(date: NaiveDate) -> Result<Self, String> {
+        Ok(Self {
+            date: Some(from_naive_date(date)?),
+            time: None,
+            offset: None,
+        })
+    }
+}
+impl From<NaiveTime> for Datetime {
+    fn from(time: NaiveTime) -> Self {
+        Self {
+            date: None,
+            time: Some(from_naive_time(time)),
+            offset: None,
+        }
+    }
+}
+impl TryFrom<NaiveDateTime> for Datetime {
+    type Error = String;
+    fn try_from(dt: NaiveDateTime) -> Result<Self, String> {
+        Ok(Self {
+            date: Some(from_naive_date(dt.date())?),
+            time: Some(from_naive_time(dt.time())),
+            offset: None,
+        })
+    }
+}
+impl TryFrom<DateTime<FixedOffset>> for Datetime {
+    type Error = String;
+    fn try_from(dt: DateTime<FixedOffset>) -> Result<Self, String> {
+        let minutes = (dt.offset().local_minus_utc() / 60) as i16;
+        Ok(Self {
+            offset: Some(Offset::Custom { minutes }),
+            ..Self::try_from(dt.naive_local())?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Datetime;
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+
+    #[test]
+    fn round_trip() -> Result<(), String> {
+        let dt: Datetime = "1979-05-27T00:32:00.999999-07:00".parse()?;
+        let chrono_dt = DateTime::<FixedOffset>::try_from(&dt)?;
+        assert_eq!(chrono_dt.to_rfc3339(), "1979-05-27T00:32:00.999999-07:00");
+        assert_eq!(Datetime::try_from(chrono_dt)?, dt);
+
+        let date: Datetime = "1979-05-27".parse()?;
+        assert!(NaiveTime::try_from(&date).is_err());
+        assert_eq!(Datetime::try_from(NaiveDate::try_from(&date)?)?, date);
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+//! `serde` support for [`Datetime`], [`Date`], and [`Time`], which are written as their TOML
+//! (RFC 3339) strings.
+
+// stdlib imports
+use std::fmt::Display;
+use std::str::FromStr;
+// third-party imports
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+// my imports
+use super::{Date, Datetime, Time};
+
+fn serialize<T: Display, S: Serializer>(val: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(val)
+}
+
+fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr<Err = String>,
+    D: Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(D::Error::custom)
+}
+
+impl Serialize for Datetime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self, serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Datetime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self, serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer)
+    }
+}
+
+impl Serialize for Time {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self, serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Time {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Date, Datetime, Time};
+    use serde_test::{assert_de_tokens_error, assert_tokens, Token};
+
+    #[test]
+    fn round_trip() -> Result<(), String> {
+        let dt: Datetime = "1979-05-27T00:32:00.999999-07:00".parse()?;
+        assert_tokens(&dt, &[Token::Str("1979-05-27T00:32:00.999999-07:00")]);
+        let date: Date = "1979-05-27".parse()?;
+        assert_tokens(&date, &[Token::Str("1979-05-27")]);
+        let time: Time = "07:32:00".parse()?;
+        assert_tokens(&time, &[Token::Str("07:32:00")]);
+
+        assert_de_tokens_error::<Date>(
+            &[Token::Str("1979-13-27")],
+            &"1979-13-27".parse::<Date>().unwrap_err(),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Conversions between [`Datetime`] and the types of the `time` crate.
+
+// third-party imports
+use time::{Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+// my imports
+use super::{Date, Datetime, Offset, Time};
+
+fn to_date(date: &Date) -> Result<time::Date, String> {
+    Month::try_from(date.month)
+        .and_then(|month| time::Date::from_calendar_date(date.year.into(), month, date.day))
+        .map_err(|err| format!("`{}` is not a valid date: {}", date, err))
+}
+
+fn to_time(time: &Time) -> Result<time::Time, String> {
+    time::Time::from_hms_nano(time.hour, time.minute, time.second, time.nanosecond())
+        .map_err(|err| format!("`{}` is not a valid time: {}", time, err))
+}
+
+fn from_date(date: time::Date) -> Result<Date, String> {
+    match u16::try_from(date.year()) {
+        Ok(year) if year <= 9999 => Ok(Date {
+            year,
+            month: date.month().into(),
+            day: date.day(),
+        }),
+        _ => Err(format!("The year of `{}` is outside 0000 to 9999.", date)),
+    }
+}
+
+fn from_time(time: time::Time) -> Time {
+    Time::from_nanos(time.hour(), time.minute(), time.second(), time.nanosecond())
+}
+
+impl TryFrom<&Datetime> for time::Date {
+    type Error = String;
+    fn try_from(dt: &Datetime) -> Result<Self, String> {
+        match dt {
+            Datetime {
+                date: Some(date),
+                time: None,
+                offset: None,
+            } => to_date(date),
+            _ => Err(dt.not_a("a local date")),
+        }
+    }
+}
+impl TryFrom<&Datetime> for time::Time {
+    type Error = String;
+    fn try_from(dt: &Datetime) -> Result<Self, String> {
+        match dt {
+            Datetime {
+                date: None,
+                time: Some(time),
+                offset: None,
+            } => to_time(time),
+            _ => Err(dt.not_a("a local time")),
+        }
+    }
+}
+impl TryFrom<&Datetime> for PrimitiveDateTime {
+    type Error = String;
+    fn try_from(dt: &Datetime) -> Result<Self, String> {
+        match dt {
+            Datetime {
+                date: Some(date),
+                time: Some(time),
+                offset: None,
+            } => Ok(PrimitiveDateTime::new(to_date(date)?, to_time(time)?)),
+            _ => Err(dt.not_a("a local date-time")),
+        }
+    }
+}
+impl TryFrom<&Datetime> for OffsetDateTime {
+    type Error = String;
+    fn try_from(dt: &Datetime) -> Result<Self, String> {
+        let (date, time, offset) = match dt {
+            Datetime {
+                date: Some(date),
+                time: Some(time),
+                offset: Some(offset),
+            } => (date, time, *offset),
+            _ => return Err(dt.not_a("an offset date-time")),
+        };
+        let offset = UtcOffset::from_whole_seconds(i32::from(offset.minutes()) * 60)
+            .map_err(|err| format!("`{}` has an invalid offset: {}", dt, err))?;
+        Ok(PrimitiveDateTime::new(to_date(date)?, to_time(time)?).assume_offset(offset))
+    }
+}
+
+impl TryFrom<time::Date> for Datetime {
+    type Error = String;
+    fn try_from(date: time::Date) -> Result<Self, String> {
+        Ok(Self {
+            date: Some(from_date(date)?),
+            time: None,
+            offset: None,
+        })
+    }
+}
+impl From<time::Time> for Datetime {
+    fn from(time: time::Time) -> Self {
+        Self {
+            date: None,
+            time: Some(from_time(time)),
+            offset: None,
+        }
+    }
+}
+impl TryFrom<PrimitiveDateTime> for Datetime {
+    type Error = String;
+    fn try_from(dt: PrimitiveDateTime) -> Result<Self, String> {
+        Ok(Self {
+            date: Some(from_date(dt.date())?),
+            time: Some(from_time(dt.time())),
+            offset: None,
+        })
+    }
+}
+impl TryFrom<OffsetDateTime> for Datetime {
+    type Error = String;
+    fn try_from(dt: OffsetDateTime) -> Result<Self, String> {
+        let minutes = (dt.offset().whole_seconds() / 60) as i16;
+        Ok(Self {
+            offset: Some(Offset::Custom { minutes }),
+            ..Self::try_from(PrimitiveDateTime::new(dt.date(), dt.time()))?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Datetime, Time};
+    use time::OffsetDateTime;
+
+    #[test]
+    fn round_trip() -> Result<(), String> {
+        let dt: Datetime = "1979-05-27T00:32:00.999999-07:00".parse()?;
+        let time_dt = OffsetDateTime::try_from(&dt)?;
+        assert_eq!(time_dt.unix_timestamp(), 296_638_320);
+        assert_eq!(Datetime::try_from(time_dt)?, dt);
+
+        // the `time` crate has no leap seconds
+        let leap = Datetime {
+            date: None,
+            time: Some(Time {
+                hour: 23,
+                minute: 59,
+                second: 60,
+                fraction: None,
+            }),
+            offset: None,
+        };
+        assert!(time::Time::try_from(&leap).is_err());
+        Ok(())
+    }
+}
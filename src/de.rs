@@ -0,0 +1,372 @@
+//! A `serde` [`Deserializer`] over parsed documents, for filling in Rust types such as the
+//! structs written by `tomlp codegen`.
+//!
+//! Datetimes are handed to types as their TOML strings, so [`Datetime`](crate::Datetime),
+//! [`Date`](crate::Date), and [`Time`](crate::Time) fields read them back exactly. Missing keys
+//! are `None` for `Option` fields, and strings fill unit enum variants.
+
+// stdlib imports
+use std::fmt;
+// third-party imports
+use serde::de::value::{BorrowedStrDeserializer, StrDeserializer};
+use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess};
+use serde::de::{Deserialize, Visitor};
+use serde::forward_to_deserialize_any;
+// my imports
+use crate::parsedtoml::ParsedTOML;
+use crate::span::dotted_key;
+use crate::tomltypes::{TOMLTable, TOMLType};
+
+/// Deserializes a `T` from a table.
+///
+/// ```
+/// #[derive(serde::Deserialize)]
+/// struct Package {
+///     name: String,
+///     version: Option<String>,
+///     released: tomlp::Date,
+/// }
+///
+/// let mut table = tomlp::TOMLTable::new();
+/// table.insert("name".to_string(), tomlp::TOMLType::BasicStr("web".to_string()));
+/// let released = tomlp::TOMLType::Datetime("2024-05-01".parse()?);
+/// table.insert("released".to_string(), released);
+/// let package: Package = tomlp::from_table(&table)?;
+/// assert_eq!(package.name, "web");
+/// assert_eq!(package.version, None);
+/// assert_eq!(package.released.to_string(), "2024-05-01");
+/// # Ok::<(), String>(())
+/// ```
+pub fn from_table<'de, T: Deserialize<'de>>(table: &'de TOMLTable) -> Result<T, String> {
+    T::deserialize(Node {
+        val: Val::Table(table),
+        path: &[],
+    })
+    .map_err(|err| err.to_string())
+}
+
+impl ParsedTOML {
+    /// Deserializes a `T` from the whole document, as with [`from_table`](crate::from_table).
+    ///
+    /// ```
+    /// #[derive(serde::Deserialize)]
+    /// struct Config {
+    ///     name: String,
+    ///     ports: Vec<u16>,
+    /// }
+    ///
+    /// let config: Config = tomlp::parse_str("name = 'web'\nports = [80, 443]")?.deserialize()?;
+    /// assert_eq!(config.ports, [80, 443]);
+    ///
+    /// let err = tomlp::parse_str("name = 'web'\nports = [80, -1]")?
+    ///     .deserialize::<Config>()
+    ///     .err();
+    /// assert_eq!(
+    ///     err.as_deref(),
+    ///     Some("`ports.1`: invalid value: integer `-1`, expected u16.")
+    /// );
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T, String> {
+        from_table(self.table())
+    }
+}
+
+/// A failure to deserialize, and the path of the value it happened at.
+#[derive(Debug)]
+struct Error {
+    message: String,
+    path: Option<Vec<String>>,
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            path: None,
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) if !path.is_empty() => {
+                write!(f, "`{}`: {}.", dotted_key(path), self.message)
+            }
+            _ => write!(f, "{}.", self.message),
+        }
+    }
+}
+
+/// A value or a table of an array of tables.
+#[derive(Clone, Copy)]
+enum Val<'de> {
+    Value(&'de TOMLType),
+    Table(&'de TOMLTable),
+}
+
+/// A value to deserialize, and where it is in the document.
+#[derive(Clone, Copy)]
+struct Node<'de, 'p> {
+    val: Val<'de>,
+    path: &'p [String],
+}
+
+impl Node<'_, '_> {
+    /// Marks `err` as having happened here, unless it happened within this value.
+    fn locate(&self, mut err: Error) -> Error {
+        if err.path.is_none() {
+            err.path = Some(self.path.to_vec());
+        }
+        err
+    }
+}
+
+impl<'de> Deserializer<'de> for Node<'de, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let val = match self.val {
+            Val::Value(val) => val,
+            Val::Table(table) => {
+                return visitor
+                    .visit_map(Entries::new(table, self.path))
+                    .map_err(|err| self.locate(err))
+            }
+        };
+        match val {
+            TOMLType::Bool(b) => visitor.visit_bool(*b),
+            TOMLType::Int(n) => visitor.visit_i64(*n),
+            // `u64` fields don't take `i128`s
+            TOMLType::WideInt(n) => match u64::try_from(*n) {
+                Ok(n) => visitor.visit_u64(n),
+                Err(_) => visitor.visit_i128(*n),
+            },
+            TOMLType::Float(n) => visitor.visit_f64(*n),
+            TOMLType::BasicStr(s)
+            | TOMLType::MultiStr(s)
+            | TOMLType::LitStr(s)
+            | TOMLType::MultiLitStr(s) => visitor.visit_borrowed_str(s),
+            TOMLType::Datetime(dt) => visitor.visit_string(dt.to_string()),
+            TOMLType::Array(arr) => visitor.visit_seq(Elements {
+                vals: arr.iter().map(Val::Value).collect(),
+                index: 0,
+                path: self.path,
+            }),
+            TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table) => {
+                visitor.visit_map(Entries::new(table, self.path))
+            }
+            TOMLType::AoT(tables) => visitor.visit_seq(Elements {
+                vals: tables.iter().map(Val::Table).collect(),
+                index: 0,
+                path: self.path,
+            }),
+        }
+        .map_err(|err| self.locate(err))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.val {
+            Val::Value(val) if val.str().is_some() => {
+                let variant: StrDeserializer<Error> = val.str().unwrap_or("").into_deserializer();
+                visitor.visit_enum(variant).map_err(|err| self.locate(err))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// The elements of an array or an array of tables.
+struct Elements<'de, 'p> {
+    vals: Vec<Val<'de>>,
+    index: usize,
+    path: &'p [String],
+}
+
+impl<'de> SeqAccess<'de> for Elements<'de, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        let Some(&val) = self.vals.get(self.index) else {
+            return Ok(None);
+        };
+        let mut path = self.path.to_vec();
+        path.push(self.index.to_string());
+        self.index += 1;
+        seed.deserialize(Node { val, path: &path }).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.vals.len() - self.index)
+    }
+}
+
+/// The keys and values of a table, in order of their keys.
+struct Entries<'de, 'p> {
+    entries: Vec<(&'de String, &'de TOMLType)>,
+    index: usize,
+    path: &'p [String],
+}
+
+impl<'de, 'p> Entries<'de, 'p> {
+    fn new(table: &'de TOMLTable, path: &'p [String]) -> Self {
+        let mut entries: Vec<_> = table.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        Self {
+            entries,
+            index: 0,
+            path,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for Entries<'de, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((key, _)) = self.entries.get(self.index) else {
+            return Ok(None);
+        };
+        let key: BorrowedStrDeserializer<Error> = BorrowedStrDeserializer::new(key);
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (key, val) = self.entries[self.index];
+        self.index += 1;
+        let mut path = self.path.to_vec();
+        path.push(key.clone());
+        seed.deserialize(Node {
+            val: Val::Value(val),
+            path: &path,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len() - self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Debug,
+        Info,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Target {
+        path: String,
+        test: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Config {
+        #[serde(rename = "odd-key")]
+        odd_key: f64,
+        wide: u64,
+        level: Level,
+        when: crate::Datetime,
+        text: Option<String>,
+        bin: Vec<Target>,
+        limits: HashMap<String, i32>,
+        mixed: Vec<crate::TOMLType>,
+    }
+
+    #[test]
+    fn types() -> Result<(), String> {
+        let source = "\
+'odd-key' = 1
+wide = 18446744073709551615
+level = 'info'
+when = 1979-05-27T07:32:00Z
+mixed = [1, 'a']
+limits.cpu = 2
+[[bin]]
+path = 'a'
+[[bin]]
+path = 'b'
+test = true
+";
+        let parsed = crate::ParseOptions::new()
+            .wide_integers(true)
+            .parse_str(source)
+            .map_err(|err| err.to_string())?;
+        let config: Config = parsed.deserialize()?;
+        assert_eq!(config.odd_key, 1.0);
+        assert_eq!(config.wide, u64::MAX);
+        assert_eq!(config.level, Level::Info);
+        assert_eq!(config.when.to_string(), "1979-05-27T07:32:00Z");
+        assert_eq!(config.text, None);
+        assert_eq!(config.bin[1].test, Some(true));
+        assert_eq!(config.limits["cpu"], 2);
+        assert_eq!(config.mixed[1].str(), Some("a"));
+        assert_ne!(config.level, Level::Debug);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() -> Result<(), String> {
+        let source = "odd-key = 1\nwide = 1\nlevel = 'info'\nwhen = 1979-05-27\nmixed = []\nlimits = {}\n[[bin]]\npath = 1\n";
+        let err = crate::parse_str(source)?
+            .deserialize::<Config>()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "`bin.0.path`: invalid type: integer `1`, expected a string."
+        );
+
+        let source = source
+            .replace("path = 1", "path = 'a'")
+            .replace("'info'", "'warn'");
+        let err = crate::parse_str(&source)?
+            .deserialize::<Config>()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "`level`: unknown variant `warn`, expected `debug` or `info`."
+        );
+
+        let err = crate::parse_str("wide = 1")?
+            .deserialize::<Config>()
+            .unwrap_err();
+        assert_eq!(err, "missing field `odd-key`.");
+        Ok(())
+    }
+}
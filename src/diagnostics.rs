@@ -0,0 +1,258 @@
+//! Checks for documents that are valid but likely contain a mistake.
+
+// stdlib imports
+use std::collections::{HashMap, HashSet};
+// my imports
+use super::error::{Warning, WarningKind};
+use super::lexer::{tokenize, TokenKind};
+use super::span::{child_path, SpanMap};
+use super::tomltypes::{TOMLTableRef, TOMLTypeRef};
+
+/// A key as it was written in the source.
+struct KeyUse<'t> {
+    key: &'t str,
+    parent: String, // `\0`-delimited path of the table the key belongs to
+    quoted: bool,
+    line: usize,
+    pos: usize, // byte offset, for ordering
+}
+
+/// Reports suspicious keys, indentation, and lines longer than `max_line_len` characters.
+/// `spans` must have been recorded while parsing `source` into `table`.
+pub(crate) fn check(
+    source: &str,
+    table: &TOMLTableRef<'_>,
+    spans: &SpanMap,
+    max_line_len: usize,
+) -> Vec<Warning> {
+    let mut uses = Vec::new();
+    collect_keys(source, table, "", spans, &mut uses);
+    uses.sort_by_key(|key_use| key_use.pos);
+
+    let mut warnings = check_keys(&uses);
+    warnings.extend(check_indentation(source));
+    warnings.extend(check_line_lengths(source, max_line_len));
+    warnings.sort_by_key(|warning| warning.line);
+    warnings
+}
+
+/// Gathers the keys of `table` and everything nested in it.
+fn collect_keys<'t>(
+    source: &str,
+    table: &'t TOMLTableRef<'_>,
+    path: &str,
+    spans: &SpanMap,
+    uses: &mut Vec<KeyUse<'t>>,
+) {
+    for (key, val) in table {
+        let child = child_path(path, key);
+        if let Some(span) = spans.get(&child).and_then(|located| located.key.as_ref()) {
+            let raw = source.get(span.bytes.clone()).unwrap_or_default();
+            uses.push(KeyUse {
+                key,
+                parent: path.to_string(),
+                quoted: raw.starts_with(['"', '\'']),
+                line: span.line,
+                pos: span.bytes.start,
+            });
+        }
+        collect_value(source, val, &child, spans, uses);
+    }
+}
+
+fn collect_value<'t>(
+    source: &str,
+    val: &'t TOMLTypeRef<'_>,
+    path: &str,
+    spans: &SpanMap,
+    uses: &mut Vec<KeyUse<'t>>,
+) {
+    match val {
+        TOMLTypeRef::HTable(table)
+        | TOMLTypeRef::DKTable(table)
+        | TOMLTypeRef::InlineTable(table) => collect_keys(source, table, path, spans, uses),
+        TOMLTypeRef::AoT(tables) => {
+            for (i, table) in tables.iter().enumerate() {
+                collect_keys(
+                    source,
+                    table,
+                    &child_path(path, &i.to_string()),
+                    spans,
+                    uses,
+                );
+            }
+        }
+        TOMLTypeRef::Array(vals) => {
+            for (i, val) in vals.iter().enumerate() {
+                collect_value(source, val, &child_path(path, &i.to_string()), spans, uses);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Empty keys, keys differing from a sibling only by case, and keys that are quoted in one
+/// place but bare in another.
+fn check_keys(uses: &[KeyUse<'_>]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let mut siblings: HashMap<(&str, String), &KeyUse<'_>> = HashMap::new();
+    let mut styles: HashMap<&str, &KeyUse<'_>> = HashMap::new();
+    let mut restyled: HashSet<&str> = HashSet::new(); // only the first inconsistency is reported
+    for key_use in uses {
+        if key_use.key.is_empty() {
+            let message = "Empty key `\"\"`.".to_string();
+            warnings.push(Warning::new(WarningKind::EmptyKey, key_use.line, message));
+        }
+
+        let folded = (key_use.parent.as_str(), key_use.key.to_lowercase());
+        let first = *siblings.entry(folded).or_insert(key_use);
+        if first.key != key_use.key {
+            let message = format!(
+                "Key `{}` differs from `{}` (line {}) only by case.",
+                key_use.key, first.key, first.line
+            );
+            warnings.push(Warning::new(WarningKind::KeyCase, key_use.line, message));
+        }
+
+        let first = *styles.entry(key_use.key).or_insert(key_use);
+        if first.quoted != key_use.quoted && restyled.insert(key_use.key) {
+            let (this, that) = if key_use.quoted {
+                ("quoted", "bare")
+            } else {
+                ("bare", "quoted")
+            };
+            let message = format!(
+                "Key `{}` is {} here but {} on line {}.",
+                key_use.key, this, that, first.line
+            );
+            warnings.push(Warning::new(WarningKind::KeyQuoting, key_use.line, message));
+        }
+    }
+    warnings
+}
+
+/// Lines indented with both tabs and spaces, or with a different character than the first
+/// indented line.
+fn check_indentation(source: &str) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let mut first: Option<(char, usize)> = None;
+    let mut line_start = true;
+    let mut tokens = tokenize(source).flatten().peekable();
+    while let Some(tok) = tokens.next() {
+        let at_line_start = std::mem::replace(&mut line_start, tok.kind == TokenKind::Newline);
+        if !at_line_start || tok.kind != TokenKind::Whitespace {
+            continue;
+        }
+        // whitespace-only lines aren't indentation
+        if tokens
+            .peek()
+            .is_none_or(|next| next.kind == TokenKind::Newline)
+        {
+            continue;
+        }
+        let indent = tok.text(source);
+        let message = if indent.contains(' ') && indent.contains('\t') {
+            "Indentation mixes tabs and spaces.".to_string()
+        } else {
+            let (name, other) = if indent.contains('\t') {
+                ("tabs", "spaces")
+            } else {
+                ("spaces", "tabs")
+            };
+            let ch = indent.chars().next().unwrap_or(' ');
+            match first {
+                None => {
+                    first = Some((ch, tok.line));
+                    continue;
+                }
+                Some((first_ch, first_line)) if first_ch != ch => format!(
+                    "Indented with {}, while line {} is indented with {}.",
+                    name, first_line, other
+                ),
+                Some(_) => continue,
+            }
+        };
+        warnings.push(Warning::new(
+            WarningKind::MixedIndentation,
+            tok.line,
+            message,
+        ));
+    }
+    warnings
+}
+
+fn check_line_lengths(source: &str, max_line_len: usize) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let len = line.chars().count();
+        if len > max_line_len {
+            let message = format!(
+                "Line is {} characters long, more than {}.",
+                len, max_line_len
+            );
+            warnings.push(Warning::new(WarningKind::LongLine, i + 1, message));
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ParseError, ParseOptions, WarningKind};
+
+    fn warnings(source: &str) -> Result<Vec<(WarningKind, usize)>, ParseError> {
+        let parsed = ParseOptions::new()
+            .diagnostics(true)
+            .max_line_length(40)
+            .parse_str(source)?;
+        Ok(parsed
+            .warnings()
+            .iter()
+            .map(|warning| (warning.kind, warning.line))
+            .collect())
+    }
+
+    #[test]
+    fn keys() -> Result<(), ParseError> {
+        use WarningKind::*;
+        assert_eq!(
+            warnings("name = 1\nName = 2\nNAME.x = 3")?,
+            [(KeyCase, 2), (KeyCase, 3)]
+        );
+        assert_eq!(warnings("[a]\nName = 1\n[b]\nname = 2")?, []);
+        assert_eq!(
+            warnings("[a]\nport = 1\n[b]\n\"port\" = 2\n[c]\n'port' = 3\n[d]\nport = 4")?,
+            [(KeyQuoting, 4)]
+        );
+        assert_eq!(
+            warnings("x = { \"\" = 1 }\n[\"a b\"]\n[c]\n'a b' = 2")?,
+            [(EmptyKey, 1)]
+        );
+
+        let parsed = ParseOptions::new()
+            .diagnostics(true)
+            .parse_str("a = 1\nA = 2")?;
+        assert_eq!(
+            parsed.warnings()[0].to_string(),
+            "Line 2: Key `A` differs from `a` (line 1) only by case."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn layout() -> Result<(), ParseError> {
+        use WarningKind::*;
+        let source = "a = [\n    1,\n\t2,\n \t3,\n]\n  \t\ns = \"\"\"\n\t\ttext\"\"\"\n";
+        assert_eq!(
+            warnings(source)?,
+            [(MixedIndentation, 3), (MixedIndentation, 4)]
+        );
+        let long = format!("key = '{}'\nshort = 1", "x".repeat(40));
+        assert_eq!(warnings(&long)?, [(LongLine, 1)]);
+
+        let quiet = ParseOptions::new().parse_str("a = 1\n\tA = 2\n")?;
+        assert!(quiet.warnings().is_empty());
+        assert!(quiet.span_of("a").is_none());
+        Ok(())
+    }
+}
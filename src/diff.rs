@@ -0,0 +1,537 @@
+//! Structural differences between two documents, ignoring order and formatting.
+//!
+//! ```
+//! use tomlp::diff::{self, ChangeKind, DiffOptions};
+//!
+//! let old = tomlp::parse_str("name = 'app'\nport = 80\n[[bin]]\nname = 'a'\n[[bin]]\nname = 'b'\n")?;
+//! let new = tomlp::parse_str("port = '80'\nname = \"app\"\n[[bin]]\nname = 'b'\n")?;
+//! let options = DiffOptions {
+//!     aot_key: Some("name".to_string()),
+//! };
+//! let changes = diff::compare(old.table(), new.table(), &options);
+//! assert_eq!(changes.len(), 2);
+//! assert_eq!(changes[0].to_string(), "- bin.0 = { name = \"a\" }");
+//! assert_eq!(changes[1].kind, ChangeKind::TypeChanged);
+//! assert_eq!(changes[1].to_string(), "~ port: 80 (integer) -> \"80\" (string)");
+//! # Ok::<(), String>(())
+//! ```
+
+// stdlib imports
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+// my imports
+use crate::json::Json;
+use crate::lexer::quote_basic;
+use crate::span::dotted_key;
+use crate::tomltypes::{TOMLTable, TOMLType};
+
+/// Settings for [`compare`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DiffOptions {
+    /// A key (ex. `name`) whose value identifies each table of an array of tables, so that
+    /// tables are matched up by it rather than by position. Arrays where some table lacks
+    /// the key, or two tables share a value of it, are still matched up by position.
+    pub aot_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// A key or array element only in the new document
+    Added,
+    /// A key or array element only in the old document
+    Removed,
+    /// A value that differs, but is of the same type
+    Changed,
+    /// A value of a different type (ex. an integer that became a string)
+    TypeChanged,
+}
+
+impl ChangeKind {
+    /// The name of the kind in JSON output (ex. `type-changed`).
+    pub fn name(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+            ChangeKind::TypeChanged => "type-changed",
+        }
+    }
+}
+
+/// A value within one of the documents.
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'a> {
+    /// The value of a key, or an element of an array
+    Value(&'a TOMLType),
+    /// A table of an array of tables
+    Table(&'a TOMLTable),
+}
+
+impl<'a> Node<'a> {
+    /// The type of the value, as far as changes go, since strings and tables of any kind
+    /// are alike: `string`, `integer`, `float`, `boolean`, `datetime`, `array`, or `table`.
+    pub fn type_name(self) -> &'static str {
+        let val = match self {
+            Node::Value(val) => val,
+            Node::Table(_) => return "table",
+        };
+        match val {
+            TOMLType::Bool(_) => "boolean",
+            TOMLType::Int(_) | TOMLType::WideInt(_) => "integer",
+            TOMLType::Float(_) => "float",
+            TOMLType::BasicStr(_)
+            | TOMLType::MultiStr(_)
+            | TOMLType::LitStr(_)
+            | TOMLType::MultiLitStr(_) => "string",
+            TOMLType::Datetime(_) => "datetime",
+            TOMLType::Array(_) | TOMLType::AoT(_) => "array",
+            TOMLType::HTable(_) | TOMLType::DKTable(_) | TOMLType::InlineTable(_) => "table",
+        }
+    }
+
+    /// The node as a table, if it's a table of any kind.
+    pub(crate) fn table(self) -> Option<&'a TOMLTable> {
+        match self {
+            Node::Value(
+                TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table),
+            )
+            | Node::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    /// The elements of the node, if it's an array of any kind.
+    pub(crate) fn elements(self) -> Option<Vec<Node<'a>>> {
+        match self {
+            Node::Value(TOMLType::Array(items)) => Some(items.iter().map(Node::Value).collect()),
+            Node::Value(TOMLType::AoT(tables)) => Some(tables.iter().map(Node::Table).collect()),
+            _ => None,
+        }
+    }
+
+    /// Whether two nodes are of the same type and equal.
+    fn same(self, other: Node<'_>) -> bool {
+        if self.type_name() != other.type_name() {
+            return false;
+        }
+        if let (Some(a), Some(b)) = (self.table(), other.table()) {
+            return a.len() == b.len()
+                && a.iter().all(|(key, val)| {
+                    b.get(key)
+                        .is_some_and(|other| Node::Value(val).same(Node::Value(other)))
+                });
+        }
+        if let (Some(a), Some(b)) = (self.elements(), other.elements()) {
+            return a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same(b));
+        }
+        let (Node::Value(a), Node::Value(b)) = (self, other) else {
+            return false;
+        };
+        match (a, b) {
+            (TOMLType::Bool(a), TOMLType::Bool(b)) => a == b,
+            (TOMLType::Float(a), TOMLType::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (TOMLType::Datetime(a), TOMLType::Datetime(b)) => a == b,
+            _ => match (a.str(), b.str()) {
+                (Some(a), Some(b)) => a == b,
+                _ => a.i128().is_some() && a.i128() == b.i128(),
+            },
+        }
+    }
+
+    fn to_json(self) -> Json {
+        if let Some(table) = self.table() {
+            let keys: BTreeSet<&String> = table.keys().collect();
+            return Json::Object(
+                keys.into_iter()
+                    .map(|key| (key.clone(), Node::Value(&table[key]).to_json()))
+                    .collect(),
+            );
+        }
+        if let Some(elements) = self.elements() {
+            return Json::Array(elements.into_iter().map(Node::to_json).collect());
+        }
+        match self {
+            Node::Value(TOMLType::Bool(b)) => Json::Bool(*b),
+            Node::Value(TOMLType::Float(f)) if f.is_finite() => Json::Number(*f),
+            Node::Value(TOMLType::Datetime(dt)) => Json::String(dt.to_string()),
+            Node::Value(val) => match (val.str(), val.i128()) {
+                (Some(s), _) => Json::String(s.to_string()),
+                (_, Some(n)) => Json::Integer(n),
+                // infinities and NaN, which JSON lacks
+                _ => Json::String(self.to_string()),
+            },
+            Node::Table(_) => unreachable!(),
+        }
+    }
+}
+
+/// Writes the node as an inline TOML value, with the keys of tables in sorted order.
+impl fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(table) = self.table() {
+            if table.is_empty() {
+                return write!(f, "{{}}");
+            }
+            let keys: BTreeSet<&String> = table.keys().collect();
+            let entries: Vec<String> = keys
+                .into_iter()
+                .map(|key| format!("{} = {}", dotted_key(&[key]), Node::Value(&table[key])))
+                .collect();
+            return write!(f, "{{ {} }}", entries.join(", "));
+        }
+        if let Some(elements) = self.elements() {
+            let elements: Vec<String> = elements.iter().map(Node::to_string).collect();
+            return write!(f, "[{}]", elements.join(", "));
+        }
+        let Node::Value(val) = self else {
+            unreachable!()
+        };
+        match val {
+            TOMLType::Bool(b) => write!(f, "{}", b),
+            TOMLType::Float(n) if n.is_nan() => write!(f, "nan"),
+            TOMLType::Float(n) if n.is_infinite() => {
+                write!(f, "{}", if *n > 0.0 { "inf" } else { "-inf" })
+            }
+            TOMLType::Float(n) => write!(f, "{:?}", n),
+            TOMLType::Datetime(dt) => write!(f, "{}", dt),
+            _ => match (val.str(), val.i128()) {
+                (Some(s), _) => write!(f, "{}", quote_basic(s)),
+                (_, Some(n)) => write!(f, "{}", n),
+                _ => unreachable!(),
+            },
+        }
+    }
+}
+
+/// A difference between the documents, at a key path.
+#[derive(Debug)]
+pub struct Change<'a> {
+    /// Written as a TOML key, with array elements addressed by their index (ex.
+    /// `bin.0.name`), as for [`ParsedTOML::span_of`](crate::ParsedTOML::span_of). The index is
+    /// the element's position in the new document, or in the old one if it was removed.
+    pub path: String,
+    pub kind: ChangeKind,
+    /// The value in the old document, unless the change is an addition
+    pub old: Option<Node<'a>>,
+    /// The value in the new document, unless the change is a removal
+    pub new: Option<Node<'a>>,
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.old, self.new) {
+            (None, Some(new)) => write!(f, "+ {} = {}", self.path, new),
+            (Some(old), None) => write!(f, "- {} = {}", self.path, old),
+            (Some(old), Some(new)) if self.kind == ChangeKind::TypeChanged => write!(
+                f,
+                "~ {}: {} ({}) -> {} ({})",
+                self.path,
+                old,
+                old.type_name(),
+                new,
+                new.type_name()
+            ),
+            (Some(old), Some(new)) => write!(f, "~ {}: {} -> {}", self.path, old, new),
+            (None, None) => write!(f, "~ {}", self.path),
+        }
+    }
+}
+
+/// Compares two tables (ex. two whole documents), returning the differences in order of
+/// their paths, with keys in sorted order.
+///
+/// Strings compare by their contents, however they're quoted, and tables compare by their
+/// keys, however they're defined. Arrays of tables (written either way) are compared table
+/// by table, while other arrays are compared as a whole.
+pub fn compare<'a>(
+    old: &'a TOMLTable,
+    new: &'a TOMLTable,
+    options: &DiffOptions,
+) -> Vec<Change<'a>> {
+    let mut differ = Differ {
+        options,
+        path: Vec::new(),
+        changes: Vec::new(),
+    };
+    differ.tables(old, new);
+    differ.changes
+}
+
+/// Writes changes as a JSON array with an object for each change, with the fields `path`,
+/// `change` (`added`, `removed`, `changed`, or `type-changed`), and `old` and `new` where
+/// they apply. Type changes also have `old_type` and `new_type`. Datetimes are written as
+/// strings.
+///
+/// ```
+/// use tomlp::diff::{self, DiffOptions};
+///
+/// let old = tomlp::parse_str("a = 1")?;
+/// let new = tomlp::parse_str("a = 2\nb = [true]")?;
+/// let changes = diff::compare(old.table(), new.table(), &DiffOptions::default());
+/// assert_eq!(
+///     diff::to_json(&changes),
+///     r#"[
+///   {
+///     "path": "a",
+///     "change": "changed",
+///     "old": 1,
+///     "new": 2
+///   },
+///   {
+///     "path": "b",
+///     "change": "added",
+///     "new": [true]
+///   }
+/// ]
+/// "#
+/// );
+/// # Ok::<(), String>(())
+/// ```
+pub fn to_json(changes: &[Change<'_>]) -> String {
+    let changes = changes
+        .iter()
+        .map(|change| {
+            let mut fields = vec![
+                ("path".to_string(), Json::String(change.path.clone())),
+                (
+                    "change".to_string(),
+                    Json::String(change.kind.name().to_string()),
+                ),
+            ];
+            if let Some(old) = change.old {
+                fields.push(("old".to_string(), old.to_json()));
+            }
+            if let Some(new) = change.new {
+                fields.push(("new".to_string(), new.to_json()));
+            }
+            if let (ChangeKind::TypeChanged, Some(old), Some(new)) =
+                (change.kind, change.old, change.new)
+            {
+                let type_name = |node: Node| Json::String(node.type_name().to_string());
+                fields.push(("old_type".to_string(), type_name(old)));
+                fields.push(("new_type".to_string(), type_name(new)));
+            }
+            Json::Object(fields)
+        })
+        .collect();
+    Json::Array(changes).pretty() + "\n"
+}
+
+/// Walks both documents at once, collecting changes.
+struct Differ<'a, 'o> {
+    options: &'o DiffOptions,
+    /// The keys and indices down to the current value
+    path: Vec<String>,
+    changes: Vec<Change<'a>>,
+}
+
+impl<'a> Differ<'a, '_> {
+    fn push(&mut self, kind: ChangeKind, old: Option<Node<'a>>, new: Option<Node<'a>>) {
+        self.changes.push(Change {
+            path: dotted_key(&self.path),
+            kind,
+            old,
+            new,
+        });
+    }
+
+    fn tables(&mut self, old: &'a TOMLTable, new: &'a TOMLTable) {
+        let keys: BTreeSet<&'a String> = old.keys().chain(new.keys()).collect();
+        for key in keys {
+            self.path.push(key.clone());
+            self.nodes(old.get(key).map(Node::Value), new.get(key).map(Node::Value));
+            self.path.pop();
+        }
+    }
+
+    fn nodes(&mut self, old: Option<Node<'a>>, new: Option<Node<'a>>) {
+        let (old, new) = match (old, new) {
+            (Some(old), Some(new)) => (old, new),
+            (None, None) => return,
+            (_, None) => return self.push(ChangeKind::Removed, old, new),
+            (None, _) => return self.push(ChangeKind::Added, old, new),
+        };
+        if old.type_name() != new.type_name() {
+            return self.push(ChangeKind::TypeChanged, Some(old), Some(new));
+        }
+        if let (Some(old), Some(new)) = (old.table(), new.table()) {
+            return self.tables(old, new);
+        }
+        if let (Some(old), Some(new)) = (tables_of(old), tables_of(new)) {
+            return self.aots(&old, &new);
+        }
+        if !old.same(new) {
+            self.push(ChangeKind::Changed, Some(old), Some(new));
+        }
+    }
+
+    /// Compares arrays of tables, table by table.
+    fn aots(&mut self, old: &[Node<'a>], new: &[Node<'a>]) {
+        // pairs of old and new indices
+        let pairs: Vec<(Option<usize>, Option<usize>)> = match (self.ids(old), self.ids(new)) {
+            (Some(old_ids), Some(new_ids)) => {
+                // removed tables first, since they're numbered as in the old document
+                let removed = (0..old.len()).filter(|i| !new_ids.contains(&old_ids[*i]));
+                let mut pairs: Vec<_> = removed.map(|i| (Some(i), None)).collect();
+                pairs.extend(
+                    new_ids
+                        .iter()
+                        .enumerate()
+                        .map(|(i, id)| (old_ids.iter().position(|old_id| old_id == id), Some(i))),
+                );
+                pairs
+            }
+            _ => (0..old.len().max(new.len()))
+                .map(|i| ((i < old.len()).then_some(i), (i < new.len()).then_some(i)))
+                .collect(),
+        };
+        for (o, n) in pairs {
+            self.path.push(n.or(o).unwrap_or_default().to_string());
+            self.nodes(o.map(|o| old[o]), n.map(|n| new[n]));
+            self.path.pop();
+        }
+    }
+
+    /// The values of [`DiffOptions::aot_key`] in `tables`, written out, if each table has
+    /// one that none of the others share.
+    fn ids(&self, tables: &[Node<'a>]) -> Option<Vec<String>> {
+        let key = self.options.aot_key.as_ref()?;
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+        for table in tables {
+            let id = Node::Value(table.table()?.get(key)?).to_string();
+            if !seen.insert(id.clone()) {
+                return None;
+            }
+            ids.push(id);
+        }
+        Some(ids)
+    }
+}
+
+/// The tables of an array of tables, or of a nonempty array of only inline tables.
+fn tables_of(node: Node<'_>) -> Option<Vec<Node<'_>>> {
+    let elements = node.elements()?;
+    let all_tables = elements.iter().all(|element| element.table().is_some());
+    (all_tables && !elements.is_empty()).then_some(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, to_json, ChangeKind, DiffOptions};
+
+    fn changes(old: &str, new: &str, options: &DiffOptions) -> Result<Vec<String>, String> {
+        let (old, new) = (crate::parse_str(old)?, crate::parse_str(new)?);
+        Ok(compare(old.table(), new.table(), options)
+            .iter()
+            .map(|change| change.to_string())
+            .collect())
+    }
+
+    #[test]
+    fn values() -> Result<(), String> {
+        let options = DiffOptions::default();
+        let old = "a = 'x'\nb = 1\nc.d = 1.5\n[e]\nf = [1, 2]\ng = 1979-05-27\nh = { i = true }\n";
+        let new = "a = \"\"\"x\"\"\"\nb = 2\nc = { d = 1.5 }\n[e]\nf = [1, 2, 3]\ng = '1979-05-27'\nh.j = nan\n'k l' = -inf\n";
+        assert_eq!(
+            changes(old, new, &options)?,
+            vec![
+                "~ b: 1 -> 2",
+                "~ e.f: [1, 2] -> [1, 2, 3]",
+                "~ e.g: 1979-05-27 (datetime) -> \"1979-05-27\" (string)",
+                "- e.h.i = true",
+                "+ e.h.j = nan",
+                "+ e.\"k l\" = -inf",
+            ]
+        );
+        assert!(changes(old, old, &options)?.is_empty());
+        assert!(changes("a = [{ b = 1 }]", "[[a]]\nb = 1", &options)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn aots() -> Result<(), String> {
+        let old = "[[bin]]\nname = 'a'\npath = 'a.rs'\n[[bin]]\nname = 'b'\n[[bin]]\nname = 'c'\n";
+        let new =
+            "[[bin]]\nname = 'c'\n[[bin]]\nname = 'a'\npath = 'src/a.rs'\n[[bin]]\nname = 'd'\n";
+        assert_eq!(
+            changes(old, new, &DiffOptions::default())?,
+            vec![
+                "~ bin.0.name: \"a\" -> \"c\"",
+                "- bin.0.path = \"a.rs\"",
+                "~ bin.1.name: \"b\" -> \"a\"",
+                "+ bin.1.path = \"src/a.rs\"",
+                "~ bin.2.name: \"c\" -> \"d\"",
+            ]
+        );
+        let options = DiffOptions {
+            aot_key: Some("name".to_string()),
+        };
+        assert_eq!(
+            changes(old, new, &options)?,
+            vec![
+                "- bin.1 = { name = \"b\" }",
+                "~ bin.1.path: \"a.rs\" -> \"src/a.rs\"",
+                "+ bin.2 = { name = \"d\" }",
+            ]
+        );
+        // a duplicate name means matching by position
+        let new = "[[bin]]\nname = 'a'\n[[bin]]\nname = 'a'\n";
+        assert_eq!(
+            changes(old, new, &options)?,
+            vec![
+                "- bin.0.path = \"a.rs\"",
+                "~ bin.1.name: \"b\" -> \"a\"",
+                "- bin.2 = { name = \"c\" }",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn json() -> Result<(), String> {
+        let old = crate::parse_str("a = 1\n[[b]]\nc = 1979-05-27T07:32:00Z")?;
+        let new = crate::parse_str("a = 'one'")?;
+        let changes = compare(old.table(), new.table(), &DiffOptions::default());
+        assert_eq!(changes[0].kind, ChangeKind::TypeChanged);
+        let expected = r#"[
+  {
+    "path": "a",
+    "change": "type-changed",
+    "old": 1,
+    "new": "one",
+    "old_type": "integer",
+    "new_type": "string"
+  },
+  {
+    "path": "b",
+    "change": "removed",
+    "old": [
+      {
+        "c": "1979-05-27T07:32:00Z"
+      }
+    ]
+  }
+]
+"#;
+        assert_eq!(to_json(&changes), expected);
+
+        // integers are exact, and floats keep their fraction
+        let options = crate::ParseOptions::new().wide_integers(true);
+        let old = options.parse_str(
+            "a = 9007199254740993\nb = 1.0\nc = 170141183460469231731687303715884105727",
+        )?;
+        let new = options.parse_str(
+            "a = 9007199254740992\nb = 2.5\nc = 170141183460469231731687303715884105726",
+        )?;
+        let changes = compare(old.table(), new.table(), &DiffOptions::default());
+        let json = to_json(&changes);
+        assert!(json.contains(r#""old": 9007199254740993,"#));
+        assert!(json.contains(r#""new": 9007199254740992"#));
+        assert!(json.contains(r#""old": 1.0,"#));
+        assert!(json.contains(r#""new": 2.5"#));
+        assert!(json.contains(r#""old": 170141183460469231731687303715884105727,"#));
+        assert!(json.contains(r#""new": 170141183460469231731687303715884105726"#));
+        Ok(())
+    }
+}
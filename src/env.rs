@@ -0,0 +1,298 @@
+//! Configuration from environment variables, to layer over configuration files.
+
+// stdlib imports
+use std::ffi::OsStr;
+// my imports
+use crate::merge::Layered;
+use crate::parsedtoml::ParsedTOML;
+use crate::tomlparse::TOMLParser;
+use crate::tomltypes::{TOMLTable, TOMLType};
+
+/// Settings for reading values from environment variables, where a variable such as
+/// `APP__SERVER__PORT=8080` sets the key `server.port`.
+///
+/// The rest of a variable's name after the prefix is split at each separator into keys,
+/// which are lowercased. Its value is read as a TOML value (ex. `8080`, `true`, `[1, 2]`,
+/// `1979-05-27`, or `"quoted"`), or as a string if it isn't one.
+///
+/// ```
+/// use tomlp::{EnvOptions, MergeOptions, ValFromTOMLKey};
+///
+/// let mut config = tomlp::parse_str("[server]\nhost = 'localhost'\nport = 80")?;
+/// let vars = [
+///     ("APP__SERVER__PORT", "8080"),
+///     ("APP__SERVER__TAGS", "['a', 'b']"),
+///     ("HOME", "/root"),
+/// ];
+/// let env = EnvOptions::new("APP").parse_vars(vars)?;
+/// config.merge(env, &MergeOptions::default());
+/// let get = |key| config.retrieve(key, ".");
+/// assert_eq!(get("server.port").and_then(|port| port.i64()), Some(8080));
+/// assert_eq!(get("server.tags").and_then(|tags| tags.array()).map(Vec::len), Some(2));
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvOptions {
+    /// The start of the names of the variables to read, which is followed by the separator
+    /// (ex. `APP`). If empty, every variable is read.
+    pub prefix: String,
+    /// What separates the keys within a variable's name. Defaults to `__`, so that single
+    /// underscores can be part of keys.
+    pub separator: String,
+}
+
+impl EnvOptions {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            separator: "__".to_string(),
+        }
+    }
+
+    /// Reads the variables of this process's environment into a document.
+    pub fn parse(&self) -> Result<ParsedTOML, String> {
+        self.parse_vars(std::env::vars_os())
+    }
+
+    /// Reads the given variables, as names and values, into a document. Fails if one
+    /// variable sets a key within another's (ex. `APP__SERVER` and `APP__SERVER__PORT`).
+    pub fn parse_vars<I, K, V>(&self, vars: I) -> Result<ParsedTOML, String>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        let mut table = TOMLTable::new();
+        // the names and keys of the variables so far
+        let mut set: Vec<(String, Vec<String>)> = Vec::new();
+        for var in self.overrides(vars)? {
+            let within =
+                |keys: &[String]| keys.starts_with(&var.keys) || var.keys.starts_with(keys);
+            if let Some((other, _)) = set.iter().find(|(_, keys)| within(keys)) {
+                return Err(format!(
+                    "Environment variables `{}` and `{}` set the same key.",
+                    other, var.name
+                ));
+            }
+            insert(&mut table, &var.keys, var.value);
+            set.push((var.name, var.keys));
+        }
+        Ok(ParsedTOML::from_table(table))
+    }
+
+    /// The variables that set keys, in order of their names.
+    fn overrides<I, K, V>(&self, vars: I) -> Result<Vec<Override>, String>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        if self.separator.is_empty() {
+            return Err("The separator of environment variables can't be empty.".to_string());
+        }
+        let mut overrides = Vec::new();
+        for (name, val) in vars {
+            let Some(name) = name.as_ref().to_str() else {
+                continue;
+            };
+            let rest = match self.prefix.as_str() {
+                "" => Some(name),
+                prefix => name
+                    .strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_prefix(self.separator.as_str())),
+            };
+            let Some(rest) = rest else {
+                continue;
+            };
+            let keys: Vec<String> = rest
+                .split(self.separator.as_str())
+                .map(str::to_lowercase)
+                .collect();
+            if keys.iter().any(String::is_empty) {
+                return Err(format!("Environment variable `{}` has an empty key.", name));
+            }
+            let val = val
+                .as_ref()
+                .to_str()
+                .ok_or_else(|| format!("Environment variable `{}` isn't valid UTF-8.", name))?;
+            overrides.push(Override {
+                name: name.to_string(),
+                keys,
+                value: parse_value(val),
+            });
+        }
+        overrides.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(overrides)
+    }
+}
+
+impl Layered {
+    /// Merges the variables of this process's environment over the layers so far, as a layer
+    /// for each variable, named after it, so that [`source_of`](Self::source_of) tells which
+    /// variable set a key.
+    ///
+    /// ```no_run
+    /// use tomlp::{EnvOptions, Layered, MergeOptions};
+    ///
+    /// let mut config = Layered::new(MergeOptions::default());
+    /// config
+    ///     .add_file("defaults.toml")?
+    ///     .add_file("local.toml")?
+    ///     .add_env(&EnvOptions::new("APP"))?;
+    /// // ex. "APP__SERVER__PORT"
+    /// println!("`server.port` is set by {:?}", config.source_of("server.port"));
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn add_env(&mut self, options: &EnvOptions) -> Result<&mut Self, String> {
+        self.add_vars(options, std::env::vars_os())
+    }
+
+    /// Merges the given variables over the layers so far, as for [`add_env`](Self::add_env).
+    pub fn add_vars<I, K, V>(&mut self, options: &EnvOptions, vars: I) -> Result<&mut Self, String>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        for var in options.overrides(vars)? {
+            let mut table = TOMLTable::new();
+            insert(&mut table, &var.keys, var.value);
+            self.add(&var.name, ParsedTOML::from_table(table));
+        }
+        Ok(self)
+    }
+}
+
+/// A variable that sets a key.
+struct Override {
+    name: String,
+    keys: Vec<String>,
+    value: TOMLType,
+}
+
+/// Reads `text` as a TOML value, or as a string if it isn't one.
+fn parse_value(text: &str) -> TOMLType {
+    let mut parser = TOMLParser::new(text);
+    match parser.parse_value() {
+        // nothing but whitespace and comments may follow
+        Ok(val) if parser.seek_nonws() == Ok(false) => val.into_owned(),
+        _ => TOMLType::BasicStr(text.to_string()),
+    }
+}
+
+/// Sets the value at `keys`, creating tables along the way as a dotted key would. Those
+/// tables must not already be set to other values.
+fn insert(table: &mut TOMLTable, keys: &[String], val: TOMLType) {
+    let Some((last, parents)) = keys.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for key in parents {
+        let next = table
+            .entry(key.clone())
+            .or_insert_with(|| TOMLType::DKTable(TOMLTable::new()));
+        table = match next {
+            TOMLType::DKTable(next) => next,
+            _ => unreachable!("a variable set a key within another's value"),
+        };
+    }
+    table.insert(last.clone(), val);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnvOptions;
+    use crate::{Layered, MergeOptions, TOMLType, ValFromTOMLKey};
+
+    #[test]
+    fn values() -> Result<(), String> {
+        let vars = [
+            ("APP__NAME", "web"),
+            ("APP__QUOTED", "'web'"),
+            ("APP__DEBUG", "true"),
+            ("APP__SERVER__PORT", "8080"),
+            ("APP__SERVER__HOSTS", "[\"a\", \"b\"] # two"),
+            ("APP__SERVER__MAX_CONNECTIONS", "1_000"),
+            ("APP__START", "1979-05-27T07:32:00Z"),
+            ("APP__LIMITS", "{ cpu = 2 }"),
+            ("APP__NOT_TOML", "1 2"),
+            ("APP__INJECTED", "1\n[other]"),
+            ("APPLE", "1"),
+            ("OTHER__APP__X", "1"),
+        ];
+        let parsed = EnvOptions::new("APP").parse_vars(vars)?;
+        let get = |key: &str| parsed.retrieve(key, ".");
+        assert_eq!(get("name").and_then(|val| val.str()), Some("web"));
+        assert!(matches!(get("quoted"), Some(TOMLType::LitStr(s)) if s == "web"));
+        assert!(matches!(get("debug"), Some(TOMLType::Bool(true))));
+        assert_eq!(get("server.port").and_then(|val| val.i64()), Some(8080));
+        assert_eq!(
+            get("server.hosts")
+                .and_then(|val| val.array())
+                .map(Vec::len),
+            Some(2)
+        );
+        assert_eq!(
+            get("server.max_connections").and_then(|val| val.i64()),
+            Some(1000)
+        );
+        assert!(get("start").and_then(|val| val.datetime()).is_some());
+        assert_eq!(get("limits.cpu").and_then(|val| val.i64()), Some(2));
+        assert_eq!(get("not_toml").and_then(|val| val.str()), Some("1 2"));
+        assert_eq!(
+            get("injected").and_then(|val| val.str()),
+            Some("1\n[other]")
+        );
+        assert_eq!(parsed.table().len(), 8);
+
+        let options = EnvOptions {
+            prefix: String::new(),
+            separator: "_".to_string(),
+        };
+        let parsed = options.parse_vars([("SERVER_PORT", "1")])?;
+        assert!(parsed.retrieve("server.port", ".").is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let options = EnvOptions::new("APP");
+        let err = options
+            .parse_vars([("APP__SERVER__PORT", "1"), ("APP__SERVER", "{}")])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "Environment variables `APP__SERVER` and `APP__SERVER__PORT` set the same key."
+        );
+        let err = options
+            .parse_vars([("APP__A", "1"), ("APP__a", "1")])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "Environment variables `APP__A` and `APP__a` set the same key."
+        );
+        let err = options.parse_vars([("APP____A", "1")]).unwrap_err();
+        assert_eq!(err, "Environment variable `APP____A` has an empty key.");
+    }
+
+    #[test]
+    fn layered() -> Result<(), String> {
+        let mut config = Layered::new(MergeOptions::default());
+        config
+            .add(
+                "defaults.toml",
+                crate::parse_str("[server]\nport = 80\nhost = 'a'")?,
+            )
+            .add_vars(
+                &EnvOptions::new("APP"),
+                [("APP__SERVER__PORT", "8080"), ("APP__SERVER", "'b'")],
+            )?;
+        assert_eq!(
+            config.layers(),
+            ["defaults.toml", "APP__SERVER", "APP__SERVER__PORT"]
+        );
+        assert_eq!(config.source_of("server.port"), Some("APP__SERVER__PORT"));
+        assert_eq!(config.conflicts().len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+/// The category of a [`ParseError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The document is not valid TOML.
+    Syntax,
+    /// The document exceeds one of the configured [`ParseLimits`](crate::ParseLimits).
+    LimitExceeded,
+    /// The file could not be opened or read.
+    Io,
+}
+
+/// An error produced while reading or parsing a TOML document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Further detail, such as why a value could not be read as each of the types it might
+    /// have been.
+    pub notes: Vec<Note>,
+}
+impl ParseError {
+    pub fn new(kind: ErrorKind, message: String) -> Self {
+        Self {
+            kind,
+            message,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_notes(mut self, notes: Vec<Note>) -> Self {
+        self.notes = notes;
+        self
+    }
+}
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        for note in &self.notes {
+            write!(f, "\n  {}", note)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for ParseError {}
+impl From<ParseError> for String {
+    fn from(err: ParseError) -> Self {
+        err.to_string()
+    }
+}
+
+/// A sub-diagnostic of a [`ParseError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    /// What the note is about (ex. `"integer"` for why a value is not a valid integer).
+    pub topic: &'static str,
+    pub message: String,
+}
+impl Note {
+    pub fn new(topic: &'static str, message: String) -> Self {
+        Self { topic, message }
+    }
+}
+impl std::fmt::Display for Note {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.topic, self.message)
+    }
+}
+
+/// Something accepted during parsing that a strict reading of the specification would not, or
+/// that is legal but likely a mistake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// The (1-based) line the warning refers to.
+    pub line: usize,
+    pub message: String,
+}
+impl Warning {
+    pub fn new(kind: WarningKind, line: usize, message: String) -> Self {
+        Self {
+            kind,
+            line,
+            message,
+        }
+    }
+}
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+/// The category of a [`Warning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// A newline or comment within an inline table, accepted in lenient mode.
+    InlineTableNewline,
+    /// A comma after the last key-value pair of an inline table, accepted in lenient mode.
+    InlineTableTrailingComma,
+    /// A boolean that is not lowercase (ex. `True`), accepted in lenient mode.
+    BooleanCase,
+    /// A key that differs from another key of the same table only by case.
+    KeyCase,
+    /// A key that is quoted in one place but bare in another (ex. `name` and `"name"`).
+    KeyQuoting,
+    /// The empty key `""`.
+    EmptyKey,
+    /// Indentation mixing tabs and spaces, within a line or between lines.
+    MixedIndentation,
+    /// A line longer than [`ParseOptions::max_line_length`](crate::ParseOptions::max_line_length).
+    LongLine,
+}
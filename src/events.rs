@@ -0,0 +1,283 @@
+//! Event-based parsing for documents too large to hold in memory.
+//!
+//! Input is read one top-level item (a table header or a key-value pair) at a time; only the
+//! item being parsed and the keys of the current table are kept.
+
+// stdlib imports
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+use std::io::BufRead;
+// my imports
+use super::lexer::{Checkpoint, TokenKind, Tokens};
+use super::options::ParseOptions;
+use super::tomlparse::{KeyVal, TOMLParser};
+use super::tomltypes::{TOMLTableRef, TOMLType, TOMLTypeRef};
+
+/// An item of a TOML document, in the order it appears in the source.
+#[derive(Debug)]
+pub enum Event {
+    /// `[path]`
+    TableHeader(Vec<String>),
+    /// `[[path]]`
+    ArrayOfTablesHeader(Vec<String>),
+    /// A key-value pair. The path includes the path of the table it belongs to.
+    KeyValue(Vec<String>, TOMLType),
+    /// A comment, without its leading `#`.
+    /// Comments are emitted after the item they appear in.
+    Comment(String),
+}
+
+/// Reads a TOML document as a sequence of [`Event`]s.
+///
+/// Keys are checked for redefinition within the current table, and `[table]` headers against
+/// previous headers. Conflicts between separate tables (ex. `[a]` defining `b = 1` followed by
+/// `[a.b]`) are not detected, as that would require keeping the whole document.
+/// Iteration ends after the first error.
+///
+/// ```no_run
+/// use std::{fs::File, io::BufReader};
+/// use tomlp::Event;
+///
+/// let reader = BufReader::new(File::open("dump.toml").map_err(|err| err.to_string())?);
+/// let mut records = 0;
+/// for event in tomlp::events(reader) {
+///     if let Event::ArrayOfTablesHeader(path) = event? {
+///         if path == ["record"] {
+///             records += 1;
+///         }
+///     }
+/// }
+/// println!("{} records", records);
+/// # Ok::<(), String>(())
+/// ```
+pub fn events<R: BufRead>(reader: R) -> Events<R> {
+    events_with(reader, ParseOptions::new())
+}
+
+pub(crate) fn events_with<R: BufRead>(reader: R, options: ParseOptions) -> Events<R> {
+    Events {
+        reader,
+        line: 1,
+        table: Vec::new(),
+        keys: TOMLTableRef::new(),
+        tables: HashSet::new(),
+        pending: VecDeque::new(),
+        done: false,
+        options,
+    }
+}
+
+/// An iterator over the [`Event`]s of a TOML document.
+///
+/// This `struct` is created by [`events`] and [`ParseOptions::events`].
+#[derive(Debug)]
+pub struct Events<R> {
+    reader: R,
+    line: usize,                 // Line number of the next line read
+    table: Vec<String>,          // Path of the current table
+    keys: TOMLTableRef<'static>, // Shapes of the current table's values, for catching redefinitions
+    tables: HashSet<String>,     // All `[table]` headers seen
+    pending: VecDeque<Event>,    // Events of the last item read
+    done: bool,
+    options: ParseOptions, // Settings each item is parsed with
+}
+impl<R: BufRead> Events<R> {
+    /// Reads lines until they form a complete item, then parses it.
+    fn read_item(&mut self) -> Result<(), String> {
+        let first_line = self.line;
+        let mut chunk = String::new();
+        let mut checkpoint = Checkpoint::default();
+        let mut comments = Vec::new();
+        loop {
+            match self.reader.read_line(&mut chunk) {
+                Ok(0) => self.done = true,
+                Ok(_) => self.line += 1,
+                Err(err) => {
+                    return Err(format!(
+                        "Line {}: File Read Error: {}",
+                        self.line,
+                        err.kind()
+                    ))
+                }
+            }
+            let incomplete = scan(&chunk, &mut checkpoint, &mut comments);
+            if self.done || !incomplete {
+                break;
+            }
+        }
+
+        let mut parser = self
+            .options
+            .configure(TOMLParser::new(&chunk))
+            .starting_at_line(first_line);
+        if !parser.seek_nonws()? {
+            // blank or comment-only line
+        } else if parser.at_table_header() {
+            let header = parser.parse_header()?;
+            let path: Vec<String> = header.path.into_iter().map(Cow::into_owned).collect();
+            if !header.is_aot && !self.tables.insert(path.join("\0")) {
+                return Err(format!(
+                    "Line {}: Table header `{}` is already defined.",
+                    header.line,
+                    path.join(".")
+                ));
+            }
+            self.table.clone_from(&path);
+            self.keys.clear();
+            self.pending.push_back(if header.is_aot {
+                Event::ArrayOfTablesHeader(path)
+            } else {
+                Event::TableHeader(path)
+            });
+        } else {
+            let line_num = parser.line_num();
+            let KeyVal(key, val) = parser.parse_keyval()?;
+            parser.process_eol()?;
+            let key: Vec<String> = key.into_iter().map(Cow::into_owned).collect();
+            let marker = KeyVal(
+                key.iter().cloned().map(Cow::Owned).collect(),
+                shape_of(&val),
+            );
+            if let Err(msg) = TOMLParser::insert(marker, &mut self.keys) {
+                return Err(format!("Line {}: {}", line_num, msg));
+            }
+            let mut path = self.table.clone();
+            path.extend(key);
+            self.pending
+                .push_back(Event::KeyValue(path, val.into_owned()));
+        }
+        self.pending
+            .extend(comments.into_iter().map(Event::Comment));
+        Ok(())
+    }
+}
+impl<R: BufRead> Iterator for Events<R> {
+    type Item = Result<Event, String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(msg) = self.read_item() {
+                self.done = true;
+                return Some(Err(msg));
+            }
+        }
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+/// Lexes the lines added to `chunk` since `checkpoint`, collecting their comments, and
+/// determines if `chunk` ends within a value. Lexing each line once keeps long values linear.
+fn scan(chunk: &str, checkpoint: &mut Checkpoint, comments: &mut Vec<String>) -> bool {
+    let mut tokens = Tokens::resume(chunk, std::mem::take(checkpoint));
+    for tok in tokens.by_ref().flatten() {
+        if tok.kind == TokenKind::Comment {
+            comments.push(tok.text(chunk)[1..].to_string());
+        }
+    }
+    let incomplete = tokens.is_incomplete();
+    *checkpoint = tokens.into_checkpoint();
+    incomplete
+}
+
+/// A stand-in for a value that only keeps what matters for detecting redefinitions: inline
+/// tables cannot be extended, and nothing can be added to any other value.
+fn shape_of(val: &TOMLTypeRef<'_>) -> TOMLTypeRef<'static> {
+    match val {
+        TOMLTypeRef::InlineTable(_) => TOMLTypeRef::InlineTable(TOMLTableRef::new()),
+        _ => TOMLTypeRef::Bool(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{events, Event};
+    use std::time::Instant;
+
+    fn collect(src: &str) -> Result<Vec<Event>, String> {
+        events(src.as_bytes()).collect()
+    }
+
+    #[test]
+    fn event_order() -> Result<(), String> {
+        let src = "# dump\ntitle = 'x' # inline\n\n[[record]]\nid = 1\ntags = [\n  'a', # first\n  'b',\n]\n[[record]]\nid = 2\nnote = \"\"\"\n[not a header]\n\"\"\"\n[meta.info]\nok = true";
+        let events = collect(src)?;
+        let summary: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                Event::TableHeader(path) => format!("[{}]", path.join(".")),
+                Event::ArrayOfTablesHeader(path) => format!("[[{}]]", path.join(".")),
+                Event::KeyValue(path, _) => path.join("."),
+                Event::Comment(text) => format!("#{}", text),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                "# dump",
+                "title",
+                "# inline",
+                "[[record]]",
+                "record.id",
+                "record.tags",
+                "# first",
+                "[[record]]",
+                "record.id",
+                "record.note",
+                "[meta.info]",
+                "meta.info.ok"
+            ]
+        );
+        match &events[5] {
+            Event::KeyValue(_, val) => assert_eq!(val.array().map(|arr| arr.len()), Some(2)),
+            _ => panic!("expected `record.tags`"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        const INVALID: [(&str, &str); 4] = [
+            ("[[record]]\nid = 1\nid = 2\n", "Line 3"),
+            ("a = { b = 1 }\na.c = 2\n", "Line 2"),
+            ("[a]\n[b]\n\n[a]\n", "Line 4"),
+            ("x = 1\n\ny = [\n1,\n2\n", "Line 6"),
+        ];
+        for (doc, line) in INVALID {
+            let err = collect(doc).unwrap_err();
+            assert!(err.starts_with(line), "{:?}: {}", doc, err);
+        }
+        // keys are scoped to the current table
+        assert!(collect("[[r]]\nid = 1\n[[r]]\nid = 2\n").is_ok());
+    }
+
+    #[test]
+    fn long_values() -> Result<(), String> {
+        // the best of a few runs, in seconds
+        let time = |doc: &str| -> Result<f64, String> {
+            let mut best = f64::INFINITY;
+            for _ in 0..3 {
+                let start = Instant::now();
+                collect(doc)?;
+                best = best.min(start.elapsed().as_secs_f64());
+            }
+            Ok(best)
+        };
+        let array = |lines: usize| format!("a = [\n{}]\n", "1, # one\n".repeat(lines));
+        let string = |lines: usize| format!("a = '''\n{}'''\n", "''x\n".repeat(lines));
+        let escapes = |lines: usize| format!("a = \"\"\"\n{}\"\"\"\n", "\\\"\"\"\n".repeat(lines));
+        for doc in [array, string, escapes] {
+            // each line is lexed once, so 8 times the lines takes about 8 times as long
+            let ratio = time(&doc(40_000))? / time(&doc(5_000))?;
+            assert!(ratio < 24.0, "{}", ratio);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,507 @@
+//! Rewriting documents in a standard layout, on top of the [`syntax`](crate::syntax) tree.
+
+// stdlib imports
+use std::path::Path;
+// my imports
+use crate::syntax::{Array, Content, Document, InlineTable, Key, Line, Trivia, Value};
+use crate::tomltypes::TOMLType;
+
+/// Settings for [`format`]. They can also be read from the `[fmt]` table of a `tomlp.toml`
+/// file; see [`from_toml`](Self::from_toml).
+///
+/// ```
+/// use tomlp::FormatOptions;
+/// let options = FormatOptions {
+///     indent_tables: true,
+///     array_width: 100,
+///     ..FormatOptions::default()
+/// };
+/// # assert_eq!(options.indent, FormatOptions::default().indent);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Spaces per level of indentation.
+    pub indent: usize,
+    /// Whether to indent sub-tables (and their keys) by how deeply their header is dotted
+    /// (ex. `[a.b]` one level, `[a.b.c]` two levels).
+    pub indent_tables: bool,
+    /// Widest an array may be, counting the line it's on, before it's written one element
+    /// per line. Arrays with comments are always written one element per line.
+    pub array_width: usize,
+    /// Whether the last element of an array written one element per line gets a comma.
+    pub trailing_comma: bool,
+    /// Blank lines before each table header.
+    pub blank_lines: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            indent_tables: false,
+            array_width: 80,
+            trailing_comma: true,
+            blank_lines: 1,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Reads settings from the `[fmt]` table of a configuration document, leaving the rest
+    /// at their defaults. The settings are named as the fields are, in kebab-case.
+    ///
+    /// ```
+    /// use tomlp::FormatOptions;
+    ///
+    /// let options = FormatOptions::from_toml("[fmt]\nindent = 4\ntrailing-comma = false\n")?;
+    /// assert_eq!(
+    ///     options,
+    ///     FormatOptions {
+    ///         indent: 4,
+    ///         trailing_comma: false,
+    ///         ..FormatOptions::default()
+    ///     }
+    /// );
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn from_toml(source: &str) -> Result<FormatOptions, String> {
+        let parsed = crate::parse_str(source)?;
+        let mut options = FormatOptions::default();
+        let table = match parsed.table().get("fmt") {
+            None => return Ok(options),
+            Some(
+                TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table),
+            ) => table,
+            Some(_) => return Err("Invalid setting `fmt`: expected a table.".to_string()),
+        };
+        for (name, val) in table {
+            let error = |reason: &str| format!("Invalid setting `fmt.{}`: {}.", name, reason);
+            let size = || {
+                val.u64()
+                    .and_then(|n| usize::try_from(n).ok())
+                    .ok_or_else(|| error("expected a non-negative integer"))
+            };
+            let flag = || match val {
+                TOMLType::Bool(b) => Ok(*b),
+                _ => Err(error("expected a boolean")),
+            };
+            match name.as_str() {
+                "indent" => options.indent = size()?,
+                "indent-tables" => options.indent_tables = flag()?,
+                "array-width" => options.array_width = size()?,
+                "trailing-comma" => options.trailing_comma = flag()?,
+                "blank-lines" => options.blank_lines = size()?,
+                _ => return Err(error("unknown setting")),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Reads settings from a configuration file. See [`from_toml`](Self::from_toml).
+    pub fn load<P: AsRef<Path>>(file: P) -> Result<FormatOptions, String> {
+        let source = std::fs::read_to_string(file)
+            .map_err(|err| format!("File Read Error: {}", err.kind()))?;
+        Self::from_toml(&source)
+    }
+}
+
+/// Rewrites TOML source text in a standard layout, keeping its comments and its meaning.
+///
+/// - Keys are written `key = value`, with no space around the dots of dotted keys or within
+///   the brackets of table headers.
+/// - Lines are indented only as [`FormatOptions::indent_tables`] asks, and have no trailing
+///   whitespace. Comments after content are set off by one space.
+/// - Each table header gets [`FormatOptions::blank_lines`] blank lines before it, and the
+///   comments directly above it move along with it. Blank lines right after a header, at the
+///   ends of the document, or in a run elsewhere are dropped or collapsed to one.
+/// - Arrays are written on one line if they fit within [`FormatOptions::array_width`], and
+///   otherwise one element per line, with a trailing comma if
+///   [`FormatOptions::trailing_comma`].
+/// - Inline tables are written `{ a = 1, b = 2 }`.
+/// - Values themselves (strings, numbers, etc.) are left as written.
+///
+/// Lines end as the first line of the source does. Invalid documents are an error.
+///
+/// ```
+/// use tomlp::FormatOptions;
+///
+/// let source = "  title='TOML'\n[ owner ]\n\n\nname   =  'Tom'   # the owner\nids=[1,2,3]\n";
+/// assert_eq!(
+///     tomlp::format(source, &FormatOptions::default())?,
+///     "title = 'TOML'\n\n[owner]\nname = 'Tom' # the owner\nids = [1, 2, 3]\n"
+/// );
+/// # Ok::<(), String>(())
+/// ```
+pub fn format(source: &str, options: &FormatOptions) -> Result<String, String> {
+    crate::parse_str(source)?;
+    let doc = Document::parse(source)?;
+    let newline = match source.find('\n') {
+        Some(i) if source[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
+    };
+    let mut formatter = Formatter {
+        options,
+        newline,
+        out: String::new(),
+        level: 0,
+        blank: false,
+        after_header: false,
+    };
+    formatter.document(&doc);
+    Ok(formatter.out)
+}
+
+struct Formatter<'o> {
+    options: &'o FormatOptions,
+    newline: &'static str,
+    out: String,
+    /// Indentation of the current table
+    level: usize,
+    /// Whether a blank line was skipped since the last line written
+    blank: bool,
+    /// Whether the last line written is a table header
+    after_header: bool,
+}
+
+impl Formatter<'_> {
+    fn document(&mut self, doc: &Document) {
+        // comment lines, held until we know whether they're above a header
+        let mut comments: Vec<&str> = Vec::new();
+        for line in &doc.lines {
+            match &line.content {
+                Content::Empty => match &line.comment {
+                    Some(comment) => comments.push(comment.trim_end()),
+                    None => {
+                        self.comments(&comments);
+                        comments.clear();
+                        self.blank = true;
+                    }
+                },
+                Content::Header(header) => {
+                    if self.options.indent_tables {
+                        self.level = header.key.parts.len() - 1;
+                    }
+                    if !self.out.is_empty() {
+                        self.out
+                            .push_str(&self.newline.repeat(self.options.blank_lines));
+                    }
+                    self.blank = false;
+                    self.comments(&comments);
+                    comments.clear();
+                    let (open, close) = if header.array {
+                        ("[[", "]]")
+                    } else {
+                        ("[", "]")
+                    };
+                    let indent = self.indent(self.level);
+                    let text = format!("{}{}{}{}", indent, open, key(&header.key), close);
+                    self.line(&text, line);
+                    self.after_header = true;
+                }
+                Content::KeyValue(kv) => {
+                    self.comments(&comments);
+                    comments.clear();
+                    self.separate();
+                    let prefix = format!("{}{} = ", self.indent(self.level), key(&kv.key));
+                    let val = self.value(&kv.value, self.level, width(&prefix), 0);
+                    self.line(&(prefix + &val), line);
+                }
+            }
+        }
+        self.comments(&comments);
+    }
+
+    /// Writes the blank line skipped before the next line, if any and if it's wanted there.
+    fn separate(&mut self) {
+        if self.blank && !self.out.is_empty() && !self.after_header {
+            self.out.push_str(self.newline);
+        }
+        self.blank = false;
+        self.after_header = false;
+    }
+
+    fn comments(&mut self, comments: &[&str]) {
+        if comments.is_empty() {
+            return;
+        }
+        self.separate();
+        for comment in comments {
+            let text = format!("{}{}", self.indent(self.level), comment);
+            self.out.push_str(&text);
+            self.out.push_str(self.newline);
+        }
+    }
+
+    /// Writes `text`, which is indented already, along with the comment of `line`.
+    fn line(&mut self, text: &str, line: &Line) {
+        self.out.push_str(text);
+        if let Some(comment) = &line.comment {
+            self.out.push(' ');
+            self.out.push_str(comment.trim_end());
+        }
+        self.out.push_str(self.newline);
+    }
+
+    fn indent(&self, level: usize) -> String {
+        " ".repeat(level * self.options.indent)
+    }
+
+    /// Writes a value starting at `column` of a line indented `level` times, followed by
+    /// `suffix` more characters.
+    fn value(&self, val: &Value, level: usize, column: usize, suffix: usize) -> String {
+        match val {
+            Value::Array(array) => {
+                let inline = inline(val);
+                let first_line = inline.split('\n').next().unwrap_or_default();
+                if !has_comments(val)
+                    && column + width(first_line) + suffix <= self.options.array_width
+                {
+                    inline
+                } else {
+                    self.wrapped(array, level)
+                }
+            }
+            _ => inline(val),
+        }
+    }
+
+    /// Writes an array one element per line.
+    fn wrapped(&self, array: &Array, level: usize) -> String {
+        let (elements, end) = arrange(array);
+        let inner = self.indent(level + 1);
+        let mut out = format!("[{}", self.newline);
+        for (i, element) in elements.iter().enumerate() {
+            for comment in &element.above {
+                out.push_str(&format!("{}{}{}", inner, comment, self.newline));
+            }
+            let comma = i + 1 < elements.len() || self.options.trailing_comma;
+            let suffix = usize::from(comma);
+            out.push_str(&inner);
+            out.push_str(&self.value(element.value, level + 1, width(&inner), suffix));
+            if comma {
+                out.push(',');
+            }
+            if let Some(comment) = element.after {
+                out.push(' ');
+                out.push_str(comment);
+            }
+            out.push_str(self.newline);
+        }
+        for comment in end {
+            out.push_str(&format!("{}{}{}", inner, comment, self.newline));
+        }
+        out.push_str(&self.indent(level));
+        out.push(']');
+        out
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+fn key(key: &Key) -> String {
+    let parts: Vec<&str> = key.parts.iter().map(|part| part.text.as_str()).collect();
+    parts.join(".")
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Writes a value on one line (other than the lines of multi-line strings). Inline tables
+/// with comments, which can only be written across lines, are left as written.
+fn inline(val: &Value) -> String {
+    match val {
+        Value::Scalar { text, .. } => text.clone(),
+        Value::Array(array) => {
+            let items: Vec<String> = array.items.iter().map(|item| inline(&item.value)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::InlineTable(_) if has_comments(val) => val.to_string(),
+        Value::InlineTable(InlineTable { items, .. }) if items.is_empty() => "{}".to_string(),
+        Value::InlineTable(table) => {
+            let items: Vec<String> = table
+                .items
+                .iter()
+                .map(|item| format!("{} = {}", key(&item.value.key), inline(&item.value.value)))
+                .collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+    }
+}
+
+fn has_comments(val: &Value) -> bool {
+    let in_trivia = |trivia: &[Trivia]| {
+        trivia
+            .iter()
+            .any(|trivia| matches!(trivia, Trivia::Comment(_)))
+    };
+    match val {
+        Value::Scalar { .. } => false,
+        Value::Array(array) => {
+            in_trivia(&array.trailing)
+                || array.items.iter().any(|item| {
+                    in_trivia(&item.leading)
+                        || in_trivia(&item.before_comma)
+                        || has_comments(&item.value)
+                })
+        }
+        Value::InlineTable(table) => {
+            in_trivia(&table.trailing)
+                || table.items.iter().any(|item| {
+                    in_trivia(&item.leading)
+                        || in_trivia(&item.before_comma)
+                        || has_comments(&item.value.value)
+                })
+        }
+    }
+}
+
+/// An array element, with the comments that go on their own lines above it and the one that
+/// goes after it on its line.
+struct Element<'a> {
+    above: Vec<&'a str>,
+    value: &'a Value,
+    after: Option<&'a str>,
+}
+
+/// Sorts the comments of an array by the elements they go with: a comment on the same line as
+/// an element goes after it, and the rest go above the next element, or at the end.
+fn arrange(array: &Array) -> (Vec<Element<'_>>, Vec<&str>) {
+    let mut elements: Vec<Element> = Vec::new();
+    let mut above = Vec::new();
+    let mut same_line = false;
+    for item in &array.items {
+        place(&item.leading, &mut same_line, &mut elements, &mut above);
+        elements.push(Element {
+            above: std::mem::take(&mut above),
+            value: &item.value,
+            after: None,
+        });
+        same_line = true;
+        place(
+            &item.before_comma,
+            &mut same_line,
+            &mut elements,
+            &mut above,
+        );
+    }
+    place(&array.trailing, &mut same_line, &mut elements, &mut above);
+    (elements, above)
+}
+
+/// Places the comments of `trivia`, which comes after `elements`. See [`arrange`].
+fn place<'a>(
+    trivia: &'a [Trivia],
+    same_line: &mut bool,
+    elements: &mut [Element<'a>],
+    above: &mut Vec<&'a str>,
+) {
+    for trivia in trivia {
+        match trivia {
+            Trivia::Newline(_) => *same_line = false,
+            Trivia::Comment(comment) => match elements.last_mut() {
+                Some(element) if *same_line && element.after.is_none() => {
+                    element.after = Some(comment.trim_end())
+                }
+                _ => above.push(comment.trim_end()),
+            },
+            Trivia::Whitespace(_) => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, FormatOptions};
+
+    #[test]
+    fn layout() -> Result<(), String> {
+        let source = "\n\n# top\nname=\"x\"\n\n\n\nversion   =   '1'  \n# about deps\n  [ dependencies ]\n\n  a.b  .c = 1 # note\n[[ bin ]]\npath = 'a'\n\n";
+        let expected = "# top\nname = \"x\"\n\nversion = '1'\n\n# about deps\n[dependencies]\na.b.c = 1 # note\n\n[[bin]]\npath = 'a'\n";
+        let options = FormatOptions::default();
+        assert_eq!(format(source, &options)?, expected);
+        assert_eq!(format(expected, &options)?, expected);
+        // comments at the end keep the blank line above them
+        let expected = "a = 1\n\n# end\n";
+        assert_eq!(format("a = 1\n\n# end\n\n", &options)?, expected);
+        assert_eq!(format(expected, &options)?, expected);
+        assert_eq!(format("#\n\n#\n\n", &options)?, "#\n\n#\n");
+
+        let options = FormatOptions {
+            indent: 4,
+            indent_tables: true,
+            blank_lines: 2,
+            ..FormatOptions::default()
+        };
+        let source = "[a]\nx = 1\n[a.b]\ny = { p = 1 ,q=[ 1,2 ] }\n[c]\r\nz = {}\n";
+        let expected =
+            "[a]\nx = 1\n\n\n    [a.b]\n    y = { p = 1, q = [1, 2] }\n\n\n[c]\nz = {}\n";
+        assert_eq!(format(source, &options)?, expected);
+        assert_eq!(
+            format("a = 1\r\n[b]\n", &options)?,
+            "a = 1\r\n\r\n\r\n[b]\r\n"
+        );
+        assert_eq!(format("", &options)?, "");
+        assert!(format("a = 1\na = 2\n", &options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn arrays() -> Result<(), String> {
+        let options = FormatOptions {
+            array_width: 20,
+            ..FormatOptions::default()
+        };
+        let source = "short = [ 1,2 ]\nlong = ['aaaa', 'bbbb', 'cccc']\nnested = [[1, 2], ['aaaa', 'bbbb', 'cccc']]\n";
+        let expected = "short = [1, 2]\nlong = [\n  'aaaa',\n  'bbbb',\n  'cccc',\n]\nnested = [\n  [1, 2],\n  [\n    'aaaa',\n    'bbbb',\n    'cccc',\n  ],\n]\n";
+        assert_eq!(format(source, &options)?, expected);
+        assert_eq!(format(expected, &options)?, expected);
+
+        let source = "a = [ # first\n    1, # one\n\n    # before two\n    2 # two\n    # end\n]\n";
+        let expected = "a = [\n  # first\n  1, # one\n  # before two\n  2 # two\n  # end\n]\n";
+        let options = FormatOptions {
+            trailing_comma: false,
+            ..FormatOptions::default()
+        };
+        assert_eq!(format(source, &options)?, expected);
+        assert_eq!(format(expected, &options)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn resources() -> Result<(), String> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tomlparse/test_resources");
+        let options = FormatOptions {
+            indent_tables: true,
+            array_width: 30,
+            ..FormatOptions::default()
+        };
+        for entry in std::fs::read_dir(dir).map_err(|err| err.to_string())? {
+            let path = entry.map_err(|err| err.to_string())?.path();
+            let source = std::fs::read_to_string(&path).map_err(|err| err.to_string())?;
+            if crate::parse_str(&source).is_ok() {
+                let formatted = format(&source, &options)?;
+                assert_eq!(format(&formatted, &options)?, formatted, "{:?}", path);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn config() {
+        assert_eq!(
+            FormatOptions::from_toml("[fmt]\nindent = -1").unwrap_err(),
+            "Invalid setting `fmt.indent`: expected a non-negative integer."
+        );
+        assert_eq!(
+            FormatOptions::from_toml("fmt.width = 1").unwrap_err(),
+            "Invalid setting `fmt.width`: unknown setting."
+        );
+        assert_eq!(
+            FormatOptions::from_toml("[other]\nx = 1"),
+            Ok(FormatOptions::default())
+        );
+    }
+}
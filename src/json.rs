@@ -0,0 +1,487 @@
+//! A small JSON reader and writer, for JSON Schemas and machine-readable output.
+
+// stdlib imports
+use std::fmt;
+
+/// How deeply arrays and objects may nest, which keeps hostile input from overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A JSON value. Objects keep their keys in document order.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    /// A number without a fraction or exponent, kept exactly
+    Integer(i128),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a JSON document.
+    pub fn parse(source: &str) -> Result<Json, String> {
+        let mut reader = Reader {
+            source,
+            pos: 0,
+            depth: 0,
+        };
+        reader.skip_ws();
+        let val = reader.value()?;
+        reader.skip_ws();
+        if reader.pos < source.len() {
+            return Err(reader.error("expected the end of the document"));
+        }
+        Ok(val)
+    }
+
+    /// The value of `key`, if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        self.as_object()?
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, val)| val)
+    }
+
+    /// Follows a JSON Pointer (ex. `/definitions/name`, or the empty string for the value itself).
+    pub fn pointer(&self, pointer: &str) -> Option<&Json> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        let mut val = self;
+        for token in pointer.strip_prefix('/')?.split('/') {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            val = match val {
+                Json::Object(_) => val.get(&token)?,
+                Json::Array(vals) => vals.get(token.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(val)
+    }
+
+    /// Writes this as indented JSON. Arrays of numbers, strings, and such stay on one line.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(0, &mut out);
+        out
+    }
+
+    fn write_pretty(&self, indent: usize, out: &mut String) {
+        let nested = |val: &Json| matches!(val, Json::Array(_) | Json::Object(_));
+        match self {
+            Json::Array(vals) if !vals.is_empty() && vals.iter().any(nested) => {
+                out.push_str("[\n");
+                for (i, val) in vals.iter().enumerate() {
+                    out.push_str(&"  ".repeat(indent + 1));
+                    val.write_pretty(indent + 1, out);
+                    out.push_str(if i + 1 < vals.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push(']');
+            }
+            Json::Array(vals) => {
+                let vals: Vec<String> = vals.iter().map(Json::to_string).collect();
+                out.push_str(&format!("[{}]", vals.join(", ")));
+            }
+            Json::Object(fields) if !fields.is_empty() => {
+                out.push_str("{\n");
+                for (i, (key, val)) in fields.iter().enumerate() {
+                    out.push_str(&"  ".repeat(indent + 1));
+                    out.push_str(&format!("{}: ", Json::String(key.clone())));
+                    val.write_pretty(indent + 1, out);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push('}');
+            }
+            val => out.push_str(&val.to_string()),
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Integer(n) => Some(*n as f64),
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(vals) => Some(vals),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, Json)]> {
+        match self {
+            Json::Object(fields) => Some(fields),
+            _ => None,
+        }
+    }
+}
+
+/// Writes compact JSON.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Integer(n) => write!(f, "{}", n),
+            // always with a fraction or exponent, so it reads back as a `Number`
+            Json::Number(n) if n.is_finite() => write!(f, "{:?}", n),
+            Json::Number(_) => f.write_str("null"),
+            Json::String(s) => write_string(f, s),
+            Json::Array(vals) => {
+                f.write_str("[")?;
+                for (i, val) in vals.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", val)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(fields) => {
+                f.write_str("{")?;
+                for (i, (key, val)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", val)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if u32::from(c) < 0x20 => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+struct Reader<'s> {
+    source: &'s str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, reason: &str) -> String {
+        let line = self.source[..self.pos].matches('\n').count() + 1;
+        format!("Line {}: JSON Parsing Error: {}.", line, reason)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", byte as char)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => {
+                for (word, val) in [
+                    ("null", Json::Null),
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                ] {
+                    if self.source[self.pos..].starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(val);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+            None => Err(self.error("unexpected end of the document")),
+        }
+    }
+
+    fn nested(&mut self, read: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let val = read(self);
+        self.depth -= 1;
+        val
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_ws();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            self.skip_ws();
+            self.expect(b':')?;
+            self.skip_ws();
+            fields.push((key, self.value()?));
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut vals = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(vals));
+        }
+        loop {
+            self.skip_ws();
+            vals.push(self.value()?);
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(vals));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            let rest = &self.source[self.pos..];
+            let Some(c) = rest.chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(out),
+                '\\' => out.push(self.escape()?),
+                c if u32::from(c) < 0x20 => return Err(self.error("control character in a string")),
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += 1;
+        Ok(match c {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.hex4()?;
+                let code = if (0xD800..0xDC00).contains(&high) {
+                    // a surrogate pair, written as two escapes
+                    if !self.source[self.pos..].starts_with("\\u") {
+                        return Err(self.error("unpaired surrogate"));
+                    }
+                    self.pos += 2;
+                    let low = self.hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("unpaired surrogate"));
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    high
+                };
+                char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))?
+            }
+            _ => return Err(self.error("invalid escape")),
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .source
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("expected four hex digits"))?;
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).map_err(|_| self.error("expected four hex digits"))
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        let digits = |reader: &mut Self| {
+            let from = reader.pos;
+            while reader.peek().is_some_and(|b| b.is_ascii_digit()) {
+                reader.pos += 1;
+            }
+            reader.pos - from
+        };
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let int_start = self.pos;
+        if digits(self) == 0
+            || (self.source.as_bytes()[int_start] == b'0' && self.pos - int_start > 1)
+        {
+            return Err(self.error("invalid number"));
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if digits(self) == 0 {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if digits(self) == 0 {
+                return Err(self.error("invalid number"));
+            }
+        }
+        let text = &self.source[start..self.pos];
+        // numbers with a fraction or exponent, or too large for an `i128`, are floats
+        if let Ok(n) = text.parse() {
+            return Ok(Json::Integer(n));
+        }
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| self.error("invalid number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+
+    #[test]
+    fn round_trip() -> Result<(), String> {
+        let source = r#"{"a":[1,-2.5,1e300,true,null],"b\n\"":"\u00e9\ud83d\ude00","c":{}}"#;
+        let json = Json::parse(source)?;
+        assert_eq!(json.pointer("/a/1"), Some(&Json::Number(-2.5)));
+        assert_eq!(json.pointer("/b\n\"").and_then(Json::as_str), Some("é😀"));
+        assert_eq!(json.pointer("/c"), Some(&Json::Object(Vec::new())));
+        assert_eq!(json.pointer(""), Some(&json));
+        assert_eq!(Json::parse(&json.to_string())?, json);
+        assert_eq!(
+            Json::parse(" [ 0 , 10 ] ")?.to_string(),
+            "[0,10]".to_string()
+        );
+        assert_eq!(
+            json.pretty(),
+            "{\n  \"a\": [1, -2.5, 1e300, true, null],\n  \"b\\n\\\"\": \"é😀\",\n  \"c\": {}\n}"
+        );
+        assert_eq!(Json::parse(&json.pretty())?, json);
+
+        // integers are exact, and floats are written with a fraction or exponent
+        let source = "[9007199254740993,-170141183460469231731687303715884105728,1.0,1e3,1e100]";
+        let json = Json::parse(source)?;
+        assert_eq!(json.pointer("/0"), Some(&Json::Integer(9007199254740993)));
+        assert_eq!(json.pointer("/1"), Some(&Json::Integer(i128::MIN)));
+        assert_eq!(json.pointer("/2"), Some(&Json::Number(1.0)));
+        assert_eq!(
+            json.to_string(),
+            "[9007199254740993,-170141183460469231731687303715884105728,1.0,1000.0,1e100]"
+        );
+        assert_eq!(Json::parse(&json.to_string())?, json);
+        assert_eq!(Json::parse("1e40")?, Json::Number(1e40));
+        assert_eq!(
+            Json::parse("1000000000000000000000000000000000000000")?,
+            Json::Number(1e39)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        let cases = [
+            (
+                "",
+                "Line 1: JSON Parsing Error: unexpected end of the document.",
+            ),
+            ("[1,]", "Line 1: JSON Parsing Error: expected a value."),
+            ("{\n\"a\" 1}", "Line 2: JSON Parsing Error: expected `:`."),
+            ("01", "Line 1: JSON Parsing Error: invalid number."),
+            ("\"\\x\"", "Line 1: JSON Parsing Error: invalid escape."),
+            (
+                "\"\\ud83d\"",
+                "Line 1: JSON Parsing Error: unpaired surrogate.",
+            ),
+            (
+                "{} {}",
+                "Line 1: JSON Parsing Error: expected the end of the document.",
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(Json::parse(source).unwrap_err(), expected, "{:?}", source);
+        }
+        let deep = "[".repeat(200);
+        assert!(Json::parse(&deep)
+            .unwrap_err()
+            .contains("nested too deeply"));
+    }
+}
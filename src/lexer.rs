@@ -0,0 +1,908 @@
+//! A byte-oriented lexer for TOML source text.
+//!
+//! TOML is defined over Unicode code points, and every character the grammar gives meaning to
+//! is ASCII. Since UTF-8 never uses ASCII bytes inside a multi-byte sequence, the lexer can walk
+//! the source one byte at a time and only ever lands on character boundaries.
+//!
+//! [`tokenize`] exposes the token stream for tools that need it (ex. syntax highlighters).
+//! Every byte of the source belongs to exactly one token, so concatenating the spans of a
+//! successful tokenization reproduces the input.
+//!
+//! ```
+//! use tomlp::lexer::{tokenize, TokenKind::*};
+//!
+//! let src = "[server]\nport = 8080 # default\n";
+//! let kinds = tokenize(src)
+//!     .map(|tok| tok.map(|tok| tok.kind))
+//!     .collect::<Result<Vec<_>, String>>()?;
+//! assert_eq!(
+//!     kinds,
+//!     [
+//!         BracketOpen, BareKey, BracketClose, Newline,
+//!         BareKey, Whitespace, Equals, Whitespace, Integer, Whitespace, Comment, Newline,
+//!     ]
+//! );
+//! # Ok::<(), String>(())
+//! ```
+
+// stdlib imports
+use std::ops::Range;
+
+// Characters of Interest
+pub(crate) const COMMENT_TOKEN: u8 = b'#';
+pub(crate) const DOT_TOKEN: u8 = b'.';
+pub(crate) const ESCAPE_TOKEN: u8 = b'\\';
+pub(crate) const INLINETAB_OPEN_TOKEN: u8 = b'{';
+pub(crate) const INLINETAB_CLOSE_TOKEN: u8 = b'}';
+pub(crate) const KEY_VAL_SEP: u8 = b'=';
+pub(crate) const LITERAL_STR_TOKEN: u8 = b'\'';
+pub(crate) const SEQUENCE_DELIM: u8 = b',';
+pub(crate) const STR_TOKEN: u8 = b'"';
+pub(crate) const TABLE_OPEN_TOKEN: u8 = b'[';
+pub(crate) const TABLE_CLOSE_TOKEN: u8 = b']';
+
+/// Lex a TOML document into its tokens.
+///
+/// The iterator tracks whether a key or a value is expected, so `1234 = 1234` yields a
+/// [`TokenKind::BareKey`] followed by a [`TokenKind::Integer`].
+/// After a lexing error, the iterator resumes on the next line.
+pub fn tokenize(src: &str) -> Tokens<'_> {
+    Tokens {
+        lexer: Lexer::new(src),
+        mode: Mode::Key,
+        nesting: Vec::new(),
+        truncated: false,
+        open_string: None,
+    }
+}
+
+/// The lexing context.
+/// Bare keys and unquoted values share characters (ex. `1234 = 1234`), so the caller decides
+/// which of the two it expects next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mode {
+    Key,
+    Value,
+}
+
+/// The kinds of lexemes found in a TOML document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A run of spaces and/or tabs
+    Whitespace,
+    /// `\n` or `\r\n`
+    Newline,
+    /// From `#` up to (not including) the end of the line
+    Comment,
+    /// `=`
+    Equals,
+    /// `.`
+    Dot,
+    /// `,`
+    Comma,
+    /// `[`
+    BracketOpen,
+    /// `]`
+    BracketClose,
+    /// `[[`, opening an array of tables header
+    DoubleBracketOpen,
+    /// `]]`, closing an array of tables header
+    DoubleBracketClose,
+    /// `{`
+    BraceOpen,
+    /// `}`
+    BraceClose,
+    /// An unquoted key (ex. `bare-key_1`)
+    BareKey,
+    /// A basic or literal string in key position (ex. `"127.0.0.1"`)
+    QuotedKey,
+    /// `"..."`
+    BasicStr,
+    /// `"""..."""`
+    MultiStr,
+    /// `'...'`
+    LitStr,
+    /// `'''...'''`
+    MultiLitStr,
+    /// Decimal, hexadecimal, octal, or binary integer
+    Integer,
+    /// Includes `inf` and `nan`
+    Float,
+    /// Offset date-time, local date-time, local date, or local time
+    Datetime,
+    /// `true` or `false`, in any case (only lowercase is valid)
+    Boolean,
+}
+
+/// A lexeme and its location in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Byte range of the lexeme, delimiters included.
+    pub span: Range<usize>,
+    /// The (1-based) line the lexeme begins on.
+    pub line: usize,
+}
+impl Token {
+    /// The lexeme's text within the source it was produced from.
+    pub fn text<'s>(&self, src: &'s str) -> &'s str {
+        &src[self.span.clone()]
+    }
+}
+
+/// A cursor over TOML source text producing [`Token`]s.
+///
+/// The lexer is cheap to clone, which is how lookahead is done.
+#[derive(Debug, Clone)]
+pub(crate) struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+}
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    /// Number lines starting from `line` instead of 1.
+    pub fn starting_at_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    /// The line the cursor is on
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The byte offset of the cursor
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// The size of the source in bytes
+    pub fn source_len(&self) -> usize {
+        self.src.len()
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.pos >= self.src.len()
+    }
+
+    /// The text of a lexeme produced by this lexer.
+    pub fn slice(&self, span: Range<usize>) -> &'a str {
+        &self.src[span]
+    }
+
+    /// Retrieve the byte under the cursor without consuming it.
+    pub fn peek_byte(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    /// Retrieve a preview of the next token.
+    pub fn peek_token(&self, mode: Mode) -> Result<Option<Token>, String> {
+        self.clone().next_token(mode)
+    }
+
+    /// Skips spaces and tabs.
+    pub fn skip_ws(&mut self) {
+        self.eat_while(is_ws);
+    }
+
+    pub fn next_token(&mut self, mode: Mode) -> Result<Option<Token>, String> {
+        let start = self.pos;
+        let line = self.line;
+        let Some(b) = self.peek_byte() else {
+            return Ok(None);
+        };
+        let kind = match b {
+            b' ' | b'\t' => {
+                self.skip_ws();
+                TokenKind::Whitespace
+            }
+            b'\n' => {
+                self.pos += 1;
+                self.line += 1;
+                TokenKind::Newline
+            }
+            b'\r' => {
+                if self.byte_at(start + 1) != Some(b'\n') {
+                    return Err(format!(
+                        "Line {}: Carriage return must be followed by a newline.",
+                        line
+                    ));
+                }
+                self.pos += 2;
+                self.line += 1;
+                TokenKind::Newline
+            }
+            COMMENT_TOKEN => {
+                self.lex_comment()?;
+                TokenKind::Comment
+            }
+            KEY_VAL_SEP => self.single(TokenKind::Equals),
+            DOT_TOKEN if mode == Mode::Key => self.single(TokenKind::Dot),
+            SEQUENCE_DELIM => self.single(TokenKind::Comma),
+            TABLE_OPEN_TOKEN if mode == Mode::Key && self.byte_at(start + 1) == Some(b) => {
+                self.pos += 2;
+                TokenKind::DoubleBracketOpen
+            }
+            TABLE_CLOSE_TOKEN if mode == Mode::Key && self.byte_at(start + 1) == Some(b) => {
+                self.pos += 2;
+                TokenKind::DoubleBracketClose
+            }
+            TABLE_OPEN_TOKEN => self.single(TokenKind::BracketOpen),
+            TABLE_CLOSE_TOKEN => self.single(TokenKind::BracketClose),
+            INLINETAB_OPEN_TOKEN => self.single(TokenKind::BraceOpen),
+            INLINETAB_CLOSE_TOKEN => self.single(TokenKind::BraceClose),
+            STR_TOKEN | LITERAL_STR_TOKEN => {
+                let kind = if b == STR_TOKEN {
+                    self.lex_basic_quoted()?
+                } else {
+                    self.lex_literal_quoted()?
+                };
+                match kind {
+                    TokenKind::BasicStr | TokenKind::LitStr if mode == Mode::Key => {
+                        TokenKind::QuotedKey
+                    }
+                    _ => kind,
+                }
+            }
+            _ if mode == Mode::Key && is_barekey_char(b) => {
+                self.eat_while(is_barekey_char);
+                TokenKind::BareKey
+            }
+            _ if mode == Mode::Value && is_bare_value_char(b) => {
+                self.lex_bare_value();
+                let lexeme = &self.src[start..self.pos];
+                match classify_bare_value(lexeme.as_bytes()) {
+                    Some(kind) => kind,
+                    None => return Err(format!("Line {}: Invalid value `{}`.", line, lexeme)),
+                }
+            }
+            _ => {
+                let ch = self.src[start..].chars().next().unwrap_or_default();
+                return Err(format!(
+                    "Line {}: Unexpected character `{}`.",
+                    line,
+                    ch.escape_debug()
+                ));
+            }
+        };
+        Ok(Some(Token {
+            kind,
+            span: start..self.pos,
+            line,
+        }))
+    }
+
+    ///////////////////
+    // Lexing Functions
+    ///////////////////
+
+    fn single(&mut self, kind: TokenKind) -> TokenKind {
+        self.pos += 1;
+        kind
+    }
+
+    fn byte_at(&self, idx: usize) -> Option<u8> {
+        self.src.as_bytes().get(idx).copied()
+    }
+
+    fn eat_while(&mut self, pred: impl Fn(u8) -> bool) {
+        while let Some(b) = self.peek_byte() {
+            if !pred(b) {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes a comment, validating its characters.
+    /// Assumes the cursor is on `#`.
+    fn lex_comment(&mut self) -> Result<(), String> {
+        self.pos += 1;
+        while let Some(b) = self.peek_byte() {
+            match b {
+                b'\n' => break,
+                b'\r' if self.byte_at(self.pos + 1) == Some(b'\n') => break,
+                _ if !is_valid_comment_byte(b) => {
+                    return Err(format!(
+                        "Line {}: Invalid comment character U+{:04X}.",
+                        self.line, b
+                    ))
+                }
+                _ => self.pos += 1,
+            }
+        }
+        Ok(())
+    }
+
+    /// Consumes a basic or multi-line basic string.
+    /// Assumes the cursor is on the opening `"`.
+    fn lex_basic_quoted(&mut self) -> Result<TokenKind, String> {
+        if self.src[self.pos..].starts_with("\"\"\"") {
+            self.lex_multiline(STR_TOKEN)?;
+            return Ok(TokenKind::MultiStr);
+        }
+        let line = self.line;
+        self.pos += 1;
+        loop {
+            match self.peek_byte() {
+                None | Some(b'\n') => {
+                    return Err(format!("Line {}: Non-terminating basic string.", line))
+                }
+                Some(STR_TOKEN) => {
+                    self.pos += 1;
+                    return Ok(TokenKind::BasicStr);
+                }
+                Some(ESCAPE_TOKEN) => {
+                    // The escaped character is validated when the string is decoded, but it
+                    // must not end the lexeme.
+                    match self.byte_at(self.pos + 1) {
+                        None | Some(b'\n') => {
+                            return Err(format!("Line {}: Non-terminating basic string.", line))
+                        }
+                        Some(_) => self.pos += 2,
+                    }
+                }
+                Some(b) if !is_valid_str_byte(b) => {
+                    return Err(format!(
+                        "Line {}: Invalid Unicode Character U+{:04X}",
+                        line, b
+                    ))
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Consumes a literal or multi-line literal string.
+    /// Assumes the cursor is on the opening `'`.
+    fn lex_literal_quoted(&mut self) -> Result<TokenKind, String> {
+        if self.src[self.pos..].starts_with("'''") {
+            self.lex_multiline(LITERAL_STR_TOKEN)?;
+            return Ok(TokenKind::MultiLitStr);
+        }
+        let line = self.line;
+        self.pos += 1;
+        loop {
+            match self.peek_byte() {
+                None | Some(b'\n') => {
+                    return Err(format!("Line {}: Non-terminating literal string.", line))
+                }
+                Some(LITERAL_STR_TOKEN) => {
+                    self.pos += 1;
+                    return Ok(TokenKind::LitStr);
+                }
+                Some(b) if !is_valid_str_byte(b) => {
+                    return Err(format!(
+                        "Line {}: Invalid Unicode Character U+{:04X} in literal string.",
+                        line, b
+                    ))
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Consumes a multi-line string delimited by three `delim` characters.
+    /// Up to two extra delimiter characters directly before the closing delimiter belong to the
+    /// string's content (REFERENCE: https://toml.io/en/v1.0.0#string).
+    fn lex_multiline(&mut self, delim: u8) -> Result<(), String> {
+        let line = self.line;
+        self.pos += 3;
+        self.lex_multiline_rest(delim, line)
+    }
+
+    /// Consumes the rest of a multi-line string that began on `line`, from a point within its
+    /// content that isn't in the middle of an escape or a run of delimiters.
+    fn lex_multiline_rest(&mut self, delim: u8, line: usize) -> Result<(), String> {
+        loop {
+            match self.peek_byte() {
+                None => return Err(format!("Line {}: Non-terminating multi-line string.", line)),
+                Some(b) if b == delim => {
+                    let run_start = self.pos;
+                    self.eat_while(|x| x == delim);
+                    let run = self.pos - run_start;
+                    if run >= 3 {
+                        if run > 5 {
+                            return Err(format!(
+                                "Line {}: Too many consecutive quotation marks closing a multi-line string.",
+                                self.line
+                            ));
+                        }
+                        return Ok(());
+                    }
+                }
+                Some(ESCAPE_TOKEN) if delim == STR_TOKEN => match self.byte_at(self.pos + 1) {
+                    None => {
+                        return Err(format!("Line {}: Non-terminating multi-line string.", line))
+                    }
+                    Some(b'\n') => {
+                        self.pos += 2;
+                        self.line += 1;
+                    }
+                    Some(_) => self.pos += 2,
+                },
+                Some(b'\n') => {
+                    self.pos += 1;
+                    self.line += 1;
+                }
+                Some(b'\r') if self.byte_at(self.pos + 1) == Some(b'\n') => self.pos += 1,
+                Some(b) if !is_valid_str_byte(b) => {
+                    return Err(format!(
+                        "Line {}: Invalid Unicode Character U+{:04X}",
+                        self.line, b
+                    ))
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Consumes an unquoted value.
+    /// Local date-times may separate the date and time with a space (ex. `1979-05-27 07:32:00`),
+    /// so the lexeme continues past a space that sits between a full date and a time.
+    fn lex_bare_value(&mut self) {
+        let start = self.pos;
+        self.eat_while(is_bare_value_char);
+        let lexeme = &self.src.as_bytes()[start..self.pos];
+        let rest = &self.src.as_bytes()[self.pos..];
+        if is_full_date(lexeme)
+            && rest.len() >= 4
+            && rest[0] == b' '
+            && rest[1].is_ascii_digit()
+            && rest[2].is_ascii_digit()
+            && rest[3] == b':'
+        {
+            self.pos += 1;
+            self.eat_while(is_bare_value_char);
+        }
+    }
+}
+
+/// The containers a [`Tokens`] iterator is currently within.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nesting {
+    Header,
+    Array,
+    InlineTable,
+}
+
+/// An iterator over the tokens of a TOML document.
+///
+/// This `struct` is created by [`tokenize`].
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    lexer: Lexer<'a>,
+    mode: Mode,
+    nesting: Vec<Nesting>,
+    truncated: bool,
+    /// The delimiter, start, and line of a multi-line string the source ended within
+    open_string: Option<(u8, usize, usize)>,
+}
+
+/// Where a [`Tokens`] iterator stopped, kept without borrowing the source so that lexing can
+/// continue once more text is appended to it.
+#[derive(Debug, Clone)]
+pub(crate) struct Checkpoint {
+    pos: usize,
+    line: usize,
+    mode: Mode,
+    nesting: Vec<Nesting>,
+    open_string: Option<(u8, usize, usize)>,
+}
+impl Default for Checkpoint {
+    fn default() -> Self {
+        Self {
+            pos: 0,
+            line: 1,
+            mode: Mode::Key,
+            nesting: Vec::new(),
+            open_string: None,
+        }
+    }
+}
+
+impl<'a> Tokens<'a> {
+    /// Continues lexing `src` from where a [`Checkpoint`] taken on the start of it stopped,
+    /// which must be the end of a line. Only the text after that is lexed, except for the rest
+    /// of a multi-line string it ended within.
+    pub(crate) fn resume(src: &'a str, checkpoint: Checkpoint) -> Self {
+        let mut lexer = Lexer::new(src);
+        lexer.pos = checkpoint.pos;
+        lexer.line = checkpoint.line;
+        Self {
+            lexer,
+            mode: checkpoint.mode,
+            nesting: checkpoint.nesting,
+            truncated: false,
+            open_string: checkpoint.open_string,
+        }
+    }
+
+    /// Where lexing stopped, to [`resume`](Self::resume) from once more text is appended.
+    pub(crate) fn into_checkpoint(self) -> Checkpoint {
+        Checkpoint {
+            pos: self.lexer.pos,
+            line: self.lexer.line,
+            mode: self.mode,
+            nesting: self.nesting,
+            open_string: self.open_string,
+        }
+    }
+
+    /// Determines if the source ended within an array or a multi-line string, i.e. if more
+    /// input could complete the last value.
+    pub(crate) fn is_incomplete(&self) -> bool {
+        self.truncated || self.nesting.contains(&Nesting::Array)
+    }
+
+    /// Determines what the token following `kind` is lexed as.
+    fn update_mode(&mut self, kind: TokenKind) {
+        match kind {
+            TokenKind::Newline if self.nesting.last() != Some(&Nesting::Array) => {
+                self.nesting.clear();
+                self.mode = Mode::Key;
+            }
+            TokenKind::Equals => self.mode = Mode::Value,
+            TokenKind::BracketOpen if self.mode == Mode::Key => self.nesting.push(Nesting::Header),
+            TokenKind::BracketOpen => self.nesting.push(Nesting::Array),
+            TokenKind::DoubleBracketOpen => self.nesting.push(Nesting::Header),
+            TokenKind::BracketClose | TokenKind::DoubleBracketClose => {
+                if self.nesting.pop() == Some(Nesting::Array) {
+                    self.mode = Mode::Value;
+                } else {
+                    self.mode = Mode::Key;
+                }
+            }
+            TokenKind::BraceOpen => {
+                self.nesting.push(Nesting::InlineTable);
+                self.mode = Mode::Key;
+            }
+            TokenKind::BraceClose => {
+                self.nesting.pop();
+                self.mode = Mode::Value;
+            }
+            TokenKind::Comma => match self.nesting.last() {
+                Some(Nesting::InlineTable) => self.mode = Mode::Key,
+                _ => self.mode = Mode::Value,
+            },
+            _ => (),
+        }
+    }
+
+    /// Skips the remainder of the line the lexer errored on, up to the newline, which is lexed
+    /// as usual so that the lines of later tokens stay right.
+    fn recover(&mut self) {
+        let rest = &self.lexer.src[self.lexer.pos..];
+        self.lexer.pos += rest.find('\n').unwrap_or(rest.len());
+        if self.nesting.last() != Some(&Nesting::Array) {
+            self.nesting.clear();
+            self.mode = Mode::Key;
+        }
+    }
+}
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<Token, String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, line) = match self.open_string {
+            // already reported as unterminated
+            Some(_) if self.lexer.is_eof() => return None,
+            Some((_, start, line)) => (start, line),
+            None => (self.lexer.pos, self.lexer.line),
+        };
+        let result = match self.open_string.take() {
+            Some((delim, ..)) => self.lexer.lex_multiline_rest(delim, line).map(|()| {
+                let kind = if delim == STR_TOKEN {
+                    TokenKind::MultiStr
+                } else {
+                    TokenKind::MultiLitStr
+                };
+                Some(Token {
+                    kind,
+                    span: start..self.lexer.pos,
+                    line,
+                })
+            }),
+            None => self.lexer.next_token(self.mode),
+        };
+        match result {
+            Ok(Some(tok)) => {
+                self.update_mode(tok.kind);
+                Some(Ok(tok))
+            }
+            Ok(None) => None,
+            Err(msg) => {
+                self.truncated = self.lexer.is_eof();
+                let lexeme = &self.lexer.src.as_bytes()[start..];
+                self.open_string = [STR_TOKEN, LITERAL_STR_TOKEN]
+                    .into_iter()
+                    .find(|&delim| self.truncated && lexeme.starts_with(&[delim; 3]))
+                    .map(|delim| (delim, start, line));
+                if self.open_string.is_none() {
+                    self.recover();
+                }
+                Some(Err(msg))
+            }
+        }
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+/// Spaces and tabs
+fn is_ws(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+/// Determines if the provided byte is a valid bare key character.
+fn is_barekey_char(b: u8) -> bool {
+    matches!(b, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-')
+}
+
+/// Determines if `key` can be written without quotes.
+pub(crate) fn is_bare_key(key: &str) -> bool {
+    !key.is_empty() && key.bytes().all(is_barekey_char)
+}
+
+/// Writes `text` as a basic string, quotes included.
+pub(crate) fn quote_basic(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Characters that may appear in an integer, float, boolean, or datetime.
+fn is_bare_value_char(b: u8) -> bool {
+    matches!(b, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'+' | b'.' | b':')
+}
+
+/// `YYYY-MM-DD`
+fn is_full_date(s: &[u8]) -> bool {
+    s.len() == 10
+        && s.iter().enumerate().all(|(i, b)| {
+            if i == 4 || i == 7 {
+                *b == b'-'
+            } else {
+                b.is_ascii_digit()
+            }
+        })
+}
+
+/// Classifies an unquoted value by its shape.
+/// Whether the value is well-formed (ex. no leading zeros, valid dates) is decided when it is
+/// parsed.
+fn classify_bare_value(s: &[u8]) -> Option<TokenKind> {
+    if s.eq_ignore_ascii_case(b"true") || s.eq_ignore_ascii_case(b"false") {
+        return Some(TokenKind::Boolean);
+    }
+    let is_digit = |idx: usize| s.get(idx).is_some_and(u8::is_ascii_digit);
+    if (is_digit(0) && is_digit(1) && is_digit(2) && is_digit(3) && s.get(4) == Some(&b'-'))
+        || (is_digit(0) && is_digit(1) && s.get(2) == Some(&b':'))
+    {
+        return Some(TokenKind::Datetime);
+    }
+    let unsigned = match s {
+        [b'+' | b'-', rest @ ..] => rest,
+        _ => s,
+    };
+    match unsigned {
+        b"inf" | b"nan" => Some(TokenKind::Float),
+        [b'0', b'x' | b'o' | b'b', rest @ ..] => rest
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'_')
+            .then_some(TokenKind::Integer),
+        [b'0'..=b'9', ..] if unsigned.iter().all(|b| b.is_ascii_digit() || *b == b'_') => {
+            Some(TokenKind::Integer)
+        }
+        [b'0'..=b'9', ..] => unsigned
+            .iter()
+            .all(|b| matches!(b, b'0'..=b'9' | b'_' | b'.' | b'e' | b'E' | b'+' | b'-'))
+            .then_some(TokenKind::Float),
+        [b'.', ..] => Some(TokenKind::Float),
+        _ => None,
+    }
+}
+
+/// Control characters other than tab are not allowed to appear literally in strings.
+/// Non-ASCII bytes are always part of a valid code point since the source is a `&str`.
+fn is_valid_str_byte(b: u8) -> bool {
+    b == b'\t' || (b >= 0x20 && b != 0x7F)
+}
+
+fn is_valid_comment_byte(b: u8) -> bool {
+    is_valid_str_byte(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, Lexer, Mode, TokenKind};
+
+    fn kinds(src: &str, mode: Mode) -> Result<Vec<TokenKind>, String> {
+        let mut lexer = Lexer::new(src);
+        let mut out = Vec::new();
+        while let Some(tok) = lexer.next_token(mode)? {
+            out.push(tok.kind);
+        }
+        Ok(out)
+    }
+
+    #[test]
+    fn keys() -> Result<(), String> {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("a.\"b\" . 'c' = # x\n", Mode::Key)?,
+            vec![
+                BareKey, Dot, QuotedKey, Whitespace, Dot, Whitespace, QuotedKey, Whitespace,
+                Equals, Whitespace, Comment, Newline
+            ]
+        );
+        assert_eq!(
+            kinds("[[x]]", Mode::Key)?,
+            vec![DoubleBracketOpen, BareKey, DoubleBracketClose]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn values() -> Result<(), String> {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("[1.5,{}, 1979-05-27 07:32:00]\r\n", Mode::Value)?,
+            vec![
+                BracketOpen,
+                Float,
+                Comma,
+                BraceOpen,
+                BraceClose,
+                Comma,
+                Whitespace,
+                Datetime,
+                BracketClose,
+                Newline
+            ]
+        );
+        assert_eq!(
+            kinds("0x_ff 1_000 -inf 1e3 true 07:32:00", Mode::Value)?,
+            vec![
+                Integer, Whitespace, Integer, Whitespace, Float, Whitespace, Float, Whitespace,
+                Boolean, Whitespace, Datetime
+            ]
+        );
+        assert!(kinds("yes", Mode::Value).is_err());
+        assert_eq!(kinds("FALSE", Mode::Value)?, vec![Boolean]);
+        let mut lexer = Lexer::new("1979-05-27 # date");
+        let tok = lexer.next_token(Mode::Value)?.unwrap();
+        assert_eq!(lexer.slice(tok.span), "1979-05-27");
+        Ok(())
+    }
+
+    #[test]
+    fn strings() -> Result<(), String> {
+        use TokenKind::*;
+        let src = "\"a\\\"b\" '''x\n''''' \"\"\"\"q\"\"\"\"";
+        assert_eq!(
+            kinds(src, Mode::Value)?,
+            vec![BasicStr, Whitespace, MultiLitStr, Whitespace, MultiStr]
+        );
+        assert!(kinds("\"unterminated\n\"", Mode::Value).is_err());
+        assert!(kinds("'''a''''''", Mode::Value).is_err());
+        assert!(kinds("\"\u{7}\"", Mode::Value).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn code_points_not_graphemes() -> Result<(), String> {
+        // A combining mark directly after the closing quote must not hide the delimiter.
+        let mut lexer = Lexer::new("\"e\"\u{301}");
+        let tok = lexer.next_token(Mode::Value)?.unwrap();
+        assert_eq!(tok.kind, TokenKind::BasicStr);
+        assert_eq!(lexer.slice(tok.span), "\"e\"");
+        assert!(lexer.next_token(Mode::Value).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn line_tracking() -> Result<(), String> {
+        let mut lexer = Lexer::new("a\n\"\"\"\n\n\"\"\"\nb");
+        while let Some(tok) = lexer.next_token(Mode::Key)? {
+            if lexer.slice(tok.span.clone()) == "b" {
+                assert_eq!(tok.line, 5);
+            }
+        }
+        assert_eq!(lexer.line(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn token_stream() {
+        use TokenKind::*;
+        let src = "[[a]]\n1 = [1, {b = 'x'}]\n\"k\" = 2\n";
+        let toks: Vec<_> = tokenize(src).collect::<Result<_, _>>().unwrap();
+        let kinds: Vec<TokenKind> = toks.iter().map(|tok| tok.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DoubleBracketOpen,
+                BareKey,
+                DoubleBracketClose,
+                Newline,
+                BareKey,
+                Whitespace,
+                Equals,
+                Whitespace,
+                BracketOpen,
+                Integer,
+                Comma,
+                Whitespace,
+                BraceOpen,
+                BareKey,
+                Whitespace,
+                Equals,
+                Whitespace,
+                LitStr,
+                BraceClose,
+                BracketClose,
+                Newline,
+                QuotedKey,
+                Whitespace,
+                Equals,
+                Whitespace,
+                Integer,
+                Newline
+            ]
+        );
+        // Spans cover the whole source.
+        let joined: String = toks.iter().map(|tok| tok.text(src)).collect();
+        assert_eq!(joined, src);
+        assert_eq!(toks.last().unwrap().line, 3);
+    }
+
+    #[test]
+    fn token_stream_recovery() {
+        let results: Vec<_> = tokenize("a = ?\nb = 1\n").collect();
+        assert_eq!(results.iter().filter(|tok| tok.is_err()).count(), 1);
+        let last = results
+            .iter()
+            .rev()
+            .find_map(|tok| tok.as_ref().ok())
+            .unwrap();
+        assert_eq!((last.kind, last.line), (TokenKind::Newline, 2));
+
+        // the line and span of tokens after an unterminated string
+        let source = "a = \"b\nc = 1\n";
+        let results: Vec<_> = tokenize(source).collect();
+        assert_eq!(results.iter().filter(|tok| tok.is_err()).count(), 1);
+        let tokens: Vec<_> = results.into_iter().filter_map(Result::ok).collect();
+        let newline = tokens.iter().position(|tok| tok.kind == TokenKind::Newline);
+        let after = &tokens[newline.unwrap()..];
+        assert_eq!((after[0].span.clone(), after[0].line), (6..7, 1));
+        assert_eq!((after[1].kind, after[1].line), (TokenKind::BareKey, 2));
+        assert_eq!(after[1].text(source), "c");
+        let integer = after.iter().find(|tok| tok.kind == TokenKind::Integer);
+        assert_eq!(
+            integer.map(|tok| (tok.span.clone(), tok.line)),
+            Some((11..12, 2))
+        );
+    }
+}
@@ -1,18 +1,50 @@
 // Module Declarations
-mod constants; // Characters of Interest
+#[macro_use]
+mod trace; // Optional debug tracing through `log` or `tracing`
+mod datetime; // TOML dates and times
+#[cfg(feature = "serde")]
+mod de; // Deserializing Rust types from documents
+mod diagnostics; // Warnings about valid but suspicious documents
+pub mod diff; // Structural differences between documents
+mod env; // Configuration from environment variables
+mod error; // Parse errors and warnings
+mod events; // Streaming parse events
+mod format; // Rewriting documents in a standard layout
+mod json; // Reading and writing JSON
+pub mod lexer; // Splits TOML source text into tokens
+mod limits; // Resource limits for untrusted input
+pub mod lint; // Policy checks with configurable rules
+mod merge; // Combining documents in layers
+mod options; // Settings shared by the entry points
 mod parsedtoml; // The completely-parsed TOML table.
 mod parsetools; // Tools that make the parsing operation easier for me to think about
+pub mod schema; // Validating documents against a schema
+mod sort; // Sorting the keys of chosen tables
+mod span; // Source locations of parsed keys and values
+pub mod syntax; // A lossless syntax tree
 mod tomlparse; // The TOML parser
 mod tomltypes; // Rust representations of TOML types
 
 // Imports
-pub use parsedtoml::ParsedTOML;
-use tomlparse::TOMLParser;
-pub use tomltypes::{TOMLTable, TOMLType, ValFromTOMLKey};
+pub use datetime::{Date, Datetime, DatetimeKind, Offset, Time};
+#[cfg(feature = "serde")]
+pub use de::from_table;
+pub use env::EnvOptions;
+pub use error::{ErrorKind, Note, ParseError, Warning, WarningKind};
+pub use events::{events, Event, Events};
+pub use format::{format, FormatOptions};
+pub use limits::ParseLimits;
+pub use merge::{Conflict, Layered, MergeOptions, Strategy};
+pub use options::{ParseOptions, Spec};
+pub use parsedtoml::{ParsedTOML, ParsedTOMLRef};
+pub use sort::{sort, SortOptions};
+pub use span::{Literal, Radix, Span};
+pub use tomltypes::{TOMLTable, TOMLTableRef, TOMLType, TOMLTypeRef, ValFromTOMLKey};
 
 /// The interface to the TOML parser.
 /// Takes a string slice representing either an absolute path or a path relative to the current working directory.
 /// The file must have extension `.toml`.
+/// See [`ParseOptions`] for other settings.
 ///
 /// Ex. Given some TOML:
 ///
@@ -45,11 +77,11 @@ pub use tomltypes::{TOMLTable, TOMLType, ValFromTOMLKey};
 ///
 /// Parse via
 ///
-/// ```
+/// ```no_run
 /// use tomlp::parse;
 /// let result = parse("test.toml")?;
 /// println!("{}", result);
-/// Ok(())
+/// # Ok::<(), tomlp::ParseError>(())
 /// ```
 ///
 /// This results in:
@@ -83,8 +115,102 @@ pub use tomltypes::{TOMLTable, TOMLType, ValFromTOMLKey};
 ///     └── chrono
 ///         └── 0.4.38
 /// ```
-pub fn parse(file: &str) -> Result<ParsedTOML, String> {
-    let mut parser = TOMLParser::init(file)?;
-    let table = parser.parse_toml()?;
-    Ok(ParsedTOML::from(table))
+pub fn parse(file: &str) -> Result<ParsedTOML, ParseError> {
+    ParseOptions::new().parse(file)
+}
+
+/// Parses TOML source text that is already in memory.
+///
+/// ```
+/// let parsed = tomlp::parse_str("title = 'TOML'\n[owner]\nname = 'Tom'\n")?;
+/// println!("{}", parsed);
+/// # Ok::<(), tomlp::ParseError>(())
+/// ```
+pub fn parse_str(source: &str) -> Result<ParsedTOML, ParseError> {
+    ParseOptions::new().parse_str(source)
+}
+
+/// Parses the given file like [`parse`], also recording where each key and value was found.
+///
+/// ```no_run
+/// let parsed = tomlp::parse_with_spans("config.toml")?;
+/// if let Some(span) = parsed.span_of("server.timeout") {
+///     // ex. "config.toml:42:11"
+///     println!("`timeout` at {} must be positive", span);
+/// }
+/// # Ok::<(), tomlp::ParseError>(())
+/// ```
+pub fn parse_with_spans(file: &str) -> Result<ParsedTOML, ParseError> {
+    ParseOptions::new().spans(true).parse(file)
+}
+
+/// Parses the given file like [`parse`], also reporting what is valid but likely a mistake.
+/// See [`ParseOptions::diagnostics`].
+///
+/// ```no_run
+/// let (parsed, warnings) = tomlp::parse_with_warnings("config.toml")?;
+/// for warning in &warnings {
+///     // ex. "Line 12: Key `Port` differs from `port` (line 3) only by case."
+///     eprintln!("warning: {}", warning);
+/// }
+/// println!("{}", parsed);
+/// # Ok::<(), tomlp::ParseError>(())
+/// ```
+pub fn parse_with_warnings(file: &str) -> Result<(ParsedTOML, Vec<Warning>), ParseError> {
+    let mut parsed = ParseOptions::new().diagnostics(true).parse(file)?;
+    let warnings = parsed.take_warnings();
+    Ok((parsed, warnings))
+}
+
+/// Parses the given file like [`parse`], keeping integers beyond the range of `i64` (ex. 64-bit
+/// hashes) as [`TOMLType::WideInt`] instead of rejecting them.
+///
+/// ```no_run
+/// use tomlp::ValFromTOMLKey;
+///
+/// let parsed = tomlp::parse_with_wide_integers("objects.toml")?;
+/// if let Some(hash) = parsed.retrieve("object\0hash", "\0").and_then(|val| val.u64()) {
+///     println!("{:016x}", hash);
+/// }
+/// # Ok::<(), tomlp::ParseError>(())
+/// ```
+pub fn parse_with_wide_integers(file: &str) -> Result<ParsedTOML, ParseError> {
+    ParseOptions::new().wide_integers(true).parse(file)
+}
+
+/// Parses the given file like [`parse`], rejecting documents that exceed `limits`.
+///
+/// Oversized files are rejected without being read in full.
+///
+/// ```no_run
+/// use tomlp::{ErrorKind, ParseLimits};
+///
+/// let limits = ParseLimits {
+///     max_input_bytes: 64 * 1024,
+///     ..ParseLimits::default()
+/// };
+/// match tomlp::parse_with_limits("upload.toml", limits) {
+///     Ok(parsed) => println!("{}", parsed),
+///     Err(err) if err.kind == ErrorKind::LimitExceeded => println!("rejected: {}", err),
+///     Err(err) => return Err(err),
+/// }
+/// # Ok::<(), tomlp::ParseError>(())
+/// ```
+pub fn parse_with_limits(file: &str, limits: ParseLimits) -> Result<ParsedTOML, ParseError> {
+    ParseOptions::new().limits(limits).parse(file)
+}
+
+/// Parses TOML source text, borrowing keys and strings from `source` whenever no escape
+/// processing was needed.
+///
+/// ```no_run
+/// let source = std::fs::read_to_string("dump.toml").map_err(|err| err.to_string())?;
+/// let parsed = tomlp::parse_borrowed(&source)?;
+/// if let Some(name) = parsed.retrieve("package\0name", "\0").and_then(|val| val.str()) {
+///     println!("{}", name);
+/// }
+/// # Ok::<(), String>(())
+/// ```
+pub fn parse_borrowed(source: &str) -> Result<ParsedTOMLRef<'_>, ParseError> {
+    ParseOptions::new().parse_borrowed(source)
 }
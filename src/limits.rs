@@ -0,0 +1,50 @@
+/// Bounds on the resources a document may use while being parsed, for accepting untrusted
+/// input. Exceeding any of them produces an error of kind
+/// [`ErrorKind::LimitExceeded`](crate::ErrorKind::LimitExceeded).
+///
+/// The default only bounds the nesting depth, which keeps deeply-nested documents from
+/// overflowing the stack.
+///
+/// ```
+/// use tomlp::ParseLimits;
+/// let limits = ParseLimits {
+///     max_input_bytes: 1024 * 1024,
+///     max_string_len: 4096,
+///     ..ParseLimits::default()
+/// };
+/// # assert_eq!(limits.max_depth, ParseLimits::default().max_depth);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Size of the whole document.
+    pub max_input_bytes: usize,
+    /// Nesting of tables, dotted keys, arrays, and inline tables (ex. `a.b = [[1]]` has a
+    /// depth of 4).
+    pub max_depth: usize,
+    /// Elements of an array or an array of tables.
+    pub max_array_len: usize,
+    /// Keys of a single table.
+    pub max_table_keys: usize,
+    /// Size of a string or key as written, delimiters included.
+    pub max_string_len: usize,
+}
+impl ParseLimits {
+    /// No limits at all, other than available memory and stack.
+    pub const fn none() -> Self {
+        Self {
+            max_input_bytes: usize::MAX,
+            max_depth: usize::MAX,
+            max_array_len: usize::MAX,
+            max_table_keys: usize::MAX,
+            max_string_len: usize::MAX,
+        }
+    }
+}
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            ..Self::none()
+        }
+    }
+}
@@ -0,0 +1,689 @@
+//! Policy checks for documents that are valid but don't follow a house style.
+//!
+//! [`check`] runs each [`Rule`] at the [`Severity`] its [`LintOptions`] give it, reporting a
+//! [`Finding`] for each violation. [`to_json`] and [`to_sarif`] write findings for other tools.
+//!
+//! ```
+//! use tomlp::lint::{self, LintOptions, Rule, Severity};
+//!
+//! let options = LintOptions::from_toml("[lint]\nunnecessary-quotes = 'error'\n")?;
+//! let findings = lint::check("\"name\" = 'x'\ntags = ['a', 'b', 'a']\n", &options)?;
+//! assert_eq!(findings.len(), 2);
+//! assert_eq!(findings[0].rule, Rule::UnnecessaryQuotes);
+//! assert_eq!(findings[0].severity, Severity::Error);
+//! assert_eq!(
+//!     findings[1].to_string(),
+//!     "Line 2: warning: `a` is already in the array (line 2). [duplicate-array-values]"
+//! );
+//! # Ok::<(), String>(())
+//! ```
+
+// stdlib imports
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+// my imports
+use crate::json::Json;
+use crate::lexer::{is_bare_key, TokenKind};
+use crate::span::dotted_key;
+use crate::syntax::{Content, Document, Key, Trivia, Value};
+use crate::tomltypes::{TOMLTable, TOMLType};
+
+/// A named check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Rule {
+    /// An inline table nested within more than [`LintOptions::max_inline_depth`] inline
+    /// tables, counting itself (ex. `a = { b = { c = 1 } }` nests `b` 2 deep).
+    InlineTableDepth,
+    /// A quoted key that could be bare (ex. `"name"`).
+    UnnecessaryQuotes,
+    /// A table defined by dotted keys that aren't written together (ex. `a.x = 1`, then
+    /// `b = 2`, then `a.y = 3`), or that a table header later adds to.
+    SplitDottedTable,
+    /// A string that appears earlier in the same array.
+    DuplicateArrayValues,
+    /// A multi-line string (basic or literal) whose contents are a single line.
+    MultilineString,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::InlineTableDepth,
+        Rule::UnnecessaryQuotes,
+        Rule::SplitDottedTable,
+        Rule::DuplicateArrayValues,
+        Rule::MultilineString,
+    ];
+
+    /// The name of the rule in configuration and output (ex. `unnecessary-quotes`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::InlineTableDepth => "inline-table-depth",
+            Rule::UnnecessaryQuotes => "unnecessary-quotes",
+            Rule::SplitDottedTable => "split-dotted-table",
+            Rule::DuplicateArrayValues => "duplicate-array-values",
+            Rule::MultilineString => "multiline-string",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    /// A one-line summary of the rule.
+    pub fn description(self) -> &'static str {
+        match self {
+            Rule::InlineTableDepth => "Inline tables should not be nested too deeply.",
+            Rule::UnnecessaryQuotes => "Keys should be quoted only when they must be.",
+            Rule::SplitDottedTable => {
+                "Tables defined by dotted keys should be defined in one place."
+            }
+            Rule::DuplicateArrayValues => "Arrays of strings should not repeat a value.",
+            Rule::MultilineString => "Multi-line strings should hold more than one line.",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How much a finding matters, in increasing order. Rules that are `Off` aren't checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Off,
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// The name of the severity in configuration and output (ex. `warning`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Off => "off",
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Severity> {
+        [
+            Severity::Off,
+            Severity::Note,
+            Severity::Warning,
+            Severity::Error,
+        ]
+        .into_iter()
+        .find(|severity| severity.name() == name)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Settings for [`check`]. Every rule is a warning unless `severities` says otherwise.
+///
+/// ```
+/// use tomlp::lint::{LintOptions, Rule, Severity};
+/// let options = LintOptions {
+///     severities: [(Rule::MultilineString, Severity::Off)].into(),
+///     ..LintOptions::default()
+/// };
+/// # assert_eq!(options.severity(Rule::MultilineString), Severity::Off);
+/// # assert_eq!(options.severity(Rule::UnnecessaryQuotes), Severity::Warning);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintOptions {
+    /// The severity of each rule that isn't a warning.
+    pub severities: HashMap<Rule, Severity>,
+    /// How deeply inline tables may nest; see [`Rule::InlineTableDepth`]. 0 forbids inline
+    /// tables altogether.
+    pub max_inline_depth: usize,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            severities: HashMap::new(),
+            max_inline_depth: 1,
+        }
+    }
+}
+
+impl LintOptions {
+    pub fn severity(&self, rule: Rule) -> Severity {
+        self.severities
+            .get(&rule)
+            .copied()
+            .unwrap_or(Severity::Warning)
+    }
+
+    /// Reads settings from the `[lint]` table of a configuration document, leaving the rest
+    /// at their defaults. Each key is the name of a rule, and its value is either a severity
+    /// (`off`, `note`, `warning`, or `error`) or a table with a `severity` and the rule's
+    /// settings. Only `inline-table-depth` has a setting, `max-depth`.
+    ///
+    /// ```
+    /// use tomlp::lint::{LintOptions, Rule, Severity};
+    ///
+    /// let options = LintOptions::from_toml(
+    ///     r#"
+    ///     [lint]
+    ///     unnecessary-quotes = "off"
+    ///     inline-table-depth = { severity = "error", max-depth = 2 }
+    ///     "#,
+    /// )?;
+    /// assert_eq!(options.severity(Rule::UnnecessaryQuotes), Severity::Off);
+    /// assert_eq!(options.severity(Rule::InlineTableDepth), Severity::Error);
+    /// assert_eq!(options.max_inline_depth, 2);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn from_toml(source: &str) -> Result<LintOptions, String> {
+        let parsed = crate::parse_str(source)?;
+        let mut options = LintOptions::default();
+        let table = match parsed.table().get("lint") {
+            None => return Ok(options),
+            Some(val) => table_of(val).ok_or("Invalid setting `lint`: expected a table.")?,
+        };
+        for (name, val) in table {
+            let error = |field: &str, reason: &str| {
+                let mut path = vec!["lint", name.as_str()];
+                if !field.is_empty() {
+                    path.push(field);
+                }
+                format!("Invalid setting `{}`: {}.", dotted_key(&path), reason)
+            };
+            let rule = Rule::from_name(name).ok_or_else(|| error("", "unknown rule"))?;
+            let severity = |val: &TOMLType, field: &str| {
+                val.str()
+                    .and_then(Severity::from_name)
+                    .ok_or_else(|| error(field, "expected `off`, `note`, `warning`, or `error`"))
+            };
+            let Some(settings) = table_of(val) else {
+                options.severities.insert(rule, severity(val, "")?);
+                continue;
+            };
+            for (field, val) in settings {
+                match (rule, field.as_str()) {
+                    (_, "severity") => {
+                        options.severities.insert(rule, severity(val, field)?);
+                    }
+                    (Rule::InlineTableDepth, "max-depth") => {
+                        options.max_inline_depth = val
+                            .u64()
+                            .and_then(|n| usize::try_from(n).ok())
+                            .ok_or_else(|| error(field, "expected a non-negative integer"))?;
+                    }
+                    _ => return Err(error(field, "unknown setting")),
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    /// Reads settings from a configuration file. See [`from_toml`](Self::from_toml).
+    pub fn load<P: AsRef<Path>>(file: P) -> Result<LintOptions, String> {
+        let source = std::fs::read_to_string(file)
+            .map_err(|err| format!("File Read Error: {}", err.kind()))?;
+        Self::from_toml(&source)
+    }
+}
+
+/// A violation of a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// The (1-based) line the finding refers to.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Line {}: {}: {} [{}]",
+            self.line, self.severity, self.message, self.rule
+        )
+    }
+}
+
+/// The findings for one file, for [`to_json`] and [`to_sarif`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub file: String,
+    pub findings: Vec<Finding>,
+}
+
+/// Checks a document against the rules that `options` turns on, returning the findings in
+/// line order. Invalid documents are an error.
+pub fn check(source: &str, options: &LintOptions) -> Result<Vec<Finding>, String> {
+    crate::parse_str(source)?;
+    let doc = Document::parse(source)?;
+    let mut linter = Linter {
+        options,
+        findings: Vec::new(),
+        line: 1,
+        dotted: HashMap::new(),
+        split: HashSet::new(),
+        entry: 0,
+    };
+    linter.document(&doc);
+    linter.findings.sort_by_key(|finding| finding.line);
+    Ok(linter.findings)
+}
+
+/// Writes findings as a JSON array with an object for each finding, with the fields `file`,
+/// `line`, `rule`, `severity`, and `message`.
+///
+/// ```
+/// use tomlp::lint::{self, LintOptions, Report};
+///
+/// let findings = lint::check("'a' = 1", &LintOptions::default())?;
+/// let reports = [Report { file: "a.toml".to_string(), findings }];
+/// assert_eq!(
+///     lint::to_json(&reports),
+///     r#"[
+///   {
+///     "file": "a.toml",
+///     "line": 1,
+///     "rule": "unnecessary-quotes",
+///     "severity": "warning",
+///     "message": "Key `'a'` doesn't need quotes."
+///   }
+/// ]
+/// "#
+/// );
+/// # Ok::<(), String>(())
+/// ```
+pub fn to_json(reports: &[Report]) -> String {
+    let findings = reports
+        .iter()
+        .flat_map(|report| {
+            report.findings.iter().map(|finding| {
+                Json::Object(vec![
+                    field("file", Json::String(report.file.clone())),
+                    field("line", Json::Integer(finding.line as i128)),
+                    field("rule", Json::String(finding.rule.name().to_string())),
+                    field(
+                        "severity",
+                        Json::String(finding.severity.name().to_string()),
+                    ),
+                    field("message", Json::String(finding.message.clone())),
+                ])
+            })
+        })
+        .collect();
+    Json::Array(findings).pretty() + "\n"
+}
+
+/// Writes findings as a [SARIF](https://sarifweb.azurewebsites.net/) 2.1.0 log, which code
+/// scanning services read. Every rule is described in the log, and each finding is a result
+/// located at its file and line.
+pub fn to_sarif(reports: &[Report]) -> String {
+    let rules = Rule::ALL
+        .iter()
+        .map(|rule| {
+            Json::Object(vec![
+                field("id", Json::String(rule.name().to_string())),
+                field("shortDescription", text(rule.description())),
+            ])
+        })
+        .collect();
+    let driver = Json::Object(vec![
+        field("name", Json::String("tomlp".to_string())),
+        field(
+            "version",
+            Json::String(env!("CARGO_PKG_VERSION").to_string()),
+        ),
+        field("rules", Json::Array(rules)),
+    ]);
+    let results = reports
+        .iter()
+        .flat_map(|report| {
+            report.findings.iter().map(|finding| {
+                let index = Rule::ALL.iter().position(|rule| *rule == finding.rule);
+                let region = Json::Object(vec![field(
+                    "startLine",
+                    Json::Integer(finding.line as i128),
+                )]);
+                let artifact = Json::Object(vec![field("uri", Json::String(report.file.clone()))]);
+                let location = Json::Object(vec![field(
+                    "physicalLocation",
+                    Json::Object(vec![
+                        field("artifactLocation", artifact),
+                        field("region", region),
+                    ]),
+                )]);
+                Json::Object(vec![
+                    field("ruleId", Json::String(finding.rule.name().to_string())),
+                    field(
+                        "ruleIndex",
+                        Json::Integer(index.unwrap_or_default() as i128),
+                    ),
+                    field("level", Json::String(finding.severity.name().to_string())),
+                    field("message", text(&finding.message)),
+                    field("locations", Json::Array(vec![location])),
+                ])
+            })
+        })
+        .collect();
+    let run = Json::Object(vec![
+        field("tool", Json::Object(vec![field("driver", driver)])),
+        field("results", Json::Array(results)),
+    ]);
+    let log = Json::Object(vec![
+        field(
+            "$schema",
+            Json::String("https://json.schemastore.org/sarif-2.1.0.json".to_string()),
+        ),
+        field("version", Json::String("2.1.0".to_string())),
+        field("runs", Json::Array(vec![run])),
+    ]);
+    log.pretty() + "\n"
+}
+
+struct Linter<'o> {
+    options: &'o LintOptions,
+    findings: Vec<Finding>,
+    /// The line being read
+    line: usize,
+    /// Tables defined by dotted keys, with the last entry (and its line) that added to each
+    dotted: HashMap<Vec<String>, (usize, usize)>,
+    /// Tables defined by dotted keys that were already reported as split
+    split: HashSet<Vec<String>>,
+    /// The number of headers and key/value lines read
+    entry: usize,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: Rule, line: usize, message: String) {
+        let severity = self.options.severity(rule);
+        if severity != Severity::Off {
+            self.findings.push(Finding {
+                rule,
+                severity,
+                line,
+                message,
+            });
+        }
+    }
+
+    fn document(&mut self, doc: &Document) {
+        let mut table = Vec::new();
+        for line in &doc.lines {
+            match &line.content {
+                Content::Empty => (),
+                Content::Header(header) => {
+                    self.key(&header.key);
+                    table = header.key.names().unwrap_or_default();
+                    self.header(&table, header.array);
+                }
+                Content::KeyValue(kv) => {
+                    self.key(&kv.key);
+                    let mut path = table.clone();
+                    path.extend(kv.key.names().unwrap_or_default());
+                    self.dotted_key(&path[..path.len() - 1], table.len());
+                    self.value(&kv.value, 0);
+                }
+            }
+            self.line += line.newline.matches('\n').count();
+        }
+    }
+
+    /// Checks a header for adding to a table defined by dotted keys. A `[[...]]` header
+    /// starts a new table, so the dotted keys of the one before are forgotten.
+    fn header(&mut self, path: &[String], array: bool) {
+        self.entry += 1;
+        for len in 1..path.len() {
+            let table = &path[..len];
+            if let Some(&(_, line)) = self.dotted.get(table) {
+                if self.split.insert(table.to_vec()) {
+                    let message = format!(
+                        "Header `[{}]` adds to table `{}`, which is defined by dotted keys on line {}.",
+                        dotted_key(path),
+                        dotted_key(table),
+                        line
+                    );
+                    self.report(Rule::SplitDottedTable, self.line, message);
+                }
+            }
+        }
+        if array {
+            self.dotted.retain(|table, _| !table.starts_with(path));
+            self.split.retain(|table| !table.starts_with(path));
+        }
+    }
+
+    /// Records the tables a dotted key defines (the parts of `path` past `base`), checking
+    /// that each continues from the entry before.
+    fn dotted_key(&mut self, path: &[String], base: usize) {
+        self.entry += 1;
+        for len in base + 1..=path.len() {
+            let table = &path[..len];
+            if let Some(&(entry, line)) = self.dotted.get(table) {
+                if entry + 1 != self.entry && self.split.insert(table.to_vec()) {
+                    let message = format!(
+                        "Table `{}` continues here, apart from its other dotted keys (last on line {}).",
+                        dotted_key(table),
+                        line
+                    );
+                    self.report(Rule::SplitDottedTable, self.line, message);
+                }
+            }
+            self.dotted.insert(table.to_vec(), (self.entry, self.line));
+        }
+    }
+
+    fn key(&mut self, key: &Key) {
+        for part in &key.parts {
+            let quoted = part.text.starts_with(['"', '\'']);
+            if quoted && part.name().is_ok_and(|name| is_bare_key(&name)) {
+                let message = format!("Key `{}` doesn't need quotes.", part.text);
+                self.report(Rule::UnnecessaryQuotes, self.line, message);
+            }
+        }
+    }
+
+    /// Checks a value nested within `depth` inline tables.
+    fn value(&mut self, val: &Value, depth: usize) {
+        match val {
+            Value::Scalar { kind, text } => {
+                if matches!(kind, TokenKind::MultiStr | TokenKind::MultiLitStr)
+                    && val
+                        .string()
+                        .is_ok_and(|string| string.is_some_and(|s| !s.contains('\n')))
+                {
+                    let message = "Multi-line string holds a single line of text.".to_string();
+                    self.report(Rule::MultilineString, self.line, message);
+                }
+                self.line += text.matches('\n').count();
+            }
+            Value::Array(array) => {
+                let mut seen: HashMap<String, usize> = HashMap::new();
+                for item in &array.items {
+                    self.trivia(&item.leading);
+                    if let Ok(Some(string)) = item.value.string() {
+                        match seen.get(&string) {
+                            Some(first) => {
+                                let message = format!(
+                                    "`{}` is already in the array (line {}).",
+                                    string, first
+                                );
+                                self.report(Rule::DuplicateArrayValues, self.line, message);
+                            }
+                            None => {
+                                seen.insert(string, self.line);
+                            }
+                        }
+                    }
+                    self.value(&item.value, depth);
+                    self.trivia(&item.before_comma);
+                }
+                self.trivia(&array.trailing);
+            }
+            Value::InlineTable(table) => {
+                let depth = depth + 1;
+                if depth == self.options.max_inline_depth + 1 {
+                    let message = format!(
+                        "Inline table is nested {} deep, more than {}.",
+                        depth, self.options.max_inline_depth
+                    );
+                    self.report(Rule::InlineTableDepth, self.line, message);
+                }
+                for item in &table.items {
+                    self.trivia(&item.leading);
+                    self.key(&item.value.key);
+                    self.value(&item.value.value, depth);
+                    self.trivia(&item.before_comma);
+                }
+                self.trivia(&table.trailing);
+            }
+        }
+    }
+
+    fn trivia(&mut self, trivia: &[Trivia]) {
+        self.line += trivia
+            .iter()
+            .filter(|trivia| matches!(trivia, Trivia::Newline(_)))
+            .count();
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+fn field(name: &str, val: Json) -> (String, Json) {
+    (name.to_string(), val)
+}
+
+/// A SARIF message.
+fn text(text: &str) -> Json {
+    Json::Object(vec![field("text", Json::String(text.to_string()))])
+}
+
+fn table_of(val: &TOMLType) -> Option<&TOMLTable> {
+    match val {
+        TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table) => {
+            Some(table)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, to_json, to_sarif, Finding, LintOptions, Report, Rule, Severity};
+    use crate::json::Json;
+
+    fn found(source: &str, options: &LintOptions) -> Result<Vec<(Rule, usize)>, String> {
+        let findings = check(source, options)?;
+        Ok(findings
+            .iter()
+            .map(|finding| (finding.rule, finding.line))
+            .collect())
+    }
+
+    #[test]
+    fn rules() -> Result<(), String> {
+        use Rule::*;
+        let options = LintOptions::default();
+        let source = "\
+\"a\" = 1
+'b c'.\"d\" = { e = 1 }
+f = { g = { h = 1 } }
+x.y = 1
+z = 2
+x.w = [
+  'p',
+  \"q\",
+  'p',
+  { 'i' = { j = 1 } },
+]
+s = \"\"\"
+one line\"\"\"
+m = '''
+two
+lines'''
+[x.v]
+";
+        assert_eq!(
+            found(source, &options)?,
+            [
+                (UnnecessaryQuotes, 1),
+                (UnnecessaryQuotes, 2),
+                (InlineTableDepth, 3),
+                (SplitDottedTable, 6),
+                (DuplicateArrayValues, 9),
+                (UnnecessaryQuotes, 10),
+                (InlineTableDepth, 10),
+                (MultilineString, 12),
+            ]
+        );
+
+        let options = LintOptions {
+            severities: [(UnnecessaryQuotes, Severity::Off)].into(),
+            max_inline_depth: 0,
+        };
+        let source = "a.b = 1\n[a.c]\nd = { e = 1 }\n";
+        assert_eq!(
+            found(source, &options)?,
+            [(SplitDottedTable, 2), (InlineTableDepth, 3)]
+        );
+        assert!(check("a = 1\na = 2", &options).is_err());
+
+        // each table of an array of tables has its own dotted keys
+        let source = "[[bin]]\nopt.x = 1\nname = 'a'\n[[bin]]\nopt.x = 2\n[[bin]]\nopt.x = 3\nname = 'c'\nopt.y = 3\n";
+        assert_eq!(found(source, &options)?, [(SplitDottedTable, 9)]);
+        Ok(())
+    }
+
+    #[test]
+    fn config() {
+        assert_eq!(
+            LintOptions::from_toml("[lint]\nsome-rule = 'error'").unwrap_err(),
+            "Invalid setting `lint.some-rule`: unknown rule."
+        );
+        assert_eq!(
+            LintOptions::from_toml("[lint]\nmultiline-string = 'loud'").unwrap_err(),
+            "Invalid setting `lint.multiline-string`: expected `off`, `note`, `warning`, or `error`."
+        );
+        assert_eq!(
+            LintOptions::from_toml("[lint]\nmultiline-string = { max-depth = 1 }").unwrap_err(),
+            "Invalid setting `lint.multiline-string.max-depth`: unknown setting."
+        );
+    }
+
+    #[test]
+    fn sarif() {
+        let finding = Finding {
+            rule: Rule::MultilineString,
+            severity: Severity::Note,
+            line: 3,
+            message: "Multi-line string holds a single line of text.".to_string(),
+        };
+        let reports = [Report {
+            file: "a.toml".to_string(),
+            findings: vec![finding],
+        }];
+        let sarif = to_sarif(&reports);
+        let json = Json::parse(&sarif).unwrap();
+        let result = json.pointer("/runs/0/results/0").unwrap();
+        // SARIF requires integers
+        assert_eq!(result.get("ruleIndex"), Some(&Json::Integer(4)));
+        assert_eq!(result.get("level").and_then(|l| l.as_str()), Some("note"));
+        let line = result.pointer("/locations/0/physicalLocation/region/startLine");
+        assert_eq!(line, Some(&Json::Integer(3)));
+        assert!(to_json(&reports).contains(r#""line": 3,"#));
+    }
+}
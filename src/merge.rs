@@ -0,0 +1,521 @@
+//! Combining documents in layers, each overriding the ones before it.
+
+// stdlib imports
+use std::collections::HashMap;
+// my imports
+use crate::diff::Node;
+use crate::lexer::{Lexer, Mode};
+use crate::parsedtoml::ParsedTOML;
+use crate::span::{child_path, dotted_key};
+use crate::tomltypes::{TOMLTable, TOMLType, ValFromTOMLKey};
+
+/// How a value is combined with the value of the same key in an earlier layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Strategy {
+    /// The later value replaces the earlier one, even if both are tables.
+    Override,
+    /// Tables are merged key by key, recursively, and other values are overridden. The
+    /// default.
+    Merge,
+    /// Arrays are concatenated, and other values are merged.
+    Append,
+    /// The tables of arrays of tables that share the value of a key (ex. `name`) are merged,
+    /// and the other tables are appended. Other values are merged. As in
+    /// [`DiffOptions::aot_key`](crate::diff::DiffOptions::aot_key), if some table lacks the
+    /// key, or two tables of an array share a value of it, the arrays are appended instead.
+    MergeByKey(String),
+}
+
+/// Settings for merging documents.
+///
+/// ```
+/// use tomlp::{MergeOptions, Strategy, ValFromTOMLKey};
+///
+/// let mut base = tomlp::parse_str("[server]\nhosts = ['a']\nport = 80\n[log]\nlevel = 'info'\nfile = 'x.log'")?;
+/// let site = tomlp::parse_str("[server]\nhosts = ['b']\n[log]\nlevel = 'debug'")?;
+/// let options = MergeOptions {
+///     strategies: vec![
+///         ("server.hosts".to_string(), Strategy::Append),
+///         ("log".to_string(), Strategy::Override),
+///     ],
+/// };
+/// assert!(base.merge(site, &options).is_empty());
+/// let hosts = base.retrieve("server.hosts", ".").and_then(|hosts| hosts.array());
+/// let hosts: Vec<_> = hosts.into_iter().flatten().filter_map(|host| host.str()).collect();
+/// assert_eq!(hosts, ["a", "b"]);
+/// assert!(base.retrieve("server.port", ".").is_some());
+/// assert!(base.retrieve("log.file", ".").is_none());
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MergeOptions {
+    /// The strategies for chosen keys, as dotted keys in which `*` matches any one key (ex.
+    /// `servers.*.tags`). Array indices aren't part of these keys, so the keys of a table
+    /// within an array of tables are written under the array's key (ex. `bin."ci.yml"`). The
+    /// first that matches a key is used, and keys matching none are merged with
+    /// [`Strategy::Merge`]. Keys that aren't valid TOML match nothing.
+    pub strategies: Vec<(String, Strategy)>,
+}
+
+impl MergeOptions {
+    fn strategy(&self, keys: &[String]) -> &Strategy {
+        self.strategies
+            .iter()
+            .find(|(pattern, _)| {
+                pattern_keys(pattern).is_some_and(|pattern| {
+                    pattern.len() == keys.len()
+                        && pattern
+                            .iter()
+                            .zip(keys)
+                            .all(|(seg, key)| seg.as_ref().is_none_or(|seg| seg == key))
+                })
+            })
+            .map_or(&Strategy::Merge, |(_, strategy)| strategy)
+    }
+}
+
+/// Reads a pattern of [`MergeOptions::strategies`] as a dotted key, with `None` for each `*`
+/// segment, or returns `None` if it isn't one.
+fn pattern_keys(pattern: &str) -> Option<Vec<Option<String>>> {
+    let mut keys = Vec::new();
+    let mut rest = pattern;
+    loop {
+        rest = rest.trim_start_matches([' ', '\t']);
+        // `*` isn't a key character, so it's read here rather than by the lexer
+        if let Some(after) = rest.strip_prefix('*') {
+            keys.push(None);
+            rest = after;
+        } else {
+            let tok = Lexer::new(rest).next_token(Mode::Key).ok()??;
+            keys.push(Some(ParsedTOML::key_path(&rest[..tok.span.end])?));
+            rest = &rest[tok.span.end..];
+        }
+        match rest.trim_start_matches([' ', '\t']).strip_prefix('.') {
+            Some(after) => rest = after,
+            None => break,
+        }
+    }
+    rest.trim().is_empty().then_some(keys)
+}
+
+/// A value that replaced one of a different type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The path of the value, written as a TOML key with array elements addressed by their
+    /// index, as for [`ParsedTOML::span_of`].
+    pub path: String,
+    /// The type that was replaced, as named by [`Node::type_name`] (ex. `integer`).
+    pub old_type: &'static str,
+    /// The type that replaced it.
+    pub new_type: &'static str,
+    /// The layer the new value came from, when merged by [`Layered`].
+    pub source: Option<String>,
+}
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(source) = &self.source {
+            write!(f, "{}: ", source)?;
+        }
+        write!(
+            f,
+            "`{}`: {} replaced by {}",
+            self.path, self.old_type, self.new_type
+        )
+    }
+}
+
+/// Configuration merged from layers (ex. `defaults.toml`, then `site.toml`, then
+/// `local.toml`), each taking precedence over the ones before it, which records the layer
+/// that supplied each value.
+///
+/// ```
+/// use tomlp::{Layered, MergeOptions, ValFromTOMLKey};
+///
+/// let mut config = Layered::new(MergeOptions::default());
+/// config
+///     .add("defaults.toml", tomlp::parse_str("[server]\nhost = 'localhost'\nport = 80")?)
+///     .add("local.toml", tomlp::parse_str("server.port = '8080'")?);
+/// assert_eq!(config.retrieve("server.host", ".").and_then(|host| host.str()), Some("localhost"));
+/// assert_eq!(config.source_of("server.host"), Some("defaults.toml"));
+/// assert_eq!(config.source_of("server.port"), Some("local.toml"));
+/// assert_eq!(
+///     config.conflicts()[0].to_string(),
+///     "local.toml: `server.port`: integer replaced by string"
+/// );
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Layered {
+    options: MergeOptions,
+    table: TOMLTable,
+    layers: Vec<String>,
+    /// The layer that supplied each value, by `\0`-delimited path
+    sources: HashMap<String, usize>,
+    conflicts: Vec<Conflict>,
+}
+impl Layered {
+    pub fn new(options: MergeOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Parses `file` and merges it over the layers so far.
+    pub fn add_file(&mut self, file: &str) -> Result<&mut Self, String> {
+        let parsed = crate::parse(file).map_err(|err| format!("{}: {}", file, err))?;
+        Ok(self.add(file, parsed))
+    }
+
+    /// Merges a parsed document over the layers so far, naming the layer `name`.
+    pub fn add(&mut self, name: &str, parsed: ParsedTOML) -> &mut Self {
+        let table = parsed.into_table();
+        let layer = self.layers.len();
+        self.layers.push(name.to_string());
+        let conflicts = merge(
+            &mut self.table,
+            table,
+            &self.options,
+            Some((&mut self.sources, layer)),
+        );
+        self.conflicts
+            .extend(conflicts.into_iter().map(|conflict| Conflict {
+                source: Some(name.to_string()),
+                ..conflict
+            }));
+        self
+    }
+
+    /// The merged top-level table.
+    pub fn table(&self) -> &TOMLTable {
+        &self.table
+    }
+
+    /// The names of the layers, in the order they were added.
+    pub fn layers(&self) -> &[String] {
+        &self.layers
+    }
+
+    /// The name of the layer that supplied the value at the given path, written as for
+    /// [`ParsedTOML::span_of`]. For tables, and arrays with elements from several layers,
+    /// that's the last layer that added to them.
+    pub fn source_of(&self, path: &str) -> Option<&str> {
+        let layer = self.sources.get(&ParsedTOML::key_path(path)?)?;
+        Some(&self.layers[*layer])
+    }
+
+    /// The values that replaced one of a different type, in the order they were merged.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+}
+impl ValFromTOMLKey for Layered {
+    fn retrieve(&self, key_sequence: &str, delimiter: &str) -> Option<&TOMLType> {
+        self.table.retrieve(key_sequence, delimiter)
+    }
+}
+
+/// Merges `layer` over `base`, recording the layer of each value it sets in `sources`.
+pub(crate) fn merge(
+    base: &mut TOMLTable,
+    layer: TOMLTable,
+    options: &MergeOptions,
+    sources: Option<(&mut HashMap<String, usize>, usize)>,
+) -> Vec<Conflict> {
+    let mut merger = Merger {
+        options,
+        keys: Vec::new(),
+        path: Vec::new(),
+        conflicts: Vec::new(),
+        sources,
+    };
+    merger.tables(base, layer);
+    merger.conflicts
+}
+
+struct Merger<'a> {
+    options: &'a MergeOptions,
+    /// The keys down to the current value, for choosing its strategy
+    keys: Vec<String>,
+    /// The keys and array indices down to the current value
+    path: Vec<String>,
+    conflicts: Vec<Conflict>,
+    sources: Option<(&'a mut HashMap<String, usize>, usize)>,
+}
+
+impl Merger<'_> {
+    fn tables(&mut self, base: &mut TOMLTable, layer: TOMLTable) {
+        let mut entries: Vec<(String, TOMLType)> = layer.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, val) in entries {
+            self.keys.push(key.clone());
+            self.path.push(key.clone());
+            match base.get_mut(&key) {
+                Some(old) => self.value(old, val),
+                None => {
+                    self.record(&val);
+                    base.insert(key, val);
+                }
+            }
+            self.keys.pop();
+            self.path.pop();
+        }
+    }
+
+    fn value(&mut self, base: &mut TOMLType, layer: TOMLType) {
+        let (old_type, new_type) = (
+            Node::Value(base).type_name(),
+            Node::Value(&layer).type_name(),
+        );
+        if old_type != new_type {
+            self.conflicts.push(Conflict {
+                path: dotted_key(&self.path),
+                old_type,
+                new_type,
+                source: None,
+            });
+            return self.replace(base, layer);
+        }
+        let options = self.options;
+        match (options.strategy(&self.keys), base, layer) {
+            (Strategy::Override, base, layer) => self.replace(base, layer),
+            (
+                _,
+                TOMLType::HTable(base) | TOMLType::DKTable(base) | TOMLType::InlineTable(base),
+                TOMLType::HTable(layer) | TOMLType::DKTable(layer) | TOMLType::InlineTable(layer),
+            ) => {
+                self.touch();
+                self.tables(base, layer);
+            }
+            (Strategy::Append, base, layer @ (TOMLType::Array(_) | TOMLType::AoT(_))) => {
+                self.append(base, layer, None)
+            }
+            (Strategy::MergeByKey(key), base, layer @ (TOMLType::Array(_) | TOMLType::AoT(_))) => {
+                self.append(base, layer, Some(key))
+            }
+            (_, base, layer) => self.replace(base, layer),
+        }
+    }
+
+    /// Appends the elements of the array `layer` to the array `base`, first merging any
+    /// tables that share the value of `key` with one in `base`.
+    fn append(&mut self, base: &mut TOMLType, layer: TOMLType, key: Option<&str>) {
+        self.touch();
+        let aot = matches!(base, TOMLType::AoT(_));
+        let mut elements = into_elements(std::mem::replace(base, TOMLType::Array(Vec::new())));
+        let added = into_elements(layer);
+        let ids = key.and_then(|key| Some((ids(&elements, key)?, ids(&added, key)?)));
+        for (i, val) in added.into_iter().enumerate() {
+            let matched = ids
+                .as_ref()
+                .and_then(|(old_ids, new_ids)| old_ids.iter().position(|id| *id == new_ids[i]));
+            match matched {
+                Some(j) => {
+                    self.path.push(j.to_string());
+                    self.value(&mut elements[j], val);
+                }
+                None => {
+                    self.path.push(elements.len().to_string());
+                    self.record(&val);
+                    elements.push(val);
+                }
+            }
+            self.path.pop();
+        }
+        *base = from_elements(elements, aot);
+    }
+
+    fn replace(&mut self, base: &mut TOMLType, layer: TOMLType) {
+        if let Some((sources, _)) = &mut self.sources {
+            let prefix = format!("{}\0", self.path.join("\0"));
+            sources.retain(|path, _| !path.starts_with(&prefix));
+        }
+        self.record(&layer);
+        *base = layer;
+    }
+
+    /// Records that the current layer supplied `val`, and everything within it.
+    fn record(&mut self, val: &TOMLType) {
+        if let Some((sources, layer)) = &mut self.sources {
+            record(sources, self.path.join("\0"), Node::Value(val), *layer);
+        }
+    }
+
+    /// Records that the current layer added to the current table or array.
+    fn touch(&mut self) {
+        if let Some((sources, layer)) = &mut self.sources {
+            sources.insert(self.path.join("\0"), *layer);
+        }
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+fn record(sources: &mut HashMap<String, usize>, path: String, node: Node<'_>, layer: usize) {
+    if let Some(table) = node.table() {
+        for (key, val) in table {
+            record(sources, child_path(&path, key), Node::Value(val), layer);
+        }
+    }
+    if let Some(elements) = node.elements() {
+        for (i, element) in elements.into_iter().enumerate() {
+            record(sources, child_path(&path, &i.to_string()), element, layer);
+        }
+    }
+    sources.insert(path, layer);
+}
+
+/// The values of `key` in the tables of `elements`, written out, if each element is a table
+/// with a value of it that none of the others share.
+fn ids(elements: &[TOMLType], key: &str) -> Option<Vec<String>> {
+    let mut ids: Vec<String> = Vec::new();
+    for element in elements {
+        let id = Node::Value(Node::Value(element).table()?.get(key)?).to_string();
+        if ids.contains(&id) {
+            return None;
+        }
+        ids.push(id);
+    }
+    Some(ids)
+}
+
+/// The elements of an array of either kind, with the tables of an array of tables as inline
+/// tables.
+fn into_elements(val: TOMLType) -> Vec<TOMLType> {
+    match val {
+        TOMLType::Array(items) => items,
+        TOMLType::AoT(tables) => tables.into_iter().map(TOMLType::InlineTable).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// An array of `elements`, which is an array of tables if `aot` and the elements are all
+/// tables.
+fn from_elements(elements: Vec<TOMLType>, aot: bool) -> TOMLType {
+    if !aot
+        || elements
+            .iter()
+            .any(|element| Node::Value(element).table().is_none())
+    {
+        return TOMLType::Array(elements);
+    }
+    let tables = elements.into_iter().filter_map(|element| match element {
+        TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table) => {
+            Some(table)
+        }
+        _ => None,
+    });
+    TOMLType::AoT(tables.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pattern_keys, Layered, MergeOptions, Strategy};
+    use crate::diff::{self, DiffOptions};
+
+    /// Merges `layers` in order, checking the result against `expected`.
+    fn check(layers: &[&str], options: &MergeOptions, expected: &str) -> Result<(), String> {
+        let mut merged = crate::parse_str(layers[0])?;
+        for layer in &layers[1..] {
+            let conflicts = merged.merge(crate::parse_str(layer)?, options);
+            assert!(conflicts.is_empty(), "{:?}", conflicts);
+        }
+        let expected = crate::parse_str(expected)?;
+        let changes = diff::compare(merged.table(), expected.table(), &DiffOptions::default());
+        let changes: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+        assert!(changes.is_empty(), "{:#?}", changes);
+        Ok(())
+    }
+
+    #[test]
+    fn strategies() -> Result<(), String> {
+        let base = "a = [1]\nb = [1]\n[c]\nd = 1\ne = 1\n[f]\ng = 1\n[[bin]]\nname = 'x'\npath = 'x.rs'\n[[bin]]\nname = 'y'\n";
+        let layer = "a = [2]\nb = [2]\nc.d = 2\nf = { h = 2 }\nbin = [{ name = 'y', path = 'y.rs' }, { name = 'z' }]\n";
+        check(
+            &[base, layer],
+            &MergeOptions::default(),
+            "a = [2]\nb = [2]\nc = { d = 2, e = 1 }\nf = { g = 1, h = 2 }\nbin = [{ name = 'y', path = 'y.rs' }, { name = 'z' }]\n",
+        )?;
+
+        let options = MergeOptions {
+            strategies: vec![
+                ("a".to_string(), Strategy::Append),
+                ("f".to_string(), Strategy::Override),
+                ("*".to_string(), Strategy::MergeByKey("name".to_string())),
+            ],
+        };
+        check(
+            &[base, layer, "bin = [{ name = 'x', test = true }]"],
+            &options,
+            "a = [1, 2]\nb = [1, 2]\nc = { d = 2, e = 1 }\nf = { h = 2 }\n[[bin]]\nname = 'x'\npath = 'x.rs'\ntest = true\n[[bin]]\nname = 'y'\npath = 'y.rs'\n[[bin]]\nname = 'z'\n",
+        )?;
+        // without a name for each table, they're appended
+        check(
+            &[base, "bin = [{ path = 'z.rs' }]"],
+            &options,
+            "bin = [{ name = 'x', path = 'x.rs' }, { name = 'y' }, { path = 'z.rs' }]\na = [1]\nb = [1]\nc = { d = 1, e = 1 }\nf = { g = 1 }\n",
+        )?;
+
+        // quoted keys may hold dots, and `*` within quotes is only a key
+        let options = MergeOptions {
+            strategies: vec![
+                ("a . \"b.c\"".to_string(), Strategy::Append),
+                ("'*'.x".to_string(), Strategy::Append),
+                ("a.b.c".to_string(), Strategy::Override),
+            ],
+        };
+        check(
+            &["a.\"b.c\" = [1]\na.b.c = { x = 1 }\n'*'.x = [1]\ny.x = [1]", "a.\"b.c\" = [2]\na.b.c = { y = 2 }\n'*'.x = [2]\ny.x = [2]"],
+            &options,
+            "a = { \"b.c\" = [1, 2], b = { c = { y = 2 } } }\n\"*\" = { x = [1, 2] }\ny = { x = [2] }\n",
+        )?;
+        assert_eq!(
+            options.strategy(&["b".to_string(), "c".to_string()]),
+            &Strategy::Merge
+        );
+        assert_eq!(pattern_keys("a.*"), Some(vec![Some("a".to_string()), None]));
+        assert_eq!(pattern_keys("a..b"), None);
+        assert_eq!(pattern_keys("a b"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn layered() -> Result<(), String> {
+        let options = MergeOptions {
+            strategies: vec![("servers".to_string(), Strategy::Append)],
+        };
+        let mut config = Layered::new(options);
+        config
+            .add(
+                "defaults",
+                crate::parse_str(
+                    "port = 80\n[log]\nlevel = 'info'\nfile = 'a.log'\n[[servers]]\nhost = 'a'",
+                )?,
+            )
+            .add(
+                "site",
+                crate::parse_str("port = '80'\nlog = 'off'\n[[servers]]\nhost = 'b'")?,
+            )
+            .add("local", crate::parse_str("log.level = 'debug'")?);
+        assert_eq!(config.layers(), ["defaults", "site", "local"]);
+        assert_eq!(config.source_of("servers.0.host"), Some("defaults"));
+        assert_eq!(config.source_of("servers.1.host"), Some("site"));
+        assert_eq!(config.source_of("servers"), Some("site"));
+        assert_eq!(config.source_of("port"), Some("site"));
+        assert_eq!(config.source_of("log.level"), Some("local"));
+        assert_eq!(config.source_of("log.file"), None);
+        assert_eq!(config.source_of("missing"), None);
+        let conflicts: Vec<String> = config.conflicts().iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            conflicts,
+            [
+                "site: `log`: table replaced by string",
+                "site: `port`: integer replaced by string",
+                "local: `log`: string replaced by table",
+            ]
+        );
+        Ok(())
+    }
+}
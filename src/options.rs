@@ -0,0 +1,220 @@
+// stdlib imports
+use std::io::BufRead;
+// my imports
+use super::diagnostics;
+use super::error::ParseError;
+use super::events::{events_with, Events};
+use super::limits::ParseLimits;
+use super::parsedtoml::{ParsedTOML, ParsedTOMLRef};
+use super::tomlparse::TOMLParser;
+
+/// A version of the TOML specification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Spec {
+    /// [TOML v1.0.0](https://toml.io/en/v1.0.0)
+    #[default]
+    V1_0,
+}
+
+/// Settings for a parse, shared by every entry point.
+///
+/// The free functions ([`parse`](crate::parse), [`parse_str`](crate::parse_str), ...) are
+/// shorthands for the most common settings; build a `ParseOptions` to combine them.
+///
+/// ```no_run
+/// use tomlp::{ParseLimits, ParseOptions, Spec::V1_0};
+///
+/// let options = ParseOptions::new()
+///     .spec(V1_0)
+///     .require_toml_extension(false)
+///     .preserve_order(true)
+///     .spans(true)
+///     .limits(ParseLimits::default());
+/// let parsed = options.parse("Config.lock")?;
+/// println!("{}", parsed); // keys printed in the order they were written
+/// # Ok::<(), tomlp::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    spec: Spec,
+    require_toml_extension: bool,
+    preserve_order: bool,
+    spans: bool,
+    limits: ParseLimits,
+    wide_integers: bool,
+    lenient: bool,
+    diagnostics: bool,
+    max_line_length: usize,
+}
+impl ParseOptions {
+    /// The settings used by [`parse`](crate::parse).
+    pub fn new() -> Self {
+        Self {
+            spec: Spec::V1_0,
+            require_toml_extension: true,
+            preserve_order: false,
+            spans: false,
+            limits: ParseLimits::default(),
+            wide_integers: false,
+            lenient: false,
+            diagnostics: false,
+            max_line_length: 120,
+        }
+    }
+
+    /// The version of the specification documents are parsed against.
+    pub fn spec(mut self, spec: Spec) -> Self {
+        self.spec = spec;
+        self
+    }
+
+    /// Whether files must have the `.toml` extension. On by default.
+    pub fn require_toml_extension(mut self, require: bool) -> Self {
+        self.require_toml_extension = require;
+        self
+    }
+
+    /// Remember the order keys were written in, for [`ParsedTOML::keys`] and the tree printout.
+    /// This records where each key was found, as with [`spans`](Self::spans).
+    pub fn preserve_order(mut self, preserve: bool) -> Self {
+        self.preserve_order = preserve;
+        self
+    }
+
+    /// Record where each key and value was found. See [`ParsedTOML::span_of`].
+    pub fn spans(mut self, spans: bool) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Resource limits for untrusted input. See [`ParseLimits`].
+    pub fn limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Keep integers beyond the range of `i64` as [`TOMLType::WideInt`](crate::TOMLType::WideInt)
+    /// instead of rejecting them.
+    pub fn wide_integers(mut self, wide: bool) -> Self {
+        self.wide_integers = wide;
+        self
+    }
+
+    /// Accept newlines and trailing commas in inline tables and booleans in any case (ex.
+    /// `True`), reporting each through [`ParsedTOML::warnings`] instead of failing.
+    /// Off by default, and not applied to [`events`](Self::events).
+    ///
+    /// ```
+    /// use tomlp::{ParseOptions, TOMLType, ValFromTOMLKey};
+    /// let parsed = ParseOptions::new()
+    ///     .lenient(true)
+    ///     .parse_str("point = { x = 1,\n  y = 2, }\nvisible = True\n")?;
+    /// assert!(matches!(parsed.retrieve("visible", "."), Some(TOMLType::Bool(true))));
+    /// for warning in parsed.warnings() {
+    ///     // ex. "Line 3: Boolean `True` should be lowercase."
+    ///     println!("{}", warning);
+    /// }
+    /// # assert_eq!(parsed.warnings().len(), 3);
+    /// # Ok::<(), tomlp::ParseError>(())
+    /// ```
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Check for things that are valid but likely a mistake, reporting them through
+    /// [`ParsedTOML::warnings`]: keys that differ only by case, keys that are quoted in one
+    /// place but bare in another, empty keys, indentation mixing tabs and spaces, and long lines.
+    /// Off by default. See [`parse_with_warnings`](crate::parse_with_warnings).
+    pub fn diagnostics(mut self, diagnostics: bool) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// The length, in characters, beyond which lines are reported by
+    /// [`diagnostics`](Self::diagnostics). Defaults to 120.
+    pub fn max_line_length(mut self, len: usize) -> Self {
+        self.max_line_length = len;
+        self
+    }
+
+    /// Parses the file at the given path.
+    /// Oversized files are rejected without being read in full.
+    pub fn parse(&self, file: &str) -> Result<ParsedTOML, ParseError> {
+        let source = TOMLParser::read_file_limited(
+            file,
+            self.limits.max_input_bytes,
+            self.require_toml_extension,
+        )?;
+        self.parse_source(&source, Some(file))
+    }
+
+    /// Parses TOML source text that is already in memory.
+    pub fn parse_str(&self, source: &str) -> Result<ParsedTOML, ParseError> {
+        self.parse_source(source, None)
+    }
+
+    /// Parses TOML source text, borrowing keys and strings from `source` where possible.
+    /// Key order, spans, and [`diagnostics`](Self::diagnostics) are not available; see
+    /// [`ParsedTOMLRef::into_owned`].
+    pub fn parse_borrowed<'src>(
+        &self,
+        source: &'src str,
+    ) -> Result<ParsedTOMLRef<'src>, ParseError> {
+        let mut parser = self
+            .configure(TOMLParser::new(source))
+            .with_lenient(self.lenient);
+        match parser.parse_toml() {
+            Ok(table) => Ok(ParsedTOMLRef::from(table).with_warnings(parser.take_warnings())),
+            Err(msg) => Err(parser.error(msg)),
+        }
+    }
+
+    /// Reads a TOML document as a sequence of [`Event`](crate::Event)s.
+    /// See [`events`](crate::events).
+    pub fn events<R: BufRead>(&self, reader: R) -> Events<R> {
+        events_with(reader, self.clone())
+    }
+
+    fn parse_source(&self, source: &str, file: Option<&str>) -> Result<ParsedTOML, ParseError> {
+        let mut parser = self
+            .configure(TOMLParser::new(source))
+            .with_lenient(self.lenient);
+        let keep_spans = self.spans || self.preserve_order;
+        if keep_spans || self.diagnostics {
+            parser = parser.with_spans(file);
+        }
+        let table = match parser.parse_toml() {
+            Ok(table) => table,
+            Err(msg) => return Err(parser.error(msg)),
+        };
+        let spans = parser.take_spans();
+        let mut warnings = parser.take_warnings();
+        if let (true, Some(spans)) = (self.diagnostics, &spans) {
+            warnings.extend(diagnostics::check(
+                source,
+                &table,
+                spans,
+                self.max_line_length,
+            ));
+            warnings.sort_by_key(|warning| warning.line);
+        }
+        let spans = spans.filter(|_| keep_spans);
+        Ok(ParsedTOML::with_spans(table, spans).with_warnings(warnings))
+    }
+
+    /// Applies the settings that affect how items are parsed.
+    pub(crate) fn configure<'a>(&self, parser: TOMLParser<'a>) -> TOMLParser<'a> {
+        match self.spec {
+            Spec::V1_0 => parser
+                .with_limits(self.limits)
+                .with_wide_integers(self.wide_integers),
+        }
+    }
+}
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,25 +1,204 @@
 #![allow(unused_variables, unused_imports)]
 use crate::tomltypes::{TOMLType, ValFromTOMLKey};
 
-use super::tomltypes::TOMLTable;
+use super::error::Warning;
+use super::merge::{merge, Conflict, MergeOptions};
+use super::span::{child_path, Literal, Located, Span, SpanMap};
+use super::tomlparse::TOMLParser;
+use super::tomltypes::{retrieve_ref, table_into_owned, TOMLTable, TOMLTableRef, TOMLTypeRef};
 /// The Rust representation of the complete read-only TOML table.
 #[derive(Debug)]
 pub struct ParsedTOML {
     table: TOMLTable,
+    spans: Option<SpanMap>,
+    warnings: Vec<Warning>,
 }
 impl ParsedTOML {
-    pub(super) fn from(table: TOMLTable) -> Self {
-        Self { table }
+    pub(super) fn from(table: TOMLTableRef<'_>) -> Self {
+        Self::with_spans(table, None)
+    }
+
+    pub(super) fn with_spans(table: TOMLTableRef<'_>, spans: Option<SpanMap>) -> Self {
+        Self {
+            table: table_into_owned(table),
+            spans,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_table(table: TOMLTable) -> Self {
+        Self {
+            table,
+            spans: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(super) fn with_warnings(mut self, warnings: Vec<Warning>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// What was accepted despite being invalid, when parsed with
+    /// [`ParseOptions::lenient`](crate::ParseOptions::lenient), and what looks like a mistake,
+    /// when parsed with [`ParseOptions::diagnostics`](crate::ParseOptions::diagnostics).
+    /// Ordered by line.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Moves the warnings out, leaving none behind.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// The top-level table.
+    pub fn table(&self) -> &TOMLTable {
+        &self.table
+    }
+
+    /// The location of the value at the given path.
+    /// Only available when parsed via [`parse_with_spans`](crate::parse_with_spans) or with
+    /// [`ParseOptions::spans`](crate::ParseOptions::spans).
+    ///
+    /// The path is written as a TOML key (ex. `servers."127.0.0.1".port`), with array elements
+    /// addressed by their index (ex. `products.0.name`).
+    /// Tables created implicitly by a dotted key are located at that key.
+    pub fn span_of(&self, path: &str) -> Option<&Span> {
+        self.located(path)?.value.as_ref()
+    }
+
+    /// The location of the key at the given path. Array elements have no key.
+    /// See [`span_of`](Self::span_of).
+    pub fn key_span_of(&self, path: &str) -> Option<&Span> {
+        self.located(path)?.key.as_ref()
+    }
+
+    /// The literal of the scalar value at the given path, as it was written in the source.
+    /// Recorded alongside spans; see [`span_of`](Self::span_of).
+    ///
+    /// ```no_run
+    /// use tomlp::Radix;
+    /// let parsed = tomlp::parse_with_spans("config.toml")?;
+    /// if let Some(literal) = parsed.literal_of("permissions") {
+    ///     assert_eq!(literal.radix(), Some(Radix::Octal), "{} should be octal", literal.raw);
+    /// }
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn literal_of(&self, path: &str) -> Option<&Literal> {
+        self.located(path)?.literal.as_ref()
+    }
+
+    /// The keys of the table at the given path, or of the top-level table if `path` is empty.
+    /// Paths are written as for [`span_of`](Self::span_of).
+    ///
+    /// Keys are listed in the order they were written when parsed with
+    /// [`ParseOptions::preserve_order`](crate::ParseOptions::preserve_order) or spans, and in
+    /// no particular order otherwise.
+    ///
+    /// ```
+    /// use tomlp::ParseOptions;
+    /// let parsed = ParseOptions::new()
+    ///     .preserve_order(true)
+    ///     .parse_str("[server]\nport = 80\nhost = 'localhost'\n")?;
+    /// assert_eq!(parsed.keys("server"), Some(vec!["port", "host"]));
+    /// # Ok::<(), tomlp::ParseError>(())
+    /// ```
+    pub fn keys(&self, path: &str) -> Option<Vec<&str>> {
+        let path = if path.trim().is_empty() {
+            String::new()
+        } else {
+            Self::key_path(path)?
+        };
+        let table = self.table_at(&path)?;
+        let entries = self.ordered(table, &path);
+        Some(entries.into_iter().map(|(key, _)| key.as_str()).collect())
+    }
+
+    /// Merges `other` over this document, with `other` taking precedence, and returns the
+    /// values that replaced one of a different type, which they do whatever the strategy.
+    ///
+    /// The merged document has no spans, since its values come from both documents; use a
+    /// [`Layered`](crate::Layered) to find out where each value came from.
+    pub fn merge(&mut self, other: ParsedTOML, options: &MergeOptions) -> Vec<Conflict> {
+        self.spans = None;
+        self.warnings.extend(other.warnings);
+        merge(&mut self.table, other.table, options, None)
+    }
+
+    pub(crate) fn into_table(self) -> TOMLTable {
+        self.table
+    }
+
+    fn located(&self, path: &str) -> Option<&Located> {
+        self.spans.as_ref()?.get(&Self::key_path(path)?)
+    }
+
+    /// Converts a path written as a TOML key to a `\0`-delimited one.
+    pub(crate) fn key_path(path: &str) -> Option<String> {
+        let mut parser = TOMLParser::new(path);
+        let key = parser.parse_key().ok()?;
+        if !parser.seek_nonws().ok()? {
+            Some(key.to_string())
+        } else {
+            None
+        }
+    }
+
+    /// The table at the given `\0`-delimited path.
+    fn table_at(&self, path: &str) -> Option<&TOMLTable> {
+        let mut table = &self.table;
+        if path.is_empty() {
+            return Some(table);
+        }
+        let mut segments = path.split('\0');
+        let index = |seg: Option<&str>| seg?.parse::<usize>().ok();
+        while let Some(seg) = segments.next() {
+            table = match table.get(seg)? {
+                TOMLType::HTable(next) | TOMLType::DKTable(next) | TOMLType::InlineTable(next) => {
+                    next
+                }
+                TOMLType::AoT(aot) => aot.get(index(segments.next())?)?,
+                TOMLType::Array(arr) => {
+                    let mut elems = arr;
+                    loop {
+                        match elems.get(index(segments.next())?)? {
+                            TOMLType::Array(inner) => elems = inner,
+                            TOMLType::InlineTable(next) => break next,
+                            _ => return None,
+                        }
+                    }
+                }
+                _ => return None,
+            };
+        }
+        Some(table)
+    }
+
+    /// The entries of `table`, in source order if it is known.
+    fn ordered<'t>(&self, table: &'t TOMLTable, path: &str) -> Vec<(&'t String, &'t TOMLType)> {
+        let mut entries: Vec<_> = table.iter().collect();
+        if let Some(spans) = &self.spans {
+            entries.sort_by_key(|(key, _)| {
+                spans
+                    .get(&child_path(path, key))
+                    .and_then(|located| located.key.as_ref())
+                    .map_or(usize::MAX, |span| span.bytes.start)
+            });
+        }
+        entries
     }
 
     /// A function for recursively descending and printing the TOML table.
     /// Takes inspiration from the `tree` program.
     fn tree(&self) -> String {
         let mut last_key_tracker: Vec<bool> = Vec::new();
-        Self::tree_iter(&self.table, 0, "\n/".to_string(), &mut last_key_tracker)
+        self.tree_iter(&self.table, "", 0, "\n/".to_string(), &mut last_key_tracker)
     }
     fn tree_iter(
+        &self,
         table: &TOMLTable,
+        path: &str,
         level: usize,
         mut outstr: String,
         last_key_tracker: &mut Vec<bool>,
@@ -35,19 +214,20 @@ impl ParsedTOML {
 
         last_key_tracker.push(false);
 
-        let mut key_iter = table.keys().peekable();
+        let mut entry_iter = self.ordered(table, path).into_iter().peekable();
         let mut connector: &str = NONTERMINATING_CONNECTOR;
-        while let Some(key) = key_iter.next() {
-            if let None = key_iter.peek() {
+        while let Some((key, toml_val)) = entry_iter.next() {
+            if entry_iter.peek().is_none() {
                 connector = TERMINATING_CONNECTOR;
-                last_key_tracker[level] = true;
+                if let Some(is_last_key) = last_key_tracker.last_mut() {
+                    *is_last_key = true;
+                }
             }
             outstr.push('\n');
             // print the key
             for lv in 0..level {
-                let is_last_key = *last_key_tracker.get(lv).unwrap();
-                if is_last_key {
-                    outstr.push_str(" ");
+                if last_key_tracker.get(lv) == Some(&true) {
+                    outstr.push(' ');
                 } else {
                     outstr.push_str(VERTICAL_EXTENDER);
                 }
@@ -55,31 +235,31 @@ impl ParsedTOML {
             }
             outstr.push_str(connector);
             outstr.push_str(key.as_str());
+            let path = &child_path(path, key);
             // handle the value
             // First, check for recursive table
-            let toml_val = table.get(key).unwrap();
             if let TOMLType::HTable(ref htable) = toml_val {
-                outstr = Self::tree_iter(htable, level + 1, outstr, last_key_tracker);
+                outstr = self.tree_iter(htable, path, level + 1, outstr, last_key_tracker);
                 continue;
             } else if let TOMLType::DKTable(ref dktable) = toml_val {
-                outstr = Self::tree_iter(dktable, level + 1, outstr, last_key_tracker);
+                outstr = self.tree_iter(dktable, path, level + 1, outstr, last_key_tracker);
                 continue;
             } else if let TOMLType::InlineTable(ref inlinetab) = toml_val {
-                outstr = Self::tree_iter(inlinetab, level + 1, outstr, last_key_tracker);
+                outstr = self.tree_iter(inlinetab, path, level + 1, outstr, last_key_tracker);
                 continue;
             } else if let TOMLType::AoT(ref aot) = toml_val {
                 outstr.push_str(" (Arr_of_Tbls)");
-                for table in aot.iter() {
-                    outstr = Self::tree_iter(table, level + 1, outstr, last_key_tracker);
+                for (i, table) in aot.iter().enumerate() {
+                    let path = &child_path(path, &i.to_string());
+                    outstr = self.tree_iter(table, path, level + 1, outstr, last_key_tracker);
                 }
                 continue;
             }
             // If we reach here, there's a value that we're just labeling instead of expanding.
             outstr.push('\n');
             for lv in 0..level + 1 {
-                let is_last_key = *last_key_tracker.get(lv).unwrap();
-                if is_last_key {
-                    outstr.push_str(" ");
+                if last_key_tracker.get(lv) == Some(&true) {
+                    outstr.push(' ');
                 } else {
                     outstr.push_str(VERTICAL_EXTENDER);
                 }
@@ -94,11 +274,9 @@ impl ParsedTOML {
                 TOMLType::BasicStr(s) => outstr.push_str(s),
                 TOMLType::Bool(bl) => outstr.push_str(bl.to_string().as_str()),
                 TOMLType::Int(i) => outstr.push_str(i.to_string().as_str()),
+                TOMLType::WideInt(i) => outstr.push_str(i.to_string().as_str()),
                 TOMLType::Float(f) => outstr.push_str(f.to_string().as_str()),
-                TOMLType::TimeStamp(dt) => outstr.push_str(dt.to_string().as_str()),
-                TOMLType::NaiveDateTime(dt) => outstr.push_str(dt.to_string().as_str()),
-                TOMLType::Date(dt) => outstr.push_str(dt.to_string().as_str()),
-                TOMLType::Time(dt) => outstr.push_str(dt.to_string().as_str()),
+                TOMLType::Datetime(dt) => outstr.push_str(dt.to_string().as_str()),
                 TOMLType::HTable(_)
                 | TOMLType::DKTable(_)
                 | TOMLType::InlineTable(_)
@@ -106,7 +284,7 @@ impl ParsedTOML {
             }
         }
         last_key_tracker.pop(); // remove this level's boolean
-        assert_eq!(last_key_tracker.len(), level);
+        debug_assert_eq!(last_key_tracker.len(), level);
         outstr
     }
 }
@@ -120,3 +298,44 @@ impl ValFromTOMLKey for ParsedTOML {
         self.table.retrieve(key_sequence, delimiter)
     }
 }
+
+/// A parsed TOML table borrowing its keys and strings from the source text.
+#[derive(Debug)]
+pub struct ParsedTOMLRef<'src> {
+    table: TOMLTableRef<'src>,
+    warnings: Vec<Warning>,
+}
+impl<'src> ParsedTOMLRef<'src> {
+    pub(super) fn from(table: TOMLTableRef<'src>) -> Self {
+        Self {
+            table,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(super) fn with_warnings(mut self, warnings: Vec<Warning>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// See [`ParsedTOML::warnings`].
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The top-level table.
+    pub fn table(&self) -> &TOMLTableRef<'src> {
+        &self.table
+    }
+
+    /// Retrieve a view into the value of a given key-value pair if it exists.
+    /// See [`ValFromTOMLKey`].
+    pub fn retrieve(&self, key_sequence: &str, delimiter: &str) -> Option<&TOMLTypeRef<'src>> {
+        retrieve_ref(&self.table, key_sequence, delimiter)
+    }
+
+    /// Copies any borrowed keys and strings, detaching the table from the source.
+    pub fn into_owned(self) -> ParsedTOML {
+        ParsedTOML::from(self.table).with_warnings(self.warnings)
+    }
+}
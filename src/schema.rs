@@ -0,0 +1,609 @@
+//! Validating parsed documents against a schema.
+//!
+//! A [`Schema`] describes which keys a document must or may have and what their values must
+//! look like. Schemas are built in Rust or loaded from a TOML schema file (see
+//! [`Schema::from_toml`]) or a JSON Schema (see [`Schema::from_json_schema`]); validation
+//! reports every violation at once.
+//!
+//! ```
+//! use tomlp::schema::{Pattern, Schema};
+//!
+//! let schema = Schema::table()
+//!     .required("name", Schema::string().pattern(Pattern::new("^[a-z][a-z0-9-]*$")?))
+//!     .optional("port", Schema::integer().range(1, 65535))
+//!     .optional("mode", Schema::string().one_of(["fast", "safe"]))
+//!     .optional(
+//!         "bin",
+//!         Schema::array_of_tables(Schema::table().required("path", Schema::string())),
+//!     );
+//!
+//! let parsed = tomlp::parse_str("name = 'Web'\nport = 0\n[[bin]]\nname = 'web'\n")?;
+//! let violations = schema.validate(&parsed).unwrap_err();
+//! let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+//! assert_eq!(
+//!     messages,
+//!     [
+//!         "`name`: `Web` does not match the pattern `^[a-z][a-z0-9-]*$`.",
+//!         "`port`: 0 is out of range (1 to 65535).",
+//!         "`bin.0.path`: Missing required key.",
+//!     ]
+//! );
+//! # Ok::<(), String>(())
+//! ```
+
+mod infer;
+mod json;
+mod load;
+mod pattern;
+mod rust;
+mod write;
+
+// my imports
+use super::datetime::DatetimeKind;
+use super::parsedtoml::ParsedTOML;
+use super::span::dotted_key;
+use super::tomltypes::{TOMLTable, TOMLType};
+pub use pattern::Pattern;
+pub use rust::{DatetimeTypes, RustOptions};
+
+/// A description of a TOML value.
+///
+/// Constraints only apply to the type they are named for (ex. [`pattern`](Self::pattern) to
+/// strings) and are ignored on other types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub(crate) kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Kind {
+    Any,
+    String {
+        pattern: Option<Pattern>,
+        one_of: Option<Vec<String>>,
+    },
+    Integer {
+        min: Option<i128>,
+        max: Option<i128>,
+    },
+    Float,
+    Boolean,
+    /// `None` accepts all four kinds.
+    Datetime(Option<DatetimeKind>),
+    /// Also matches arrays of tables.
+    Array(Box<Schema>),
+    Table(TableSchema),
+    /// A JSON Schema document
+    Json(json::JsonSchema),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TableSchema {
+    pub keys: Vec<KeySchema>,
+    pub deny_unknown: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KeySchema {
+    pub name: String,
+    pub schema: Schema,
+    pub required: bool,
+}
+
+/// A way in which a document does not match a [`Schema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Where the violation is, written as for [`ParsedTOML::span_of`] (ex. `bin.0.path`).
+    /// Empty for the top-level table.
+    pub path: String,
+    pub message: String,
+}
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "`{}`: {}", self.path, self.message)
+        }
+    }
+}
+
+impl Schema {
+    ////////////////////////
+    // Creation/Modification
+    ////////////////////////
+    fn of(kind: Kind) -> Self {
+        Self { kind }
+    }
+
+    /// Any value.
+    pub fn any() -> Self {
+        Self::of(Kind::Any)
+    }
+
+    /// Any kind of string.
+    pub fn string() -> Self {
+        Self::of(Kind::String {
+            pattern: None,
+            one_of: None,
+        })
+    }
+
+    pub fn integer() -> Self {
+        Self::of(Kind::Integer {
+            min: None,
+            max: None,
+        })
+    }
+
+    pub fn float() -> Self {
+        Self::of(Kind::Float)
+    }
+
+    pub fn boolean() -> Self {
+        Self::of(Kind::Boolean)
+    }
+
+    /// Any of the four date and time kinds.
+    pub fn datetime() -> Self {
+        Self::of(Kind::Datetime(None))
+    }
+
+    /// A date or time of one kind only (ex. a local date).
+    pub fn datetime_of(kind: DatetimeKind) -> Self {
+        Self::of(Kind::Datetime(Some(kind)))
+    }
+
+    /// An array whose elements all match `items`.
+    pub fn array(items: Schema) -> Self {
+        Self::of(Kind::Array(Box::new(items)))
+    }
+
+    /// An array of tables that each match `item`, written either with `[[key]]` headers or as
+    /// an array of inline tables. The same as [`array`](Self::array).
+    pub fn array_of_tables(item: Schema) -> Self {
+        Self::array(item)
+    }
+
+    /// A table, with no keys required. Unknown keys are allowed unless
+    /// [`deny_unknown_keys`](Self::deny_unknown_keys) is set.
+    pub fn table() -> Self {
+        Self::of(Kind::Table(TableSchema::default()))
+    }
+
+    /// Strings must match `pattern`.
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        if let Kind::String { pattern: slot, .. } = &mut self.kind {
+            *slot = Some(pattern);
+        }
+        self
+    }
+
+    /// Strings must be one of `values`.
+    pub fn one_of<I, S>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if let Kind::String { one_of, .. } = &mut self.kind {
+            *one_of = Some(values.into_iter().map(Into::into).collect());
+        }
+        self
+    }
+
+    /// Integers must be at least `min`.
+    pub fn min(mut self, min: i64) -> Self {
+        if let Kind::Integer { min: slot, .. } = &mut self.kind {
+            *slot = Some(i128::from(min));
+        }
+        self
+    }
+
+    /// Integers must be at most `max`.
+    pub fn max(mut self, max: i64) -> Self {
+        if let Kind::Integer { max: slot, .. } = &mut self.kind {
+            *slot = Some(i128::from(max));
+        }
+        self
+    }
+
+    /// Integers must be within `min` and `max`, inclusive.
+    pub fn range(self, min: i64, max: i64) -> Self {
+        self.min(min).max(max)
+    }
+
+    /// Tables must have `key`, matching `schema`.
+    pub fn required(self, key: &str, schema: Schema) -> Self {
+        self.with_key(key, schema, true)
+    }
+
+    /// If tables have `key`, it must match `schema`.
+    pub fn optional(self, key: &str, schema: Schema) -> Self {
+        self.with_key(key, schema, false)
+    }
+
+    fn with_key(mut self, key: &str, schema: Schema, required: bool) -> Self {
+        if let Kind::Table(table) = &mut self.kind {
+            table.keys.retain(|entry| entry.name != key);
+            table.keys.push(KeySchema {
+                name: key.to_string(),
+                schema,
+                required,
+            });
+        }
+        self
+    }
+
+    /// Tables may only have the keys given through [`required`](Self::required) and
+    /// [`optional`](Self::optional).
+    pub fn deny_unknown_keys(mut self) -> Self {
+        if let Kind::Table(table) = &mut self.kind {
+            table.deny_unknown = true;
+        }
+        self
+    }
+
+    ////////////////////
+    // Validation
+    ////////////////////
+
+    /// Checks a parsed document against this schema, returning every violation found.
+    pub fn validate(&self, parsed: &ParsedTOML) -> Result<(), Vec<Violation>> {
+        self.validate_table(parsed.table())
+    }
+
+    /// Checks a table against this schema. See [`validate`](Self::validate).
+    pub fn validate_table(&self, table: &TOMLTable) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        self.check_table(table, &mut Vec::new(), &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    fn check_table(&self, table: &TOMLTable, path: &mut Vec<String>, out: &mut Vec<Violation>) {
+        let schema = match &self.kind {
+            Kind::Any => return,
+            Kind::Table(schema) => schema,
+            Kind::Json(json) => return json.check_table(table, path, out),
+            _ => return self.mismatch("table", path, out),
+        };
+        for entry in &schema.keys {
+            path.push(entry.name.clone());
+            match table.get(&entry.name) {
+                Some(val) => entry.schema.check_value(val, path, out),
+                None if entry.required => violation(path, "Missing required key.".to_string(), out),
+                None => (),
+            }
+            path.pop();
+        }
+        if schema.deny_unknown {
+            let mut unknown: Vec<&String> = table
+                .keys()
+                .filter(|key| !schema.keys.iter().any(|entry| &entry.name == *key))
+                .collect();
+            unknown.sort();
+            for key in unknown {
+                path.push(key.clone());
+                violation(path, "Unknown key.".to_string(), out);
+                path.pop();
+            }
+        }
+    }
+
+    fn check_value(&self, val: &TOMLType, path: &mut Vec<String>, out: &mut Vec<Violation>) {
+        match (&self.kind, val) {
+            (Kind::Any, _)
+            | (Kind::Float, TOMLType::Float(_))
+            | (Kind::Boolean, TOMLType::Bool(_)) => {}
+            (Kind::Json(json), _) => json.check_value(val, path, out),
+            (Kind::String { pattern, one_of }, _) if val.str().is_some() => {
+                let text = val.str().unwrap_or_default();
+                if let Some(pattern) = pattern.as_ref().filter(|pattern| !pattern.is_match(text)) {
+                    let message = format!("`{}` does not match the pattern `{}`.", text, pattern);
+                    violation(path, message, out);
+                }
+                if let Some(values) = one_of
+                    .as_ref()
+                    .filter(|values| !values.iter().any(|v| v == text))
+                {
+                    let values: Vec<String> = values.iter().map(|v| format!("`{}`", v)).collect();
+                    let message = format!("`{}` is not one of {}.", text, values.join(", "));
+                    violation(path, message, out);
+                }
+            }
+            (Kind::Integer { min, max }, _) if val.i128().is_some() => {
+                let n = val.i128().unwrap_or_default();
+                let message = match (min, max) {
+                    (Some(min), Some(max)) if n < *min || n > *max => {
+                        format!("{} is out of range ({} to {}).", n, min, max)
+                    }
+                    (Some(min), None) if n < *min => {
+                        format!("{} is less than the minimum of {}.", n, min)
+                    }
+                    (None, Some(max)) if n > *max => {
+                        format!("{} is greater than the maximum of {}.", n, max)
+                    }
+                    _ => return,
+                };
+                violation(path, message, out);
+            }
+            (Kind::Datetime(expected), TOMLType::Datetime(dt)) => {
+                if let Some(expected) = expected.filter(|kind| *kind != dt.kind()) {
+                    self.mismatch_kind(&expected.to_string(), &dt.kind().to_string(), path, out);
+                }
+            }
+            (Kind::Array(items), TOMLType::Array(vals)) => {
+                for (i, val) in vals.iter().enumerate() {
+                    path.push(i.to_string());
+                    items.check_value(val, path, out);
+                    path.pop();
+                }
+            }
+            (Kind::Array(items), TOMLType::AoT(tables)) => {
+                for (i, table) in tables.iter().enumerate() {
+                    path.push(i.to_string());
+                    items.check_table(table, path, out);
+                    path.pop();
+                }
+            }
+            (
+                Kind::Table(_),
+                TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table),
+            ) => self.check_table(table, path, out),
+            _ => self.mismatch(&type_name(val), path, out),
+        }
+    }
+
+    fn mismatch(&self, found: &str, path: &[String], out: &mut Vec<Violation>) {
+        self.mismatch_kind(&self.type_name(), found, path, out);
+    }
+
+    fn mismatch_kind(
+        &self,
+        expected: &str,
+        found: &str,
+        path: &[String],
+        out: &mut Vec<Violation>,
+    ) {
+        violation(
+            path,
+            format!("Expected {}, found {}.", a(expected), a(found)),
+            out,
+        );
+    }
+
+    /// The name of the type this schema describes (ex. `"local date"`).
+    pub(crate) fn type_name(&self) -> String {
+        match &self.kind {
+            Kind::Any => "any value".to_string(),
+            Kind::String { .. } => "string".to_string(),
+            Kind::Integer { .. } => "integer".to_string(),
+            Kind::Float => "float".to_string(),
+            Kind::Boolean => "boolean".to_string(),
+            Kind::Datetime(None) => "datetime".to_string(),
+            Kind::Datetime(Some(kind)) => kind.to_string(),
+            Kind::Array(_) => "array".to_string(),
+            Kind::Table(_) => "table".to_string(),
+            Kind::Json(_) => "value matching the JSON Schema".to_string(),
+        }
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+fn violation(path: &[String], message: String, out: &mut Vec<Violation>) {
+    out.push(Violation {
+        path: dotted_key(path),
+        message,
+    });
+}
+
+/// The name of a value's type, as used in violations.
+fn type_name(val: &TOMLType) -> String {
+    match val {
+        TOMLType::Bool(_) => "boolean".to_string(),
+        TOMLType::Int(_) | TOMLType::WideInt(_) => "integer".to_string(),
+        TOMLType::Float(_) => "float".to_string(),
+        TOMLType::BasicStr(_)
+        | TOMLType::MultiStr(_)
+        | TOMLType::LitStr(_)
+        | TOMLType::MultiLitStr(_) => "string".to_string(),
+        TOMLType::Datetime(dt) => dt.kind().to_string(),
+        TOMLType::Array(_) => "array".to_string(),
+        TOMLType::AoT(_) => "array of tables".to_string(),
+        TOMLType::HTable(_) | TOMLType::DKTable(_) | TOMLType::InlineTable(_) => {
+            "table".to_string()
+        }
+    }
+}
+
+/// Prefixes a type name with its article.
+fn a(name: &str) -> String {
+    match name.chars().next() {
+        Some('a' | 'e' | 'i' | 'o' | 'u') => format!("an {}", name),
+        _ => format!("a {}", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Schema, Violation};
+    use crate::DatetimeKind;
+
+    fn violations(schema: &Schema, source: &str) -> Result<Vec<String>, String> {
+        let parsed = crate::parse_str(source)?;
+        let violations = schema.validate(&parsed).err().unwrap_or_default();
+        Ok(violations.iter().map(Violation::to_string).collect())
+    }
+
+    #[test]
+    fn types() -> Result<(), String> {
+        let schema = Schema::table()
+            .required("flag", Schema::boolean())
+            .required("ratio", Schema::float())
+            .required("born", Schema::datetime_of(DatetimeKind::LocalDate))
+            .required("at", Schema::datetime())
+            .required(
+                "grid",
+                Schema::array(Schema::array(Schema::integer().min(0))),
+            )
+            .optional("server", Schema::table().required("host", Schema::string()));
+        let valid = "flag = true\nratio = 0.5\nborn = 1979-05-27\nat = 07:32:00\ngrid = [[1], [2, 3]]\nserver.host = 'x'";
+        assert!(violations(&schema, valid)?.is_empty());
+
+        let invalid = "flag = 'yes'\nratio = 1\nborn = 1979-05-27T07:32:00Z\nat = 1\ngrid = [[1], [-2, 'x'], 3]\n[server]\nhost = { name = 'x' }";
+        assert_eq!(
+            violations(&schema, invalid)?,
+            [
+                "`flag`: Expected a boolean, found a string.",
+                "`ratio`: Expected a float, found an integer.",
+                "`born`: Expected a local date, found an offset date-time.",
+                "`at`: Expected a datetime, found an integer.",
+                "`grid.1.0`: -2 is less than the minimum of 0.",
+                "`grid.1.1`: Expected an integer, found a string.",
+                "`grid.2`: Expected an array, found an integer.",
+                "`server.host`: Expected a string, found a table.",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tables() -> Result<(), String> {
+        let bin = Schema::table()
+            .required("name", Schema::string())
+            .deny_unknown_keys();
+        let schema = Schema::table()
+            .required("bin", Schema::array_of_tables(bin))
+            .required("hash", Schema::integer().max(0))
+            .optional("\"quoted\" key", Schema::string().one_of(["a", "b"]));
+        let source = "hash = 0xffff_ffff_ffff_ffff\n'\"quoted\" key' = 'c'\n[[bin]]\nname = 'a'\n[[bin]]\npath = 'b'\nextra.x = 1\n";
+        let parsed = crate::ParseOptions::new()
+            .wide_integers(true)
+            .parse_str(source)
+            .map_err(String::from)?;
+        let found: Vec<String> = schema
+            .validate(&parsed)
+            .unwrap_err()
+            .iter()
+            .map(Violation::to_string)
+            .collect();
+        assert_eq!(
+            found,
+            [
+                "`bin.1.name`: Missing required key.",
+                "`bin.1.extra`: Unknown key.",
+                "`bin.1.path`: Unknown key.",
+                "`hash`: 18446744073709551615 is greater than the maximum of 0.",
+                "`\"\\\"quoted\\\" key\"`: `c` is not one of `a`, `b`.",
+            ]
+        );
+        // arrays of inline tables are checked like arrays of tables
+        let inline = "hash = 0\nbin = [{ name = 'a' }, { name = 1 }]";
+        assert_eq!(
+            violations(&schema, inline)?,
+            ["`bin.1.name`: Expected a string, found an integer."]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_toml() -> Result<(), String> {
+        let schema = Schema::from_toml(
+            "[keys.level]\ntype = 'string'\nenum = ['info', 'warn']\n[keys.retries]\ntype = 'integer'\nmin = 0\noptional = true\n[keys.hosts]\ntype = 'array'\nitems = { type = 'string', pattern = '^[a-z.]+$' }\n[keys.limits]\nadditional-keys = false\nkeys.rate = { type = 'float' }",
+        )?;
+        let expected = Schema::table()
+            .required(
+                "hosts",
+                Schema::array(Schema::string().pattern(super::Pattern::new("^[a-z.]+$")?)),
+            )
+            .required("level", Schema::string().one_of(["info", "warn"]))
+            .required(
+                "limits",
+                Schema::table()
+                    .required("rate", Schema::float())
+                    .deny_unknown_keys(),
+            )
+            .optional("retries", Schema::integer().min(0));
+        assert_eq!(schema, expected);
+
+        let errors = [
+            (
+                "[keys.a]\ntype = 'text'",
+                "Invalid schema at `keys.a.type`: unknown type `text`.",
+            ),
+            (
+                "[keys.a]\ntype = 'integer'\npattern = 'x'",
+                "Invalid schema at `keys.a.pattern`: unknown field for type `integer`.",
+            ),
+            (
+                "[keys.a]\ntype = 'string'\npattern = '(x'",
+                "Invalid schema at `keys.a.pattern`: Invalid pattern `(x`: unclosed `(`.",
+            ),
+            (
+                "keys.a = 1",
+                "Invalid schema at `keys.a`: expected a table.",
+            ),
+            (
+                "optional = true",
+                "Invalid schema at `optional`: unknown field for type `table`.",
+            ),
+            (
+                "[keys.a]\nitems = {}\n",
+                "Invalid schema at `keys.a.items`: unknown field for type `any`.",
+            ),
+        ];
+        for (source, expected) in errors {
+            assert_eq!(Schema::from_toml(source).unwrap_err(), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn infer() -> Result<(), String> {
+        let samples = [
+            "name = 'a'\nport = 80\nwhen = 2024-01-01\nmixed = 1\n[server]\nhost = 'x'\n[[bin]]\npath = 'x'",
+            "name = 'b'\nwhen = 2024-01-01T00:00:00Z\nmixed = 'a'\n'odd key' = [1, 2]\nbin = [{ path = 'y', test = true }]\n[server]\nhost = 'y'\ntls = { cert = 'c' }",
+        ];
+        let parsed = samples
+            .iter()
+            .map(|source| crate::parse_str(source))
+            .collect::<Result<Vec<_>, _>>()?;
+        let schema = Schema::infer(parsed.iter().map(|parsed| parsed.table()));
+        let server = Schema::table()
+            .required("host", Schema::string())
+            .optional("tls", Schema::table().required("cert", Schema::string()));
+        let bin = Schema::table()
+            .required("path", Schema::string())
+            .optional("test", Schema::boolean());
+        let expected = Schema::table()
+            .required("bin", Schema::array_of_tables(bin))
+            .required("mixed", Schema::any())
+            .required("name", Schema::string())
+            .optional("odd key", Schema::array(Schema::integer()))
+            .optional("port", Schema::integer())
+            .required("server", server)
+            .required("when", Schema::datetime());
+        assert_eq!(schema, expected);
+
+        // both written forms read back, and accept the samples
+        assert_eq!(Schema::from_toml(&schema.to_toml())?, schema);
+        let json = Schema::from_json_schema(&schema.to_json_schema())?;
+        for parsed in &parsed {
+            assert_eq!(json.validate(parsed), Ok(()));
+        }
+        let missing = crate::parse_str("name = 'c'")?;
+        assert_eq!(json.validate(&missing).unwrap_err().len(), 4);
+
+        // integer bounds are written exactly
+        let bounded = Schema::integer().range(i64::MIN, i64::MAX).to_json_schema();
+        assert!(bounded.contains("\"minimum\": -9223372036854775808,"));
+        assert!(bounded.contains("\"maximum\": 9223372036854775807\n"));
+        Ok(())
+    }
+}
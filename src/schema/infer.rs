@@ -0,0 +1,145 @@
+//! Inferring schemas from sample documents.
+
+// stdlib imports
+use std::collections::BTreeSet;
+// my imports
+use super::Schema;
+use crate::tomltypes::{TOMLTable, TOMLType};
+
+impl Schema {
+    /// Infers a schema that each of `samples` matches, for writing one for existing
+    /// documents.
+    ///
+    /// Each key path gets the type its values share; a key is optional if some sample (or
+    /// some table in an array of tables) leaves it out. Differing types unify as follows:
+    /// datetimes of different kinds as [`datetime`](Self::datetime), arrays by unifying all
+    /// of their elements, and anything else as [`any`](Self::any). Only types are inferred,
+    /// not patterns, enums, or ranges.
+    ///
+    /// ```
+    /// use tomlp::schema::Schema;
+    ///
+    /// let a = tomlp::parse_str("name = 'a'\nport = 80\n[[bin]]\npath = 'x'")?;
+    /// let b = tomlp::parse_str("name = 'b'\n[[bin]]\npath = 'y'\ntest = true")?;
+    /// let schema = Schema::infer([a.table(), b.table()]);
+    /// assert_eq!(
+    ///     schema,
+    ///     Schema::table()
+    ///         .required(
+    ///             "bin",
+    ///             Schema::array_of_tables(
+    ///                 Schema::table()
+    ///                     .required("path", Schema::string())
+    ///                     .optional("test", Schema::boolean()),
+    ///             ),
+    ///         )
+    ///         .required("name", Schema::string())
+    ///         .optional("port", Schema::integer())
+    /// );
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn infer<'t, I>(samples: I) -> Schema
+    where
+        I: IntoIterator<Item = &'t TOMLTable>,
+    {
+        let tables: Vec<&TOMLTable> = samples.into_iter().collect();
+        infer_table(&tables)
+    }
+}
+
+/// A schema for `tables`, with the keys in sorted order.
+fn infer_table(tables: &[&TOMLTable]) -> Schema {
+    let names: BTreeSet<&String> = tables.iter().flat_map(|table| table.keys()).collect();
+    let mut schema = Schema::table();
+    for name in names {
+        let vals: Vec<&TOMLType> = tables.iter().filter_map(|table| table.get(name)).collect();
+        let required = vals.len() == tables.len();
+        schema = schema.with_key(name, infer_values(&vals), required);
+    }
+    schema
+}
+
+/// A schema for `vals`, which are the values of one key path.
+fn infer_values(vals: &[&TOMLType]) -> Schema {
+    let Some(first) = vals.first() else {
+        return Schema::any();
+    };
+    let shared = category(first);
+    if vals.iter().any(|val| category(val) != shared) {
+        return Schema::any();
+    }
+    match *first {
+        TOMLType::Datetime(dt) => {
+            let kind = dt.kind();
+            if vals
+                .iter()
+                .all(|val| val.datetime().is_some_and(|dt| dt.kind() == kind))
+            {
+                Schema::datetime_of(kind)
+            } else {
+                Schema::datetime()
+            }
+        }
+        TOMLType::Array(_) | TOMLType::AoT(_) => {
+            // elements are tables (from `[[headers]]` or inline) or other values
+            let mut tables = Vec::new();
+            let mut elements = Vec::new();
+            for val in vals {
+                match val {
+                    TOMLType::AoT(items) => tables.extend(items),
+                    TOMLType::Array(items) => {
+                        for item in items {
+                            match table_of(item) {
+                                Some(table) => tables.push(table),
+                                None => elements.push(item),
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            let items = match (tables.is_empty(), elements.is_empty()) {
+                (false, true) => infer_table(&tables),
+                (true, false) => infer_values(&elements),
+                _ => Schema::any(),
+            };
+            Schema::array(items)
+        }
+        _ if shared == "table" => {
+            let tables: Vec<&TOMLTable> = vals.iter().filter_map(|val| table_of(val)).collect();
+            infer_table(&tables)
+        }
+        _ => match shared {
+            "string" => Schema::string(),
+            "integer" => Schema::integer(),
+            "float" => Schema::float(),
+            _ => Schema::boolean(),
+        },
+    }
+}
+
+/// Which values unify without loss: the type, with all strings, datetimes, arrays, and tables
+/// alike.
+fn category(val: &TOMLType) -> &'static str {
+    match val {
+        TOMLType::Bool(_) => "boolean",
+        TOMLType::Int(_) | TOMLType::WideInt(_) => "integer",
+        TOMLType::Float(_) => "float",
+        TOMLType::BasicStr(_)
+        | TOMLType::MultiStr(_)
+        | TOMLType::LitStr(_)
+        | TOMLType::MultiLitStr(_) => "string",
+        TOMLType::Datetime(_) => "datetime",
+        TOMLType::Array(_) | TOMLType::AoT(_) => "array",
+        TOMLType::HTable(_) | TOMLType::DKTable(_) | TOMLType::InlineTable(_) => "table",
+    }
+}
+
+fn table_of(val: &TOMLType) -> Option<&TOMLTable> {
+    match val {
+        TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table) => {
+            Some(table)
+        }
+        _ => None,
+    }
+}
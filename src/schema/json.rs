@@ -0,0 +1,1002 @@
+//! Validating against JSON Schemas.
+
+// stdlib imports
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+// my imports
+use super::{a, type_name, violation, Kind, Pattern, Schema, Violation};
+use crate::datetime::DatetimeKind;
+use crate::json::Json;
+use crate::tomltypes::{TOMLTable, TOMLType};
+
+/// How many `$ref`s may be followed without reaching a nested value, which stops cycles like
+/// `{ "$ref": "#" }`.
+const MAX_REF_DEPTH: usize = 64;
+
+/// A JSON Schema document, with its patterns compiled ahead of time.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct JsonSchema {
+    root: Arc<Json>,
+    patterns: Arc<HashMap<String, Pattern>>,
+}
+
+impl Schema {
+    /// Reads a JSON Schema (draft 4 through 2020-12), such as the ones editors use for TOML
+    /// files.
+    ///
+    /// TOML values are checked as though they were converted to JSON:
+    ///
+    /// | TOML                     | JSON Schema                                                |
+    /// | ------------------------ | ---------------------------------------------------------- |
+    /// | string                   | `"string"`                                                 |
+    /// | integer                  | `"integer"` or `"number"`                                  |
+    /// | float                    | `"number"`                                                 |
+    /// | boolean                  | `"boolean"`                                                |
+    /// | array, array of tables   | `"array"`                                                  |
+    /// | table                    | `"object"`                                                 |
+    /// | offset date-time         | `"string"`, with `"format": "date-time"`                   |
+    /// | local date-time          | `"string"`, with `"format": "local-date-time"`             |
+    /// | local date               | `"string"`, with `"format": "date"`                        |
+    /// | local time               | `"string"`, with `"format": "local-time"`                  |
+    ///
+    /// So a datetime is a string whose `format` picks the kind. `partial-date-time` and
+    /// `partial-time` (Taplo's names) and `time` are accepted for the local kinds, too. Other
+    /// formats aren't checked, and neither are strings that merely look like a date. No TOML
+    /// value is `"null"`.
+    ///
+    /// The supported keywords are `type`, `enum`, `const`, `format`, `$ref` (within the
+    /// document), `allOf`, `anyOf`, `oneOf`, `not`, `if`/`then`/`else`, `minLength`,
+    /// `maxLength`, `pattern` (see [`Pattern`]), `minimum`, `maximum`, `exclusiveMinimum`,
+    /// `exclusiveMaximum`, `multipleOf`, `items`, `prefixItems`, `additionalItems`,
+    /// `contains`, `minItems`, `maxItems`, `uniqueItems`, `properties`, `required`,
+    /// `patternProperties`, `additionalProperties`, `propertyNames`, `minProperties`, and
+    /// `maxProperties`. Others, such as `title` or `default`, are ignored.
+    ///
+    /// ```
+    /// use tomlp::schema::Schema;
+    ///
+    /// let schema = Schema::from_json_schema(
+    ///     r##"{
+    ///         "type": "object",
+    ///         "required": ["package"],
+    ///         "properties": {
+    ///             "package": { "$ref": "#/definitions/Package" }
+    ///         },
+    ///         "definitions": {
+    ///             "Package": {
+    ///                 "type": "object",
+    ///                 "required": ["name"],
+    ///                 "properties": {
+    ///                     "name": { "type": "string" },
+    ///                     "published": { "type": "string", "format": "date" }
+    ///                 }
+    ///             }
+    ///         }
+    ///     }"##,
+    /// )?;
+    /// let parsed = tomlp::parse_str("[package]\nname = 'web'\npublished = 2024-05-01T10:00:00Z")?;
+    /// let violations = schema.validate(&parsed).unwrap_err();
+    /// assert_eq!(
+    ///     violations[0].to_string(),
+    ///     "`package.published`: Expected a local date, found an offset date-time."
+    /// );
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn from_json_schema(source: &str) -> Result<Schema, String> {
+        let root = Json::parse(source)?;
+        let mut patterns = HashMap::new();
+        prepare(&root, &root, &mut "#".to_string(), &mut patterns)?;
+        Ok(Schema::of(Kind::Json(JsonSchema {
+            root: Arc::new(root),
+            patterns: Arc::new(patterns),
+        })))
+    }
+}
+
+/// A value being validated. Arrays of tables hold bare tables, hence the second variant.
+#[derive(Clone, Copy)]
+enum Value<'v> {
+    Item(&'v TOMLType),
+    Table(&'v TOMLTable),
+}
+
+impl<'v> Value<'v> {
+    fn table(self) -> Option<&'v TOMLTable> {
+        match self {
+            Value::Table(table)
+            | Value::Item(
+                TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table),
+            ) => Some(table),
+            _ => None,
+        }
+    }
+
+    fn elements(self) -> Option<Vec<Value<'v>>> {
+        match self {
+            Value::Item(TOMLType::Array(vals)) => Some(vals.iter().map(Value::Item).collect()),
+            Value::Item(TOMLType::AoT(tables)) => Some(tables.iter().map(Value::Table).collect()),
+            _ => None,
+        }
+    }
+
+    fn number(self) -> Option<f64> {
+        match self {
+            Value::Item(TOMLType::Float(n)) => Some(*n),
+            Value::Item(val) => val.i128().map(|n| n as f64),
+            Value::Table(_) => None,
+        }
+    }
+
+    /// How this number compares to `bound`, exactly if both are integers.
+    fn compare(self, bound: &Json) -> Option<Ordering> {
+        match (self, bound) {
+            (Value::Item(val), Json::Integer(bound)) if val.i128().is_some() => {
+                val.i128().map(|n| n.cmp(bound))
+            }
+            _ => self.number()?.partial_cmp(&bound.as_f64()?),
+        }
+    }
+
+    /// Whether this number is a multiple of `divisor`, exactly if both are integers.
+    fn is_multiple_of(self, divisor: &Json) -> Option<bool> {
+        match (self, divisor) {
+            (Value::Item(val), Json::Integer(divisor)) if val.i128().is_some() && *divisor != 0 => {
+                val.i128().map(|n| n % divisor == 0)
+            }
+            _ => {
+                let quotient = self.number()? / divisor.as_f64()?;
+                Some((quotient - quotient.round()).abs() <= 1e-9)
+            }
+        }
+    }
+
+    /// Whether this is of the JSON type `name`.
+    fn is(self, name: &str) -> bool {
+        match (name, self) {
+            ("string", Value::Item(val)) => val.str().is_some() || val.datetime().is_some(),
+            ("integer", Value::Item(val)) => val.i128().is_some(),
+            ("number", _) => self.number().is_some(),
+            ("boolean", Value::Item(val)) => matches!(val, TOMLType::Bool(_)),
+            ("array", _) => self.elements().is_some(),
+            ("object", _) => self.table().is_some(),
+            _ => false,
+        }
+    }
+
+    fn type_name(self) -> String {
+        match self {
+            Value::Item(val) => type_name(val),
+            Value::Table(_) => "table".to_string(),
+        }
+    }
+
+    /// The value in backticks if it's short, and its type otherwise.
+    fn describe(self) -> String {
+        match self {
+            Value::Item(TOMLType::Bool(b)) => format!("`{}`", b),
+            Value::Item(TOMLType::Float(n)) => format!("`{}`", n),
+            Value::Item(TOMLType::Datetime(dt)) => format!("`{}`", dt),
+            Value::Item(val) if val.i128().is_some() => format!("`{}`", val.i128().unwrap_or(0)),
+            Value::Item(val) if val.str().is_some() => format!("`{}`", val.str().unwrap_or("")),
+            _ => a(&self.type_name()),
+        }
+    }
+
+    /// Whether this equals `json`, as for `enum` and `const`.
+    fn equals(self, json: &Json) -> bool {
+        match (self, json) {
+            (Value::Item(TOMLType::Bool(b)), Json::Bool(other)) => b == other,
+            (Value::Item(TOMLType::Datetime(dt)), Json::String(s)) => dt.to_string() == *s,
+            (Value::Item(val), Json::String(s)) => val.str() == Some(s),
+            (Value::Item(val), Json::Integer(n)) if val.i128().is_some() => val.i128() == Some(*n),
+            (_, Json::Integer(n)) => self.number() == Some(*n as f64),
+            (_, Json::Number(n)) => self.number() == Some(*n),
+            (_, Json::Array(others)) => self.elements().is_some_and(|vals| {
+                vals.len() == others.len()
+                    && vals
+                        .iter()
+                        .zip(others)
+                        .all(|(val, other)| val.equals(other))
+            }),
+            (_, Json::Object(fields)) => self.table().is_some_and(|table| {
+                table.len() == fields.len()
+                    && fields.iter().all(|(key, other)| {
+                        table
+                            .get(key)
+                            .is_some_and(|val| Value::Item(val).equals(other))
+                    })
+            }),
+            _ => false,
+        }
+    }
+
+    /// Whether this equals `other`, as for `uniqueItems`.
+    fn same(self, other: Value<'_>) -> bool {
+        match (self, other) {
+            (Value::Item(TOMLType::Bool(b)), Value::Item(TOMLType::Bool(other))) => b == other,
+            (Value::Item(TOMLType::Datetime(dt)), Value::Item(TOMLType::Datetime(other))) => {
+                dt == other
+            }
+            (Value::Item(val), Value::Item(other)) if val.str().is_some() => {
+                val.str() == other.str()
+            }
+            _ if self.number().is_some() => self.number() == other.number(),
+            _ => match (
+                self.elements(),
+                other.elements(),
+                self.table(),
+                other.table(),
+            ) {
+                (Some(vals), Some(others), _, _) => {
+                    vals.len() == others.len()
+                        && vals.iter().zip(others).all(|(val, other)| val.same(other))
+                }
+                (_, _, Some(table), Some(other)) => {
+                    table.len() == other.len()
+                        && table.iter().all(|(key, val)| {
+                            other
+                                .get(key)
+                                .is_some_and(|other| Value::Item(val).same(Value::Item(other)))
+                        })
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+impl JsonSchema {
+    pub(super) fn root(&self) -> &Json {
+        &self.root
+    }
+
+    pub(super) fn check_value(
+        &self,
+        val: &TOMLType,
+        path: &mut Vec<String>,
+        out: &mut Vec<Violation>,
+    ) {
+        self.check(&self.root, Value::Item(val), path, out, 0);
+    }
+
+    pub(super) fn check_table(
+        &self,
+        table: &TOMLTable,
+        path: &mut Vec<String>,
+        out: &mut Vec<Violation>,
+    ) {
+        self.check(&self.root, Value::Table(table), path, out, 0);
+    }
+
+    /// Whether `val` matches `node`, without reporting why not.
+    fn matches(&self, node: &Json, val: Value<'_>, path: &mut Vec<String>, refs: usize) -> bool {
+        let mut scratch = Vec::new();
+        self.check(node, val, path, &mut scratch, refs);
+        scratch.is_empty()
+    }
+
+    /// Checks `val` against the schema `node`. `refs` counts the `$ref`s followed since the
+    /// last nested value.
+    fn check(
+        &self,
+        node: &Json,
+        val: Value<'_>,
+        path: &mut Vec<String>,
+        out: &mut Vec<Violation>,
+        refs: usize,
+    ) {
+        match node {
+            Json::Bool(false) => {
+                return violation(path, "No value is allowed here.".to_string(), out)
+            }
+            Json::Object(_) => (),
+            _ => return,
+        }
+        let keyword = |name: &str| node.get(name);
+        let number = |name: &str| keyword(name).and_then(Json::as_f64);
+
+        if let Some(target) = keyword("$ref").and_then(Json::as_str) {
+            match target
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer))
+            {
+                Some(target) if refs < MAX_REF_DEPTH => {
+                    self.check(target, val, path, out, refs + 1)
+                }
+                _ => violation(path, format!("`$ref` `{}` nests too deeply.", target), out),
+            }
+        }
+
+        if let Some(types) = keyword("type") {
+            let names: Vec<&str> = match types {
+                Json::Array(names) => names.iter().filter_map(Json::as_str).collect(),
+                _ => types.as_str().into_iter().collect(),
+            };
+            if !names.iter().any(|name| val.is(name)) {
+                let expected: Vec<String> =
+                    names.iter().map(|name| a(json_type_name(name))).collect();
+                let message = format!(
+                    "Expected {}, found {}.",
+                    expected.join(" or "),
+                    a(&val.type_name())
+                );
+                // the other keywords would only restate the mismatch
+                return violation(path, message, out);
+            }
+        }
+        if let Some(expected) = keyword("const").filter(|expected| !val.equals(expected)) {
+            let message = format!(
+                "Expected {}, found {}.",
+                json_text(expected),
+                val.describe()
+            );
+            violation(path, message, out);
+        }
+        if let Some(values) = keyword("enum")
+            .and_then(Json::as_array)
+            .filter(|values| !values.iter().any(|expected| val.equals(expected)))
+        {
+            let values: Vec<String> = values.iter().map(json_text).collect();
+            let message = format!("{} is not one of {}.", val.describe(), values.join(", "));
+            violation(path, message, out);
+        }
+        if let (Some(format), Value::Item(TOMLType::Datetime(dt))) =
+            (keyword("format").and_then(Json::as_str), val)
+        {
+            if let Some(expected) = format_kind(format).filter(|kind| *kind != dt.kind()) {
+                let message = format!(
+                    "Expected {}, found {}.",
+                    a(&expected.to_string()),
+                    a(&dt.kind().to_string())
+                );
+                violation(path, message, out);
+            }
+        }
+
+        // strings
+        if let Some(text) = match val {
+            Value::Item(val) => val.str(),
+            Value::Table(_) => None,
+        } {
+            let len = text.chars().count();
+            if let Some(min) = number("minLength").filter(|min| (len as f64) < *min) {
+                let message = format!("`{}` is shorter than {} characters.", text, min);
+                violation(path, message, out);
+            }
+            if let Some(max) = number("maxLength").filter(|max| (len as f64) > *max) {
+                let message = format!("`{}` is longer than {} characters.", text, max);
+                violation(path, message, out);
+            }
+            if let Some(pattern) = keyword("pattern")
+                .and_then(Json::as_str)
+                .and_then(|source| self.patterns.get(source))
+                .filter(|pattern| !pattern.is_match(text))
+            {
+                let message = format!("`{}` does not match the pattern `{}`.", text, pattern);
+                violation(path, message, out);
+            }
+        }
+
+        // numbers
+        if val.number().is_some() {
+            let text = match val {
+                Value::Item(TOMLType::Float(n)) => n.to_string(),
+                Value::Item(val) => val.i128().unwrap_or(0).to_string(),
+                Value::Table(_) => String::new(),
+            };
+            // bounds as the schema writes them
+            let shown = |name: &str| keyword(name).map(Json::to_string).unwrap_or_default();
+            let order = |name: &str| keyword(name).and_then(|bound| val.compare(bound));
+            // draft 4 marks `minimum` and `maximum` exclusive with booleans
+            let exclusive = |name: &str| keyword(name).and_then(Json::as_bool) == Some(true);
+            let mut messages = Vec::new();
+            match order("minimum") {
+                Some(Ordering::Less) => messages.push(format!(
+                    "{} is less than the minimum of {}.",
+                    text,
+                    shown("minimum")
+                )),
+                Some(Ordering::Equal) if exclusive("exclusiveMinimum") => messages.push(format!(
+                    "{} is not greater than {}.",
+                    text,
+                    shown("minimum")
+                )),
+                _ => (),
+            }
+            match order("maximum") {
+                Some(Ordering::Greater) => messages.push(format!(
+                    "{} is greater than the maximum of {}.",
+                    text,
+                    shown("maximum")
+                )),
+                Some(Ordering::Equal) if exclusive("exclusiveMaximum") => {
+                    messages.push(format!("{} is not less than {}.", text, shown("maximum")))
+                }
+                _ => (),
+            }
+            if order("exclusiveMinimum").is_some_and(|order| order != Ordering::Greater) {
+                messages.push(format!(
+                    "{} is not greater than {}.",
+                    text,
+                    shown("exclusiveMinimum")
+                ));
+            }
+            if order("exclusiveMaximum").is_some_and(|order| order != Ordering::Less) {
+                messages.push(format!(
+                    "{} is not less than {}.",
+                    text,
+                    shown("exclusiveMaximum")
+                ));
+            }
+            let divisor = keyword("multipleOf");
+            if divisor.and_then(|divisor| val.is_multiple_of(divisor)) == Some(false) {
+                messages.push(format!(
+                    "{} is not a multiple of {}.",
+                    text,
+                    shown("multipleOf")
+                ));
+            }
+            for message in messages {
+                violation(path, message, out);
+            }
+        }
+
+        // arrays
+        if let Some(vals) = val.elements() {
+            self.check_array(node, &vals, path, out);
+        }
+
+        // tables
+        if let Some(table) = val.table() {
+            self.check_object(node, table, path, out);
+        }
+
+        // combinations
+        if let Some(all) = keyword("allOf").and_then(Json::as_array) {
+            for sub in all {
+                self.check(sub, val, path, out, refs);
+            }
+        }
+        if let Some(any) = keyword("anyOf").and_then(Json::as_array) {
+            if !any.iter().any(|sub| self.matches(sub, val, path, refs)) {
+                violation(
+                    path,
+                    "Matches none of the `anyOf` schemas.".to_string(),
+                    out,
+                );
+            }
+        }
+        if let Some(one) = keyword("oneOf").and_then(Json::as_array) {
+            let matching = one
+                .iter()
+                .filter(|sub| self.matches(sub, val, path, refs))
+                .count();
+            match matching {
+                0 => violation(
+                    path,
+                    "Matches none of the `oneOf` schemas.".to_string(),
+                    out,
+                ),
+                1 => (),
+                n => {
+                    let message = format!(
+                        "Matches {} of the `oneOf` schemas, rather than exactly one.",
+                        n
+                    );
+                    violation(path, message, out);
+                }
+            }
+        }
+        if let Some(not) = keyword("not") {
+            if self.matches(not, val, path, refs) {
+                violation(path, "Matches the `not` schema.".to_string(), out);
+            }
+        }
+        if let Some(condition) = keyword("if") {
+            let branch = if self.matches(condition, val, path, refs) {
+                keyword("then")
+            } else {
+                keyword("else")
+            };
+            if let Some(branch) = branch {
+                self.check(branch, val, path, out, refs);
+            }
+        }
+    }
+
+    fn check_array(
+        &self,
+        node: &Json,
+        vals: &[Value<'_>],
+        path: &mut Vec<String>,
+        out: &mut Vec<Violation>,
+    ) {
+        let keyword = |name: &str| node.get(name);
+        let count = |name: &str| keyword(name).and_then(Json::as_f64);
+
+        // positional schemas come from `prefixItems` (2020-12) or an array of `items` (older)
+        let (positional, rest) = match (keyword("prefixItems"), keyword("items")) {
+            (Some(Json::Array(prefix)), items) => (prefix.as_slice(), items),
+            (_, Some(Json::Array(items))) => (items.as_slice(), keyword("additionalItems")),
+            (_, items) => (&[][..], items),
+        };
+        for (i, val) in vals.iter().enumerate() {
+            if let Some(item) = positional.get(i).or(rest) {
+                path.push(i.to_string());
+                self.check(item, *val, path, out, 0);
+                path.pop();
+            }
+        }
+
+        let len = vals.len() as f64;
+        if let Some(min) = count("minItems").filter(|min| len < *min) {
+            let message = format!("Has {} items, fewer than the minimum of {}.", len, min);
+            violation(path, message, out);
+        }
+        if let Some(max) = count("maxItems").filter(|max| len > *max) {
+            let message = format!("Has {} items, more than the maximum of {}.", len, max);
+            violation(path, message, out);
+        }
+        if keyword("uniqueItems").and_then(Json::as_bool) == Some(true) {
+            let repeat = (0..vals.len())
+                .flat_map(|j| (0..j).map(move |i| (i, j)))
+                .find(|(i, j)| vals[*i].same(vals[*j]));
+            if let Some((i, j)) = repeat {
+                violation(path, format!("Items {} and {} are equal.", i, j), out);
+            }
+        }
+        if let Some(contains) = keyword("contains") {
+            let found = vals.iter().enumerate().any(|(i, val)| {
+                path.push(i.to_string());
+                let found = self.matches(contains, *val, path, 0);
+                path.pop();
+                found
+            });
+            if !found {
+                violation(
+                    path,
+                    "No item matches the `contains` schema.".to_string(),
+                    out,
+                );
+            }
+        }
+    }
+
+    fn check_object(
+        &self,
+        node: &Json,
+        table: &TOMLTable,
+        path: &mut Vec<String>,
+        out: &mut Vec<Violation>,
+    ) {
+        let keyword = |name: &str| node.get(name);
+        let count = |name: &str| keyword(name).and_then(Json::as_f64);
+        let properties = keyword("properties")
+            .and_then(Json::as_object)
+            .unwrap_or(&[]);
+        let pattern_properties = keyword("patternProperties")
+            .and_then(Json::as_object)
+            .unwrap_or(&[]);
+
+        for name in keyword("required").and_then(Json::as_array).unwrap_or(&[]) {
+            if let Some(name) = name.as_str().filter(|name| !table.contains_key(*name)) {
+                path.push(name.to_string());
+                violation(path, "Missing required key.".to_string(), out);
+                path.pop();
+            }
+        }
+        for (name, sub) in properties {
+            if let Some(val) = table.get(name) {
+                path.push(name.clone());
+                self.check(sub, Value::Item(val), path, out, 0);
+                path.pop();
+            }
+        }
+
+        let mut keys: Vec<&String> = table.keys().collect();
+        keys.sort();
+        for key in keys {
+            let val = Value::Item(&table[key]);
+            path.push(key.clone());
+            let mut described = properties.iter().any(|(name, _)| name == key);
+            for (source, sub) in pattern_properties {
+                if self
+                    .patterns
+                    .get(source)
+                    .is_some_and(|pattern| pattern.is_match(key))
+                {
+                    described = true;
+                    self.check(sub, val, path, out, 0);
+                }
+            }
+            match keyword("additionalProperties") {
+                Some(Json::Bool(false)) if !described => {
+                    violation(path, "Unknown key.".to_string(), out)
+                }
+                Some(sub @ Json::Object(_)) if !described => self.check(sub, val, path, out, 0),
+                _ => (),
+            }
+            if let Some(names) = keyword("propertyNames") {
+                let name = TOMLType::BasicStr(key.clone());
+                self.check(names, Value::Item(&name), path, out, 0);
+            }
+            path.pop();
+        }
+
+        let len = table.len() as f64;
+        if let Some(min) = count("minProperties").filter(|min| len < *min) {
+            let message = format!("Has {} keys, fewer than the minimum of {}.", len, min);
+            violation(path, message, out);
+        }
+        if let Some(max) = count("maxProperties").filter(|max| len > *max) {
+            let message = format!("Has {} keys, more than the maximum of {}.", len, max);
+            violation(path, message, out);
+        }
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+/// Compiles the patterns of the schema `node` and checks its types and `$ref`s, recursing into
+/// its subschemas. `pointer` is where `node` is (ex. `#/properties/name`).
+fn prepare(
+    root: &Json,
+    node: &Json,
+    pointer: &mut String,
+    patterns: &mut HashMap<String, Pattern>,
+) -> Result<(), String> {
+    let fields = match node {
+        Json::Bool(_) => return Ok(()),
+        Json::Object(fields) => fields,
+        _ => return Err(schema_error(pointer, "expected an object or a boolean")),
+    };
+    for (keyword, val) in fields {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&keyword.replace('~', "~0").replace('/', "~1"));
+        let result = match keyword.as_str() {
+            "$ref" => match val.as_str() {
+                Some(target)
+                    if target
+                        .strip_prefix('#')
+                        .and_then(|p| root.pointer(p))
+                        .is_some() =>
+                {
+                    Ok(())
+                }
+                Some(target) => {
+                    let reason = format!("cannot resolve `{}` within the document", target);
+                    Err(schema_error(pointer, &reason))
+                }
+                None => Err(schema_error(pointer, "expected a string")),
+            },
+            "type" => {
+                let names: Option<Vec<&str>> = match val {
+                    Json::Array(names) => names.iter().map(Json::as_str).collect(),
+                    _ => val.as_str().map(|name| vec![name]),
+                };
+                match names
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|name| json_type_name(name).is_empty())
+                {
+                    Some(name) => Err(schema_error(pointer, &format!("unknown type `{}`", name))),
+                    None if val.as_str().is_some() || val.as_array().is_some() => Ok(()),
+                    None => Err(schema_error(pointer, "expected a string or an array")),
+                }
+            }
+            "pattern" => match val.as_str() {
+                Some(source) => compile(source, pointer, patterns),
+                None => Err(schema_error(pointer, "expected a string")),
+            },
+            "patternProperties" => each_field(val, pointer, |source, sub, pointer| {
+                compile(source, pointer, patterns)?;
+                prepare(root, sub, pointer, patterns)
+            }),
+            "properties" | "definitions" | "$defs" | "dependentSchemas" => {
+                each_field(val, pointer, |_, sub, pointer| {
+                    prepare(root, sub, pointer, patterns)
+                })
+            }
+            "allOf" | "anyOf" | "oneOf" | "prefixItems" => match val.as_array() {
+                Some(subs) => each_item(subs, root, pointer, patterns),
+                None => Err(schema_error(pointer, "expected an array")),
+            },
+            "items" => match val.as_array() {
+                Some(subs) => each_item(subs, root, pointer, patterns),
+                None => prepare(root, val, pointer, patterns),
+            },
+            "additionalItems"
+            | "additionalProperties"
+            | "contains"
+            | "propertyNames"
+            | "not"
+            | "if"
+            | "then"
+            | "else" => prepare(root, val, pointer, patterns),
+            _ => Ok(()),
+        };
+        result?;
+        pointer.truncate(len);
+    }
+    Ok(())
+}
+
+/// Calls `visit` on each field of the object `val`, with `pointer` extended to it.
+fn each_field(
+    val: &Json,
+    pointer: &mut String,
+    mut visit: impl FnMut(&str, &Json, &mut String) -> Result<(), String>,
+) -> Result<(), String> {
+    let fields = val
+        .as_object()
+        .ok_or_else(|| schema_error(pointer, "expected an object"))?;
+    for (name, sub) in fields {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&name.replace('~', "~0").replace('/', "~1"));
+        visit(name, sub, pointer)?;
+        pointer.truncate(len);
+    }
+    Ok(())
+}
+
+fn each_item(
+    subs: &[Json],
+    root: &Json,
+    pointer: &mut String,
+    patterns: &mut HashMap<String, Pattern>,
+) -> Result<(), String> {
+    for (i, sub) in subs.iter().enumerate() {
+        let len = pointer.len();
+        pointer.push_str(&format!("/{}", i));
+        prepare(root, sub, pointer, patterns)?;
+        pointer.truncate(len);
+    }
+    Ok(())
+}
+
+fn compile(
+    source: &str,
+    pointer: &str,
+    patterns: &mut HashMap<String, Pattern>,
+) -> Result<(), String> {
+    if !patterns.contains_key(source) {
+        let pattern =
+            Pattern::new(source).map_err(|err| schema_error(pointer, err.trim_end_matches('.')))?;
+        patterns.insert(source.to_string(), pattern);
+    }
+    Ok(())
+}
+
+fn schema_error(pointer: &str, reason: &str) -> String {
+    format!("Invalid JSON Schema at `{}`: {}.", pointer, reason)
+}
+
+/// The TOML name for a JSON type, or an empty string for an unknown type.
+fn json_type_name(name: &str) -> &'static str {
+    match name {
+        "string" => "string",
+        "integer" => "integer",
+        "number" => "number",
+        "boolean" => "boolean",
+        "array" => "array",
+        "object" => "table",
+        "null" => "null",
+        _ => "",
+    }
+}
+
+/// The kind of datetime a `format` asks for. See [`Schema::from_json_schema`].
+fn format_kind(format: &str) -> Option<DatetimeKind> {
+    match format {
+        "date-time" => Some(DatetimeKind::OffsetDatetime),
+        "local-date-time" | "partial-date-time" => Some(DatetimeKind::LocalDatetime),
+        "date" => Some(DatetimeKind::LocalDate),
+        "local-time" | "partial-time" | "time" => Some(DatetimeKind::LocalTime),
+        _ => None,
+    }
+}
+
+/// A JSON value in backticks, with strings left unquoted.
+fn json_text(json: &Json) -> String {
+    match json {
+        Json::String(s) => format!("`{}`", s),
+        json => format!("`{}`", json),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::{Schema, Violation};
+
+    fn violations(schema: &str, source: &str) -> Result<Vec<String>, String> {
+        let schema = Schema::from_json_schema(schema)?;
+        let parsed = crate::parse_str(source)?;
+        let violations = schema.validate(&parsed).err().unwrap_or_default();
+        Ok(violations.iter().map(Violation::to_string).collect())
+    }
+
+    #[test]
+    fn types() -> Result<(), String> {
+        let schema = r#"{
+            "properties": {
+                "name": { "type": "string", "minLength": 2, "pattern": "^[a-z]+$" },
+                "count": { "type": "integer", "minimum": 0, "exclusiveMaximum": 10 },
+                "ratio": { "type": "number", "multipleOf": 0.5 },
+                "flags": { "type": ["boolean", "array"], "items": { "type": "boolean" }, "uniqueItems": true },
+                "when": { "type": "string", "format": "date-time" },
+                "day": { "type": "string", "format": "date" },
+                "nothing": { "type": "null" }
+            }
+        }"#;
+        let valid = "name = 'ab'\ncount = 9\nratio = 2\nflags = [true, false]\nwhen = 2024-05-01T10:00:00Z\nday = 'soon'";
+        assert!(violations(schema, valid)?.is_empty());
+
+        let invalid = "name = 'A'\ncount = 10\nratio = 0.7\nflags = [true, 1, true]\nwhen = 2024-05-01T10:00:00\nday = 10:00:00\nnothing = 0";
+        assert_eq!(
+            violations(schema, invalid)?,
+            [
+                "`name`: `A` is shorter than 2 characters.",
+                "`name`: `A` does not match the pattern `^[a-z]+$`.",
+                "`count`: 10 is not less than 10.",
+                "`ratio`: 0.7 is not a multiple of 0.5.",
+                "`flags.1`: Expected a boolean, found an integer.",
+                "`flags`: Items 0 and 2 are equal.",
+                "`when`: Expected an offset date-time, found a local date-time.",
+                "`day`: Expected a local date, found a local time.",
+                "`nothing`: Expected a null, found an integer.",
+            ]
+        );
+        assert_eq!(
+            violations(r#"{ "type": ["string", "object"] }"#, "")?,
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            violations(
+                r#"{ "properties": { "a": { "type": ["string", "object"] } } }"#,
+                "a = 1"
+            )?,
+            ["`a`: Expected a string or a table, found an integer."]
+        );
+
+        // integers beyond 2^53 are compared exactly
+        let schema = r#"{
+            "properties": {
+                "a": { "maximum": 9007199254740992 },
+                "b": { "exclusiveMinimum": 9007199254740992 },
+                "c": { "multipleOf": 9007199254740992 },
+                "d": { "maximum": 1.5 }
+            }
+        }"#;
+        let source = "a = 9007199254740993\nb = 9007199254740992\nc = 9007199254740993\nd = 2";
+        assert_eq!(
+            violations(schema, source)?,
+            [
+                "`a`: 9007199254740993 is greater than the maximum of 9007199254740992.",
+                "`b`: 9007199254740992 is not greater than 9007199254740992.",
+                "`c`: 9007199254740993 is not a multiple of 9007199254740992.",
+                "`d`: 2 is greater than the maximum of 1.5.",
+            ]
+        );
+        let source = "a = 9007199254740992\nb = 9007199254740993\nc = 18014398509481984\nd = 1.5";
+        assert!(violations(schema, source)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn tables() -> Result<(), String> {
+        // after the shape of the Cargo manifest schema
+        let schema = r##"{
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "required": ["package"],
+            "properties": {
+                "package": { "$ref": "#/definitions/Package" },
+                "dependencies": {
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#/definitions/Dependency" }
+                },
+                "bin": { "type": "array", "items": { "$ref": "#/definitions/Target" }, "minItems": 1 }
+            },
+            "additionalProperties": false,
+            "definitions": {
+                "Package": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "edition": { "enum": ["2015", "2018", "2021"] },
+                        "publish": { "const": false }
+                    },
+                    "patternProperties": { "^x-": { "type": "string" } },
+                    "additionalProperties": false
+                },
+                "Dependency": {
+                    "anyOf": [
+                        { "type": "string" },
+                        { "type": "object", "required": ["version"] }
+                    ]
+                },
+                "Target": {
+                    "type": "object",
+                    "oneOf": [{ "required": ["path"] }, { "required": ["name"] }]
+                }
+            }
+        }"##;
+        let valid = "[package]\nname = 'web'\nedition = '2021'\nx-team = 'a'\n[dependencies]\nlog = '0.4'\nserde = { version = '1' }\n[[bin]]\npath = 'a.rs'";
+        assert!(violations(schema, valid)?.is_empty());
+
+        let invalid = "extra = 1\n[package]\nedition = '2024'\npublish = true\nx-team = 1\nversion = '1'\n[dependencies]\nserde = { features = [] }\n[[bin]]\npath = 'a.rs'\nname = 'a'";
+        assert_eq!(
+            violations(schema, invalid)?,
+            [
+                "`package.name`: Missing required key.",
+                "`package.edition`: `2024` is not one of `2015`, `2018`, `2021`.",
+                "`package.publish`: Expected `false`, found `true`.",
+                "`package.version`: Unknown key.",
+                "`package.x-team`: Expected a string, found an integer.",
+                "`dependencies.serde`: Matches none of the `anyOf` schemas.",
+                "`bin.0`: Matches 2 of the `oneOf` schemas, rather than exactly one.",
+                "`extra`: Unknown key.",
+            ]
+        );
+        assert_eq!(
+            violations(schema, "bin = []\n[package]\nname = 'a'")?,
+            ["`bin`: Has 0 items, fewer than the minimum of 1."]
+        );
+        // a JSON Schema can describe part of a document
+        let package = Schema::from_json_schema(r#"{ "required": ["name"] }"#)?;
+        let root = Schema::table().required("package", package);
+        let parsed = crate::parse_str("[package]\nversion = '1'")?;
+        assert_eq!(
+            root.validate(&parsed).unwrap_err()[0].to_string(),
+            "`package.name`: Missing required key."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        let cases = [
+            (r#"{ "type": "text" }"#, "Invalid JSON Schema at `#/type`: unknown type `text`."),
+            (
+                r##"{ "properties": { "a/b": { "$ref": "#/definitions/A" } } }"##,
+                "Invalid JSON Schema at `#/properties/a~1b/$ref`: cannot resolve `#/definitions/A` within the document.",
+            ),
+            (
+                r#"{ "$ref": "other.json" }"#,
+                "Invalid JSON Schema at `#/$ref`: cannot resolve `other.json` within the document.",
+            ),
+            (
+                r#"{ "items": [true, { "pattern": "(a" }] }"#,
+                "Invalid JSON Schema at `#/items/1/pattern`: Invalid pattern `(a`: unclosed `(`.",
+            ),
+            (r#"{ "anyOf": {} }"#, "Invalid JSON Schema at `#/anyOf`: expected an array."),
+            (r#"{ "not": 1 }"#, "Invalid JSON Schema at `#/not`: expected an object or a boolean."),
+            ("{ ", "Line 1: JSON Parsing Error: expected a key."),
+        ];
+        for (schema, expected) in cases {
+            assert_eq!(Schema::from_json_schema(schema).unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn ref_cycle() -> Result<(), String> {
+        assert_eq!(
+            violations(r##"{ "$ref": "#" }"##, "")?,
+            ["`$ref` `#` nests too deeply."]
+        );
+        // nesting through values is fine
+        let tree =
+            r##"{ "properties": { "child": { "$ref": "#" }, "leaf": { "type": "integer" } } }"##;
+        assert_eq!(
+            violations(tree, "child.child.child.leaf = 'x'")?,
+            ["`child.child.child.leaf`: Expected an integer, found a string."]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+//! Reading schemas from TOML schema files.
+
+// stdlib imports
+use std::path::Path;
+// my imports
+use super::{KeySchema, Kind, Pattern, Schema, TableSchema};
+use crate::datetime::DatetimeKind;
+use crate::span::dotted_key;
+use crate::tomltypes::{TOMLTable, TOMLType};
+
+impl Schema {
+    /// Reads a schema from a TOML schema document.
+    ///
+    /// The document describes the top-level table. Each value is described by a table whose
+    /// `type` is one of `any`, `string`, `integer`, `float`, `boolean`, `datetime` (any of
+    /// the four kinds), `offset-datetime`, `local-datetime`, `local-date`, `local-time`,
+    /// `array`, `array-of-tables`, or `table`. The type defaults to `table` if `keys` is
+    /// given and to `any` otherwise. Depending on the type, it may also have:
+    ///
+    /// | field             | type                         | meaning                               |
+    /// | ----------------- | ---------------------------- | ------------------------------------- |
+    /// | `pattern`         | `string`                     | see [`Pattern`]                       |
+    /// | `enum`            | `string`                     | the allowed values                    |
+    /// | `min`, `max`      | `integer`                    | inclusive bounds                      |
+    /// | `items`           | `array`, `array-of-tables`   | the schema of each element            |
+    /// | `keys`            | `table`                      | the schema of each key, by name       |
+    /// | `additional-keys` | `table`                      | whether other keys are allowed        |
+    /// | `optional`        | (any, within `keys`)         | whether the key may be left out       |
+    ///
+    /// ```
+    /// use tomlp::schema::Schema;
+    ///
+    /// let schema = Schema::from_toml(
+    ///     r#"
+    ///     additional-keys = false
+    ///
+    ///     [keys.name]
+    ///     type = "string"
+    ///     pattern = "^[a-z][a-z0-9-]*$"
+    ///
+    ///     [keys.port]
+    ///     type = "integer"
+    ///     min = 1
+    ///     max = 65535
+    ///     optional = true
+    ///
+    ///     [keys.released]
+    ///     type = "local-date"
+    ///
+    ///     [keys.bin]
+    ///     type = "array-of-tables"
+    ///     items.keys.path = { type = "string" }
+    ///     "#,
+    /// )?;
+    /// let parsed = tomlp::parse_str("name = 'web'\nreleased = 2024-05-01\n[[bin]]\npath = 'a'\n")?;
+    /// assert!(schema.validate(&parsed).is_ok());
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn from_toml(source: &str) -> Result<Schema, String> {
+        let parsed = crate::parse_str(source)?;
+        read_schema(parsed.table(), &mut Vec::new(), Describes::Document).map(|(schema, _)| schema)
+    }
+
+    /// Reads a schema from a file: a JSON Schema if it ends in `.json` (see
+    /// [`from_json_schema`](Self::from_json_schema)), and a TOML schema otherwise (see
+    /// [`from_toml`](Self::from_toml)).
+    pub fn load(file: &str) -> Result<Schema, String> {
+        let source = std::fs::read_to_string(file)
+            .map_err(|err| format!("File Read Error: {}", err.kind()))?;
+        if Path::new(file).extension().is_some_and(|ext| ext == "json") {
+            Self::from_json_schema(&source)
+        } else {
+            Self::from_toml(&source)
+        }
+    }
+}
+
+/// What a schema table describes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Describes {
+    /// The top-level table
+    Document,
+    /// The value of a key, which may be optional
+    Key,
+    /// An array element
+    Item,
+}
+
+/// Reads the schema described by `table`, along with whether it is optional.
+fn read_schema(
+    table: &TOMLTable,
+    path: &mut Vec<String>,
+    describes: Describes,
+) -> Result<(Schema, bool), String> {
+    let type_name = match table.get("type") {
+        Some(val) => val
+            .str()
+            .ok_or_else(|| field_error(path, "type", "expected a string"))?,
+        None if describes == Describes::Document || table.contains_key("keys") => "table",
+        None => "any",
+    };
+    let mut schema = match type_name {
+        "any" => Schema::any(),
+        "string" => Schema::string(),
+        "integer" => Schema::integer(),
+        "float" => Schema::float(),
+        "boolean" => Schema::boolean(),
+        "datetime" => Schema::datetime(),
+        "offset-datetime" => Schema::datetime_of(DatetimeKind::OffsetDatetime),
+        "local-datetime" => Schema::datetime_of(DatetimeKind::LocalDatetime),
+        "local-date" => Schema::datetime_of(DatetimeKind::LocalDate),
+        "local-time" => Schema::datetime_of(DatetimeKind::LocalTime),
+        "array" | "array-of-tables" => Schema::array(Schema::any()),
+        "table" => Schema::table(),
+        other => {
+            let reason = format!("unknown type `{}`", other);
+            return Err(field_error(path, "type", &reason));
+        }
+    };
+
+    let mut optional = false;
+    let mut fields: Vec<(&String, &TOMLType)> = table.iter().collect();
+    fields.sort_by_key(|(field, _)| *field);
+    for (field, val) in fields {
+        let error = |reason: &str| field_error(path, field, reason);
+        match (field.as_str(), &mut schema.kind) {
+            ("type", _) => (),
+            ("optional", _) if describes == Describes::Key => {
+                optional = read_bool(val).ok_or_else(|| error("expected a boolean"))?
+            }
+            ("pattern", Kind::String { pattern, .. }) => {
+                let source = val.str().ok_or_else(|| error("expected a string"))?;
+                *pattern =
+                    Some(Pattern::new(source).map_err(|err| error(err.trim_end_matches('.')))?);
+            }
+            ("enum", Kind::String { one_of, .. }) => {
+                let values = val
+                    .array()
+                    .and_then(|vals| vals.iter().map(|val| val.str().map(String::from)).collect())
+                    .ok_or_else(|| error("expected an array of strings"))?;
+                *one_of = Some(values);
+            }
+            ("min", Kind::Integer { min, .. }) => {
+                *min = Some(val.i128().ok_or_else(|| error("expected an integer"))?);
+            }
+            ("max", Kind::Integer { max, .. }) => {
+                *max = Some(val.i128().ok_or_else(|| error("expected an integer"))?);
+            }
+            ("items", Kind::Array(items)) => {
+                let item_table = read_table(val).ok_or_else(|| error("expected a table"))?;
+                path.push(field.clone());
+                let item_schema = read_schema(item_table, path, Describes::Item);
+                path.pop();
+                **items = item_schema?.0;
+            }
+            ("keys", Kind::Table(TableSchema { keys, .. })) => {
+                let key_tables = read_table(val).ok_or_else(|| error("expected a table"))?;
+                let mut names: Vec<&String> = key_tables.keys().collect();
+                names.sort();
+                path.push(field.clone());
+                for name in names {
+                    path.push(name.clone());
+                    let key_schema = read_table(&key_tables[name])
+                        .ok_or_else(|| field_error(path, "", "expected a table"))
+                        .and_then(|key_table| read_schema(key_table, path, Describes::Key));
+                    path.pop();
+                    let (key_schema, optional) = key_schema?;
+                    keys.push(KeySchema {
+                        name: name.clone(),
+                        schema: key_schema,
+                        required: !optional,
+                    });
+                }
+                path.pop();
+            }
+            ("additional-keys", Kind::Table(TableSchema { deny_unknown, .. })) => {
+                *deny_unknown = !read_bool(val).ok_or_else(|| error("expected a boolean"))?;
+            }
+            _ => {
+                let reason = format!("unknown field for type `{}`", type_name);
+                return Err(error(&reason));
+            }
+        }
+    }
+    Ok((schema, optional))
+}
+
+fn read_table(val: &TOMLType) -> Option<&TOMLTable> {
+    match val {
+        TOMLType::HTable(table) | TOMLType::DKTable(table) | TOMLType::InlineTable(table) => {
+            Some(table)
+        }
+        _ => None,
+    }
+}
+
+fn read_bool(val: &TOMLType) -> Option<bool> {
+    match val {
+        TOMLType::Bool(b) => Some(*b),
+        _ => None,
+    }
+}
+
+fn field_error(path: &[String], field: &str, reason: &str) -> String {
+    let mut path = path.to_vec();
+    if !field.is_empty() {
+        path.push(field.to_string());
+    }
+    format!("Invalid schema at `{}`: {}.", dotted_key(&path), reason)
+}
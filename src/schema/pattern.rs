@@ -0,0 +1,512 @@
+//! A small regular expression engine for string patterns.
+
+// stdlib imports
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A regular expression that a string must match somewhere within it. Anchor it with `^` and
+/// `$` to match the whole string, as with JSON Schema's `pattern`.
+///
+/// Supported syntax:
+///
+/// - literal characters, and `\` before any punctuation to match it literally
+/// - `.` (any character but a newline), `^`, `$`
+/// - classes such as `[a-z_]` and `[^0-9]`, and `\d`, `\w`, `\s` with their negations `\D`,
+///   `\W`, `\S`
+/// - `\n`, `\r`, `\t`
+/// - groups `(...)` and `(?:...)`, alternation `|`
+/// - `*`, `+`, `?`, `{n}`, `{n,}`, and `{n,m}` (a trailing `?` is accepted and has no effect
+///   on whether a string matches)
+///
+/// ```
+/// use tomlp::schema::Pattern;
+/// let semver = Pattern::new(r"^\d+\.\d+\.\d+(-[0-9A-Za-z.-]+)?$")?;
+/// assert!(semver.is_match("1.10.0-rc.1"));
+/// assert!(!semver.is_match("1.10"));
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    program: Vec<Inst>,
+}
+impl Pattern {
+    pub fn new(source: &str) -> Result<Self, String> {
+        let mut parser = PatternParser {
+            chars: source.chars().peekable(),
+        };
+        let program = parser
+            .alternation()
+            .and_then(|root| match parser.chars.next() {
+                None => Ok(root),
+                Some(_) => Err("unmatched `)`"),
+            })
+            .and_then(|root| {
+                let mut program = Vec::new();
+                compile(&root, &mut program)?;
+                program.push(Inst::Match);
+                Ok(program)
+            })
+            .map_err(|reason| format!("Invalid pattern `{}`: {}.", source, reason))?;
+        Ok(Self {
+            source: source.to_string(),
+            program,
+        })
+    }
+
+    /// The pattern as it was written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Determines if the pattern matches anywhere within `text`.
+    ///
+    /// Takes time proportional to the length of `text` times the size of the pattern, however
+    /// the pattern is written.
+    pub fn is_match(&self, text: &str) -> bool {
+        let len = text.chars().count();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut chars = text.chars();
+        for pos in 0..=len {
+            // a match may start anywhere
+            if current.add(&self.program, 0, pos, len) {
+                return true;
+            }
+            let Some(c) = chars.next() else {
+                break;
+            };
+            next.pcs.clear();
+            for &pc in &current.pcs {
+                let accepted = matches!(&self.program[pc], Inst::Char(node) if node.accepts(c));
+                if accepted && next.add(&self.program, pc + 1, pos + 1, len) {
+                    return true;
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        false
+    }
+}
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    /// Any character but a newline
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+impl Node {
+    /// Whether a single-character node matches `c`.
+    fn accepts(&self, c: char) -> bool {
+        match self {
+            Self::Char(expected) => c == *expected,
+            Self::Any => c != '\n',
+            Self::Class { ranges, negated } => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != *negated
+            }
+            _ => false,
+        }
+    }
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+const SPACE: &[(char, char)] = &[(' ', ' '), ('\t', '\r')];
+
+struct PatternParser<'p> {
+    chars: Peekable<Chars<'p>>,
+}
+impl PatternParser<'_> {
+    fn eat(&mut self, c: char) -> bool {
+        self.chars.next_if_eq(&c).is_some()
+    }
+
+    fn alternation(&mut self) -> Result<Node, &'static str> {
+        let mut alts = vec![self.concat()?];
+        while self.eat('|') {
+            alts.push(self.concat()?);
+        }
+        Ok(match alts.len() {
+            1 => alts.remove(0),
+            _ => Node::Alt(alts),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, &'static str> {
+        let mut nodes = Vec::new();
+        while let Some(&c) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn atom(&mut self) -> Result<Node, &'static str> {
+        Ok(match self.chars.next() {
+            Some('(') => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err("only `(?:` groups are supported");
+                }
+                let inner = self.alternation()?;
+                if !self.eat(')') {
+                    return Err("unclosed `(`");
+                }
+                inner
+            }
+            Some('[') => self.class()?,
+            Some('.') => Node::Any,
+            Some('^') => Node::Start,
+            Some('$') => Node::End,
+            Some('\\') => match self.escape()? {
+                Ok(c) => Node::Char(c),
+                Err(class) => class,
+            },
+            Some('*' | '+' | '?') => return Err("nothing to repeat"),
+            Some(c) => Node::Char(c),
+            None => return Err("unexpected end"),
+        })
+    }
+
+    /// An escape sequence, after the `\`: either a character or a class.
+    fn escape(&mut self) -> Result<Result<char, Node>, &'static str> {
+        let class = |ranges: &[(char, char)], negated| {
+            Err(Node::Class {
+                ranges: ranges.to_vec(),
+                negated,
+            })
+        };
+        Ok(match self.chars.next() {
+            Some('d') => class(DIGIT, false),
+            Some('D') => class(DIGIT, true),
+            Some('w') => class(WORD, false),
+            Some('W') => class(WORD, true),
+            Some('s') => class(SPACE, false),
+            Some('S') => class(SPACE, true),
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some(c) if c.is_ascii_punctuation() => Ok(c),
+            Some(_) => return Err("unsupported escape sequence"),
+            None => return Err("unexpected end after `\\`"),
+        })
+    }
+
+    /// A character class, after the `[`.
+    fn class(&mut self) -> Result<Node, &'static str> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        loop {
+            let lo = match self.chars.next() {
+                Some(']') => break,
+                Some('\\') => match self.escape()? {
+                    Ok(c) => c,
+                    Err(Node::Class {
+                        ranges: more,
+                        negated: false,
+                    }) => {
+                        ranges.extend(more);
+                        continue;
+                    }
+                    Err(_) => return Err("negated classes cannot appear within `[...]`"),
+                },
+                Some(c) => c,
+                None => return Err("unclosed `[`"),
+            };
+            let mut lookahead = self.chars.clone();
+            if lookahead.next() == Some('-') && !matches!(lookahead.peek(), Some(']') | None) {
+                self.chars.next();
+                let hi = match self.chars.next() {
+                    Some('\\') => match self.escape()? {
+                        Ok(c) => c,
+                        Err(_) => return Err("a class cannot end a range"),
+                    },
+                    Some(c) => c,
+                    None => return Err("unclosed `[`"),
+                };
+                if hi < lo {
+                    return Err("range out of order");
+                }
+                ranges.push((lo, hi));
+            } else {
+                ranges.push((lo, lo));
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    /// Applies a quantifier following `atom`, if any.
+    fn quantified(&mut self, atom: Node) -> Result<Node, &'static str> {
+        let quantifier = match self.chars.peek() {
+            Some(&c @ ('*' | '+' | '?' | '{')) => c,
+            _ => return Ok(atom),
+        };
+        self.chars.next();
+        let (min, max) = match quantifier {
+            '*' => (0, None),
+            '+' => (1, None),
+            '?' => (0, Some(1)),
+            _ => {
+                let min = self.number().ok_or("invalid repetition")?;
+                let max = if self.eat(',') {
+                    self.number()
+                } else {
+                    Some(min)
+                };
+                if !self.eat('}') {
+                    return Err("invalid repetition");
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err("repetition range out of order");
+                }
+                (min, max)
+            }
+        };
+        // lazy quantifiers match the same strings
+        self.eat('?');
+        if matches!(self.chars.peek(), Some('*' | '+' | '?' | '{')) {
+            return Err("nothing to repeat");
+        }
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let mut digits = String::new();
+        while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+            digits.push(c);
+        }
+        digits.parse().ok()
+    }
+}
+
+//////////////////
+// Matching
+//////////////////
+
+/// The most instructions a pattern may compile to, which bounds the work per character.
+const MAX_PROGRAM: usize = 100_000;
+
+/// An instruction of a compiled pattern, run by simulating every path through the program at
+/// once, a character at a time.
+#[derive(Debug, Clone)]
+enum Inst {
+    /// Consumes a character that the single-character node accepts.
+    Char(Node),
+    Start,
+    End,
+    /// Continues at both instructions.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// Appends the instructions for `node` to `program`.
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Result<(), &'static str> {
+    if program.len() > MAX_PROGRAM {
+        return Err("pattern too large");
+    }
+    match node {
+        Node::Char(_) | Node::Any | Node::Class { .. } => program.push(Inst::Char(node.clone())),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program)?;
+            }
+        }
+        Node::Alt(alts) => {
+            // each alternative but the last is tried by a split, then jumps past the rest
+            let mut jumps = Vec::new();
+            for (i, alt) in alts.iter().enumerate() {
+                if i + 1 == alts.len() {
+                    compile(alt, program)?;
+                    break;
+                }
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(alt, program)?;
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+            for jump in jumps {
+                program[jump] = Inst::Jump(program.len());
+            }
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(node, program)?;
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    // each optional repetition can skip to the end
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(program.len() + 1, 0));
+                        compile(node, program)?;
+                    }
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, program.len());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The instructions waiting for the character at one position, each listed once.
+struct Threads {
+    pcs: Vec<usize>,
+    /// The position each instruction was last added at
+    added: Vec<usize>,
+    stack: Vec<usize>,
+}
+impl Threads {
+    fn new(len: usize) -> Self {
+        Self {
+            pcs: Vec::new(),
+            added: vec![usize::MAX; len],
+            stack: Vec::new(),
+        }
+    }
+
+    /// Adds the instructions reachable from `pc` at `pos` without consuming a character.
+    /// Returns whether the pattern matched.
+    fn add(&mut self, program: &[Inst], pc: usize, pos: usize, len: usize) -> bool {
+        self.stack.push(pc);
+        while let Some(pc) = self.stack.pop() {
+            if self.added[pc] == pos {
+                continue;
+            }
+            self.added[pc] = pos;
+            match program[pc] {
+                Inst::Char(_) => self.pcs.push(pc),
+                Inst::Start if pos == 0 => self.stack.push(pc + 1),
+                Inst::End if pos == len => self.stack.push(pc + 1),
+                Inst::Start | Inst::End => (),
+                Inst::Split(first, second) => self.stack.extend([second, first]),
+                Inst::Jump(to) => self.stack.push(to),
+                Inst::Match => {
+                    self.stack.clear();
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+
+    #[test]
+    fn matching() -> Result<(), String> {
+        let cases: [(&str, &[&str], &[&str]); 9] = [
+            ("abc", &["abc", "xabcx"], &["ab", "acb"]),
+            ("^a.c$", &["abc", "a-c"], &["a\nc", "abcd"]),
+            (
+                "^[a-z_][a-z0-9_-]*$",
+                &["snake_case", "kebab-case1"],
+                &["Camel", "1x", ""],
+            ),
+            (r"^\d{3}-\d{4}$", &["555-1234"], &["55-1234", "555-12345"]),
+            ("^(ab|cd)+$", &["ab", "abcdab"], &["", "abc"]),
+            ("^(?:a*)*b$", &["b", "aaab"], &["aaa"]),
+            (
+                r"^\w+@\w+\.(com|org)$",
+                &["me@site.org"],
+                &["me@site.net", "@site.com"],
+            ),
+            ("^[^ ]{2,3}?$", &["ab", "abc"], &["a", "abcd", "a b"]),
+            (r"^\$\{[A-Z]+\}$", &["${HOME}"], &["$HOME"]),
+        ];
+        for (source, matching, other) in cases {
+            let pattern = Pattern::new(source)?;
+            for text in matching {
+                assert!(
+                    pattern.is_match(text),
+                    "`{}` should match `{}`",
+                    source,
+                    text
+                );
+            }
+            for text in other {
+                assert!(
+                    !pattern.is_match(text),
+                    "`{}` should not match `{}`",
+                    source,
+                    text
+                );
+            }
+        }
+        // long inputs don't recurse once per character
+        assert!(Pattern::new("^a.*z$")?.is_match(&format!("a{}z", "b".repeat(1_000_000))));
+        assert!(Pattern::new("^(?:ab)*$")?.is_match(&"ab".repeat(100_000)));
+        // nested quantifiers take linear time, rather than doubling for each character
+        let nested = Pattern::new("^(a+)+$")?;
+        assert!(!nested.is_match(&format!("{}b", "a".repeat(10_000))));
+        assert!(nested.is_match(&"a".repeat(10_000)));
+        assert!(Pattern::new("^(a|aa)*c")?.is_match(&format!("{}c", "a".repeat(10_000))));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        for source in [
+            "(a",
+            "a)",
+            "[a-",
+            "*a",
+            "a**",
+            "[z-a]",
+            "a{2,1}",
+            r"\q",
+            "a{x}",
+            "(a{1000}){1000}",
+        ] {
+            assert!(Pattern::new(source).is_err(), "{}", source);
+        }
+        let err = Pattern::new("(a").unwrap_err();
+        assert_eq!(err, "Invalid pattern `(a`: unclosed `(`.");
+    }
+}
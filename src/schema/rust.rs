@@ -0,0 +1,396 @@
+//! Generating Rust types from schemas.
+
+// stdlib imports
+use std::collections::{HashSet, VecDeque};
+// my imports
+use super::{Kind, Schema, TableSchema};
+use crate::datetime::DatetimeKind;
+use crate::span::dotted_key;
+
+/// Settings for [`Schema::to_rust`].
+///
+/// ```
+/// use tomlp::schema::{DatetimeTypes, RustOptions};
+/// let options = RustOptions {
+///     root: "Manifest".to_string(),
+///     datetimes: DatetimeTypes::Chrono,
+///     ..RustOptions::default()
+/// };
+/// # assert_eq!(options.derives, RustOptions::default().derives);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustOptions {
+    /// The name of the type for the whole document.
+    pub root: String,
+    /// What each struct derives. `serde` renames are written for keys that aren't Rust
+    /// identifiers if any of these is from `serde`. The default `serde` derives need this
+    /// crate's `serde` feature for the fields of its types, which also provides
+    /// [`ParsedTOML::deserialize`](crate::ParsedTOML) to fill them in.
+    pub derives: Vec<String>,
+    pub datetimes: DatetimeTypes,
+}
+impl Default for RustOptions {
+    fn default() -> Self {
+        Self {
+            root: "Config".to_string(),
+            derives: [
+                "Debug",
+                "Clone",
+                "PartialEq",
+                "serde::Deserialize",
+                "serde::Serialize",
+            ]
+            .map(String::from)
+            .to_vec(),
+            datetimes: DatetimeTypes::default(),
+        }
+    }
+}
+
+/// Which crate's types the generated code uses for dates and times.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DatetimeTypes {
+    /// [`Datetime`](crate::Datetime), [`Date`](crate::Date), and [`Time`](crate::Time)
+    #[default]
+    Tomlp,
+    /// `chrono::DateTime<FixedOffset>`, `NaiveDateTime`, `NaiveDate`, and `NaiveTime`; a
+    /// datetime of no one kind is still a [`Datetime`](crate::Datetime)
+    Chrono,
+}
+
+impl Schema {
+    /// Writes Rust type definitions for documents matching this schema: a struct for each
+    /// table with known keys, `Vec`s for arrays and arrays of tables, and `Option`s for
+    /// optional keys. Values of any type are [`TOMLType`](crate::TOMLType)s, and tables
+    /// without known keys are [`TOMLTable`](crate::TOMLTable)s.
+    ///
+    /// ```
+    /// use tomlp::schema::{RustOptions, Schema};
+    ///
+    /// let example = tomlp::parse_str("name = 'web'\nreleased = 2024-05-01\n[[bin]]\npath = 'a'")?;
+    /// let code = Schema::infer([example.table()]).to_rust(&RustOptions::default());
+    /// assert_eq!(
+    ///     code,
+    ///     "\
+    /// /// The whole document.
+    /// #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+    /// pub struct Config {
+    ///     pub bin: Vec<Bin>,
+    ///     pub name: String,
+    ///     pub released: tomlp::Date,
+    /// }
+    ///
+    /// /// An item of `bin`.
+    /// #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+    /// pub struct Bin {
+    ///     pub path: String,
+    /// }
+    /// "
+    /// );
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn to_rust(&self, options: &RustOptions) -> String {
+        let mut gen = RustGen {
+            options,
+            names: HashSet::from([options.root.clone()]),
+            queue: VecDeque::new(),
+        };
+        let mut out = String::new();
+        match &self.kind {
+            Kind::Table(table) if !table.keys.is_empty() => {
+                gen.queue.push_back(Pending {
+                    name: options.root.clone(),
+                    table,
+                    path: Vec::new(),
+                    item: false,
+                });
+            }
+            _ => {
+                let root_type = gen.rust_type(self, &options.root, &mut Vec::new(), false);
+                out.push_str(&format!("pub type {} = {};\n", options.root, root_type));
+            }
+        }
+        while let Some(pending) = gen.queue.pop_front() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&gen.write_struct(pending));
+        }
+        out
+    }
+}
+
+/// A struct that's been named but not yet written.
+struct Pending<'s> {
+    name: String,
+    table: &'s TableSchema,
+    path: Vec<String>,
+    /// Whether the table is an element of an array.
+    item: bool,
+}
+
+struct RustGen<'s> {
+    options: &'s RustOptions,
+    /// Type names in use
+    names: HashSet<String>,
+    queue: VecDeque<Pending<'s>>,
+}
+
+impl<'s> RustGen<'s> {
+    fn write_struct(&mut self, pending: Pending<'s>) -> String {
+        let mut out = match (pending.path.is_empty(), pending.item) {
+            (true, _) => "/// The whole document.\n".to_string(),
+            (false, true) => format!("/// An item of `{}`.\n", dotted_key(&pending.path)),
+            (false, false) => format!("/// The `{}` table.\n", dotted_key(&pending.path)),
+        };
+        if !self.options.derives.is_empty() {
+            out.push_str(&format!("#[derive({})]\n", self.options.derives.join(", ")));
+        }
+        out.push_str(&format!("pub struct {} {{\n", pending.name));
+        let serde = self
+            .options
+            .derives
+            .iter()
+            .any(|derive| derive.contains("serde"));
+        let mut fields = HashSet::new();
+        let mut path = pending.path;
+        for entry in &pending.table.keys {
+            path.push(entry.name.clone());
+            let mut rust_type = self.rust_type(&entry.schema, &pending.name, &mut path, false);
+            path.pop();
+            if !entry.required {
+                rust_type = format!("Option<{}>", rust_type);
+            }
+            let field = unique(field_name(&entry.name), &mut fields);
+            if serde && field.trim_start_matches("r#") != entry.name {
+                out.push_str(&format!("    #[serde(rename = {:?})]\n", entry.name));
+            }
+            out.push_str(&format!("    pub {}: {},\n", field, rust_type));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The type for values matching `schema` at `path`, queuing a struct if it needs one.
+    fn rust_type(
+        &mut self,
+        schema: &'s Schema,
+        parent: &str,
+        path: &mut Vec<String>,
+        item: bool,
+    ) -> String {
+        let chrono = self.options.datetimes == DatetimeTypes::Chrono;
+        match &schema.kind {
+            Kind::Any | Kind::Json(_) => "tomlp::TOMLType".to_string(),
+            Kind::String { .. } => "String".to_string(),
+            Kind::Integer { .. } => "i64".to_string(),
+            Kind::Float => "f64".to_string(),
+            Kind::Boolean => "bool".to_string(),
+            Kind::Datetime(kind) => match (kind, chrono) {
+                (Some(DatetimeKind::OffsetDatetime), true) => {
+                    "chrono::DateTime<chrono::FixedOffset>"
+                }
+                (Some(DatetimeKind::LocalDatetime), true) => "chrono::NaiveDateTime",
+                (Some(DatetimeKind::LocalDate), true) => "chrono::NaiveDate",
+                (Some(DatetimeKind::LocalTime), true) => "chrono::NaiveTime",
+                (Some(DatetimeKind::LocalDate), false) => "tomlp::Date",
+                (Some(DatetimeKind::LocalTime), false) => "tomlp::Time",
+                _ => "tomlp::Datetime",
+            }
+            .to_string(),
+            Kind::Array(items) => format!("Vec<{}>", self.rust_type(items, parent, path, true)),
+            Kind::Table(table) if table.keys.is_empty() => "tomlp::TOMLTable".to_string(),
+            Kind::Table(table) => {
+                let key = path.last().map_or("", String::as_str);
+                let mut name = type_name(key);
+                if self.names.contains(&name) {
+                    name = format!("{}{}", parent, name);
+                }
+                let name = unique(name, &mut self.names);
+                self.queue.push_back(Pending {
+                    name: name.clone(),
+                    table,
+                    path: path.clone(),
+                    item,
+                });
+                name
+            }
+        }
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+/// `name`, or `name` with the first free number appended, marking it as taken.
+fn unique(name: String, taken: &mut HashSet<String>) -> String {
+    let name = (1..)
+        .map(|n| match n {
+            1 => name.clone(),
+            n => format!("{}{}", name, n),
+        })
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or(name);
+    taken.insert(name.clone());
+    name
+}
+
+/// Splits a key into words at punctuation, spaces, and lowercase-uppercase boundaries.
+fn words(key: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut prev_lower = false;
+    for c in key.chars() {
+        if !c.is_alphanumeric() {
+            prev_lower = false;
+            words.push(String::new());
+            continue;
+        }
+        if words.is_empty() || (c.is_uppercase() && prev_lower) {
+            words.push(String::new());
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        if let Some(word) = words.last_mut() {
+            word.push(c);
+        }
+    }
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+/// A field name for `key` (ex. `max_connections` for `maxConnections`).
+fn field_name(key: &str) -> String {
+    let name = words(key).join("_").to_lowercase();
+    match name.as_str() {
+        "" => "field".to_string(),
+        "self" | "super" | "crate" | "_" => format!("{}_", name),
+        _ if name.starts_with(|c: char| c.is_numeric()) => format!("_{}", name),
+        _ if KEYWORDS.contains(&name.as_str()) => format!("r#{}", name),
+        _ => name,
+    }
+}
+
+/// A type name for `key` (ex. `HttpServer` for `http-server`).
+fn type_name(key: &str) -> String {
+    let name: String = words(key)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        })
+        .collect();
+    match name.as_str() {
+        "" => "Table".to_string(),
+        "Self" => "Self_".to_string(),
+        _ if name.starts_with(|c: char| c.is_numeric()) => format!("T{}", name),
+        _ => name,
+    }
+}
+
+/// Words that must be written as raw identifiers.
+const KEYWORDS: [&str; 47] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{field_name, type_name, DatetimeTypes, RustOptions};
+    use crate::schema::Schema;
+    use crate::DatetimeKind;
+
+    #[test]
+    fn names() {
+        let fields = [
+            ("name", "name"),
+            ("maxConnections", "max_connections"),
+            ("HTTPServer", "httpserver"),
+            ("odd key", "odd_key"),
+            ("type", "r#type"),
+            ("self", "self_"),
+            ("2fa", "_2fa"),
+            ("", "field"),
+        ];
+        for (key, field) in fields {
+            assert_eq!(field_name(key), field);
+        }
+        let types = [
+            ("http-server", "HttpServer"),
+            ("bin", "Bin"),
+            ("maxConnections", "MaxConnections"),
+            ("1", "T1"),
+            ("", "Table"),
+        ];
+        for (key, name) in types {
+            assert_eq!(type_name(key), name);
+        }
+    }
+
+    #[test]
+    fn structs() {
+        let server = Schema::table()
+            .required("host", Schema::string())
+            .optional("started", Schema::datetime_of(DatetimeKind::OffsetDatetime));
+        let schema = Schema::table()
+            .required("server", server.clone())
+            .required("backup", Schema::table().required("server", server))
+            .optional("odd key", Schema::array(Schema::array(Schema::float())))
+            .required("extra", Schema::table())
+            .required("any", Schema::any());
+        let options = RustOptions {
+            derives: vec!["Debug".to_string()],
+            datetimes: DatetimeTypes::Chrono,
+            ..RustOptions::default()
+        };
+        let expected = "\
+/// The whole document.
+#[derive(Debug)]
+pub struct Config {
+    pub server: Server,
+    pub backup: Backup,
+    pub odd_key: Option<Vec<Vec<f64>>>,
+    pub extra: tomlp::TOMLTable,
+    pub any: tomlp::TOMLType,
+}
+
+/// The `server` table.
+#[derive(Debug)]
+pub struct Server {
+    pub host: String,
+    pub started: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+/// The `backup` table.
+#[derive(Debug)]
+pub struct Backup {
+    pub server: BackupServer,
+}
+
+/// The `backup.server` table.
+#[derive(Debug)]
+pub struct BackupServer {
+    pub host: String,
+    pub started: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+";
+        assert_eq!(schema.to_rust(&options), expected);
+
+        let renamed = Schema::table()
+            .required("type", Schema::integer())
+            .required("odd key", Schema::boolean())
+            .required("odd-key", Schema::boolean());
+        let code = renamed.to_rust(&RustOptions::default());
+        assert!(code.contains("    pub r#type: i64,\n"));
+        assert!(code.contains("    #[serde(rename = \"odd key\")]\n    pub odd_key: bool,\n"));
+        assert!(code.contains("    #[serde(rename = \"odd-key\")]\n    pub odd_key2: bool,\n"));
+        assert_eq!(
+            Schema::array(Schema::integer()).to_rust(&RustOptions::default()),
+            "pub type Config = Vec<i64>;\n"
+        );
+    }
+}
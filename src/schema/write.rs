@@ -0,0 +1,270 @@
+//! Writing schemas as TOML schema files and JSON Schemas.
+
+// my imports
+use super::{KeySchema, Kind, Schema, TableSchema};
+use crate::datetime::DatetimeKind;
+use crate::json::Json;
+use crate::lexer::quote_basic;
+use crate::span::dotted_key;
+
+impl Schema {
+    /// Writes this schema as a TOML schema document, which [`from_toml`](Self::from_toml)
+    /// reads back. A JSON Schema within is written as type `any`.
+    ///
+    /// ```
+    /// use tomlp::schema::Schema;
+    ///
+    /// let schema = Schema::table()
+    ///     .required("name", Schema::string())
+    ///     .optional("tags", Schema::array(Schema::string()));
+    /// assert_eq!(
+    ///     schema.to_toml(),
+    ///     "[keys]\n\
+    ///      name = { type = \"string\" }\n\
+    ///      tags = { type = \"array\", optional = true, items = { type = \"string\" } }\n"
+    /// );
+    /// assert_eq!(Schema::from_toml(&schema.to_toml())?, schema);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        write_toml(self, &mut Vec::new(), false, &mut out);
+        out
+    }
+
+    /// Writes this schema as a JSON Schema (draft 2020-12), following the conventions of
+    /// [`from_json_schema`](Self::from_json_schema). A datetime of any kind is written as a
+    /// plain string.
+    ///
+    /// ```
+    /// use tomlp::schema::Schema;
+    /// use tomlp::DatetimeKind;
+    ///
+    /// let schema = Schema::table().required("day", Schema::datetime_of(DatetimeKind::LocalDate));
+    /// assert_eq!(
+    ///     schema.to_json_schema(),
+    ///     r#"{
+    ///   "$schema": "https://json-schema.org/draft/2020-12/schema",
+    ///   "type": "object",
+    ///   "properties": {
+    ///     "day": {
+    ///       "type": "string",
+    ///       "format": "date"
+    ///     }
+    ///   },
+    ///   "required": ["day"]
+    /// }
+    /// "#
+    /// );
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn to_json_schema(&self) -> String {
+        let mut json = self.to_json();
+        if let Json::Object(fields) = &mut json {
+            let version = "https://json-schema.org/draft/2020-12/schema".to_string();
+            fields.insert(0, ("$schema".to_string(), Json::String(version)));
+        }
+        json.pretty() + "\n"
+    }
+
+    fn to_json(&self) -> Json {
+        let typed = |name: &str| vec![("type".to_string(), Json::String(name.to_string()))];
+        let fields = match &self.kind {
+            Kind::Any => Vec::new(),
+            Kind::String { pattern, one_of } => {
+                let mut fields = typed("string");
+                if let Some(pattern) = pattern {
+                    fields.push(("pattern".to_string(), Json::String(pattern.to_string())));
+                }
+                if let Some(values) = one_of {
+                    let values = values.iter().cloned().map(Json::String).collect();
+                    fields.push(("enum".to_string(), Json::Array(values)));
+                }
+                fields
+            }
+            Kind::Integer { min, max } => {
+                let mut fields = typed("integer");
+                if let Some(min) = min {
+                    fields.push(("minimum".to_string(), Json::Integer(*min)));
+                }
+                if let Some(max) = max {
+                    fields.push(("maximum".to_string(), Json::Integer(*max)));
+                }
+                fields
+            }
+            Kind::Float => typed("number"),
+            Kind::Boolean => typed("boolean"),
+            Kind::Datetime(kind) => {
+                let mut fields = typed("string");
+                if let Some(kind) = kind {
+                    let format = Json::String(json_format(*kind).to_string());
+                    fields.push(("format".to_string(), format));
+                }
+                fields
+            }
+            Kind::Array(items) => {
+                let mut fields = typed("array");
+                if items.kind != Kind::Any {
+                    fields.push(("items".to_string(), items.to_json()));
+                }
+                fields
+            }
+            Kind::Table(TableSchema { keys, deny_unknown }) => {
+                let mut fields = typed("object");
+                let required: Vec<Json> = keys
+                    .iter()
+                    .filter(|entry| entry.required)
+                    .map(|entry| Json::String(entry.name.clone()))
+                    .collect();
+                if !keys.is_empty() {
+                    let properties = keys
+                        .iter()
+                        .map(|entry| (entry.name.clone(), entry.schema.to_json()))
+                        .collect();
+                    fields.push(("properties".to_string(), Json::Object(properties)));
+                }
+                if !required.is_empty() {
+                    fields.push(("required".to_string(), Json::Array(required)));
+                }
+                if *deny_unknown {
+                    fields.push(("additionalProperties".to_string(), Json::Bool(false)));
+                }
+                fields
+            }
+            Kind::Json(json) => return json.root().clone(),
+        };
+        Json::Object(fields)
+    }
+}
+
+///////////////////
+// Helper Functions
+///////////////////
+
+/// Writes `schema` under the table header `header` (none for the document), with its simple
+/// keys and items inline and the rest under headers of their own.
+fn write_toml(schema: &Schema, header: &mut Vec<String>, optional: bool, out: &mut String) {
+    if !header.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("[{}]\n", dotted_key(header)));
+    }
+    for (field, val) in toml_fields(schema, optional, !header.is_empty()) {
+        out.push_str(&format!("{} = {}\n", field, val));
+    }
+    match &schema.kind {
+        Kind::Array(items) if is_simple(items) => {
+            out.push_str(&format!("items = {}\n", inline_toml(items, false)));
+        }
+        Kind::Array(items) => {
+            header.push("items".to_string());
+            write_toml(items, header, false, out);
+            header.pop();
+        }
+        Kind::Table(TableSchema { keys, .. }) => {
+            let (simple, nested): (Vec<&KeySchema>, Vec<&KeySchema>) =
+                keys.iter().partition(|entry| is_simple(&entry.schema));
+            header.push("keys".to_string());
+            if !simple.is_empty() {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                out.push_str(&format!("[{}]\n", dotted_key(header)));
+                for entry in simple {
+                    let key = dotted_key(&[&entry.name]);
+                    let val = inline_toml(&entry.schema, !entry.required);
+                    out.push_str(&format!("{} = {}\n", key, val));
+                }
+            }
+            for entry in nested {
+                header.push(entry.name.clone());
+                write_toml(&entry.schema, header, !entry.required, out);
+                header.pop();
+            }
+            header.pop();
+        }
+        _ => (),
+    }
+}
+
+/// Whether `schema` can be written as an inline table: it has no keys, nor items with keys.
+fn is_simple(schema: &Schema) -> bool {
+    match &schema.kind {
+        Kind::Array(items) => is_simple(items),
+        Kind::Table(TableSchema { keys, .. }) => keys.is_empty(),
+        _ => true,
+    }
+}
+
+fn inline_toml(schema: &Schema, optional: bool) -> String {
+    let mut fields: Vec<String> = toml_fields(schema, optional, true)
+        .into_iter()
+        .map(|(field, val)| format!("{} = {}", field, val))
+        .collect();
+    if let Kind::Array(items) = &schema.kind {
+        fields.push(format!("items = {}", inline_toml(items, false)));
+    }
+    format!("{{ {} }}", fields.join(", "))
+}
+
+/// The fields of `schema` with values (ex. `type`), but not its items or keys. The type of a
+/// table is left out of the document, where it's the default.
+fn toml_fields(schema: &Schema, optional: bool, typed: bool) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+    let type_name = match &schema.kind {
+        Kind::Any | Kind::Json(_) => "any",
+        Kind::String { .. } => "string",
+        Kind::Integer { .. } => "integer",
+        Kind::Float => "float",
+        Kind::Boolean => "boolean",
+        Kind::Datetime(None) => "datetime",
+        Kind::Datetime(Some(DatetimeKind::OffsetDatetime)) => "offset-datetime",
+        Kind::Datetime(Some(DatetimeKind::LocalDatetime)) => "local-datetime",
+        Kind::Datetime(Some(DatetimeKind::LocalDate)) => "local-date",
+        Kind::Datetime(Some(DatetimeKind::LocalTime)) => "local-time",
+        Kind::Array(items) if matches!(items.kind, Kind::Table(_)) => "array-of-tables",
+        Kind::Array(_) => "array",
+        Kind::Table(_) => "table",
+    };
+    if typed || type_name != "table" {
+        fields.push(("type", quote_basic(type_name)));
+    }
+    if optional {
+        fields.push(("optional", "true".to_string()));
+    }
+    match &schema.kind {
+        Kind::String { pattern, one_of } => {
+            if let Some(pattern) = pattern {
+                fields.push(("pattern", quote_basic(pattern.as_str())));
+            }
+            if let Some(values) = one_of {
+                let values: Vec<String> = values.iter().map(|val| quote_basic(val)).collect();
+                fields.push(("enum", format!("[{}]", values.join(", "))));
+            }
+        }
+        Kind::Integer { min, max } => {
+            if let Some(min) = min {
+                fields.push(("min", min.to_string()));
+            }
+            if let Some(max) = max {
+                fields.push(("max", max.to_string()));
+            }
+        }
+        Kind::Table(TableSchema {
+            deny_unknown: true, ..
+        }) => fields.push(("additional-keys", "false".to_string())),
+        _ => (),
+    }
+    fields
+}
+
+/// The `format` of a datetime kind. See [`Schema::from_json_schema`].
+fn json_format(kind: DatetimeKind) -> &'static str {
+    match kind {
+        DatetimeKind::OffsetDatetime => "date-time",
+        DatetimeKind::LocalDatetime => "local-date-time",
+        DatetimeKind::LocalDate => "date",
+        DatetimeKind::LocalTime => "local-time",
+    }
+}
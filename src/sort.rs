@@ -0,0 +1,287 @@
+//! Sorting the keys of chosen tables, on top of the [`syntax`](crate::syntax) tree.
+
+// my imports
+use crate::syntax::{Array, Content, Document, Item, Line, Trivia, Value};
+
+/// Settings for [`sort`].
+///
+/// ```
+/// use tomlp::SortOptions;
+/// let options = SortOptions {
+///     tables: vec!["features".to_string()],
+///     arrays: true,
+/// };
+/// # assert!(!SortOptions::default().arrays);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortOptions {
+    /// The tables whose keys are sorted, as dotted keys in which `*` matches any one key (ex.
+    /// `target.*.dependencies`). Defaults to the dependency tables of a `Cargo.toml`.
+    pub tables: Vec<String>,
+    /// Whether arrays of strings within those tables are sorted too, including ones within
+    /// inline tables (ex. the `features` of a dependency).
+    pub arrays: bool,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        let tables = [
+            "dependencies",
+            "dev-dependencies",
+            "build-dependencies",
+            "workspace.dependencies",
+            "target.*.dependencies",
+            "target.*.dev-dependencies",
+            "target.*.build-dependencies",
+        ];
+        Self {
+            tables: tables.iter().map(|table| table.to_string()).collect(),
+            arrays: false,
+        }
+    }
+}
+
+/// Sorts the keys of the tables chosen by [`SortOptions::tables`], changing nothing else
+/// about the document.
+///
+/// Only tables with headers of their own are sorted, not ones defined by dotted keys or
+/// inline. Keys are sorted within each run of lines between blank lines, so that groups
+/// of keys stay apart, and the comment lines directly above a key move along with it, as
+/// does a comment after it. Strings are sorted by their contents, not as written.
+///
+/// ```
+/// use tomlp::SortOptions;
+///
+/// let source = "\
+/// [dependencies]
+/// serde = { version = '1', features = ['rc', 'derive'] }
+/// ## for errors
+/// anyhow = '1'
+///
+/// zip = '2'
+/// ";
+/// let options = SortOptions {
+///     arrays: true,
+///     ..SortOptions::default()
+/// };
+/// assert_eq!(
+///     tomlp::sort(source, &options)?,
+///     "\
+/// [dependencies]
+/// ## for errors
+/// anyhow = '1'
+/// serde = { version = '1', features = ['derive', 'rc'] }
+///
+/// zip = '2'
+/// "
+/// );
+/// # Ok::<(), String>(())
+/// ```
+pub fn sort(source: &str, options: &SortOptions) -> Result<String, String> {
+    crate::parse_str(source)?;
+    let mut doc = Document::parse(source)?;
+    let patterns: Vec<Vec<&str>> = options
+        .tables
+        .iter()
+        .map(|table| table.split('.').map(str::trim).collect())
+        .collect();
+
+    let mut start = 0;
+    while start < doc.lines.len() {
+        // the lines of a table: its header and the lines up to the next one
+        let end = (start + 1..doc.lines.len())
+            .find(|i| matches!(doc.lines[*i].content, Content::Header(_)))
+            .unwrap_or(doc.lines.len());
+        let chosen = match &doc.lines[start].content {
+            Content::Header(header) => {
+                let names = header.key.names()?;
+                patterns.iter().any(|pattern| matches(pattern, &names))
+            }
+            _ => false,
+        };
+        if chosen {
+            sort_table(&mut doc.lines[start + 1..end], options.arrays)?;
+        }
+        start = end;
+    }
+    Ok(doc.to_string())
+}
+
+fn matches(pattern: &[&str], names: &[String]) -> bool {
+    pattern.len() == names.len()
+        && pattern
+            .iter()
+            .zip(names)
+            .all(|(seg, name)| *seg == "*" || seg == name)
+}
+
+/// Sorts the keys of each run of non-blank `lines`, along with their comments.
+fn sort_table(lines: &mut [Line], arrays: bool) -> Result<(), String> {
+    for run in lines.split_mut(Line::is_blank) {
+        // each key's line, after the comment lines above it
+        let mut entries: Vec<(Vec<String>, Vec<Line>)> = Vec::new();
+        let mut pending = Vec::new();
+        for line in run.iter() {
+            pending.push(line.clone());
+            if let Content::KeyValue(kv) = &line.content {
+                entries.push((kv.key.names()?, std::mem::take(&mut pending)));
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        // lines end as they did in place, in case the last one has no line ending
+        let newlines: Vec<String> = run.iter().map(|line| line.newline.clone()).collect();
+        let sorted = entries
+            .into_iter()
+            .flat_map(|(_, lines)| lines)
+            .chain(pending);
+        for ((slot, mut line), newline) in run.iter_mut().zip(sorted).zip(newlines) {
+            line.newline = newline;
+            if arrays {
+                if let Content::KeyValue(kv) = &mut line.content {
+                    sort_arrays(&mut kv.value)?;
+                }
+            }
+            *slot = line;
+        }
+    }
+    Ok(())
+}
+
+/// Sorts the arrays of strings within `val`.
+fn sort_arrays(val: &mut Value) -> Result<(), String> {
+    match val {
+        Value::Scalar { .. } => Ok(()),
+        Value::InlineTable(table) => table
+            .items
+            .iter_mut()
+            .try_for_each(|item| sort_arrays(&mut item.value.value)),
+        Value::Array(array) => {
+            array
+                .items
+                .iter_mut()
+                .try_for_each(|item| sort_arrays(&mut item.value))?;
+            let mut strings = Vec::new();
+            for item in &array.items {
+                match item.value.string()? {
+                    Some(string) => strings.push(string),
+                    None => return Ok(()),
+                }
+            }
+            let mut order: Vec<usize> = (0..strings.len()).collect();
+            order.sort_by(|a, b| strings[*a].cmp(&strings[*b]));
+            if order.iter().enumerate().any(|(slot, i)| slot != *i) {
+                reorder(array, &order);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Puts the elements of `array` in `order`. The whitespace and commas stay in place, while
+/// the comments move with the elements they belong to: those on lines of their own above an
+/// element, and one on the same line after it.
+fn reorder(array: &mut Array, order: &[usize]) {
+    let has_comments = |trivia: &[Trivia]| trivia.iter().any(|t| matches!(t, Trivia::Comment(_)));
+    if !has_comments(&array.trailing)
+        && array
+            .items
+            .iter()
+            .all(|item| !has_comments(&item.leading) && !has_comments(&item.before_comma))
+    {
+        let values: Vec<Value> = order
+            .iter()
+            .map(|i| array.items[*i].value.clone())
+            .collect();
+        for (item, val) in array.items.iter_mut().zip(values) {
+            item.value = val;
+        }
+        return;
+    }
+
+    // Split the trivia before each element (and the `]`, which stays last) at its first line
+    // ending. Up to there, it ends the line of the element before, so it moves with that
+    // element, while the rest (comments and indentation) moves with the element after.
+    // Without a line ending, it's only whitespace, so it stays in place.
+    let n = array.items.len();
+    let mut kept = vec![Vec::new(); n + 1];
+    let mut before = vec![Vec::new(); n + 1];
+    let mut after = vec![Vec::new(); n];
+    for i in 0..=n {
+        let mut trivia = match array.items.get_mut(i) {
+            Some(item) => std::mem::take(&mut item.leading),
+            None => std::mem::take(&mut array.trailing),
+        };
+        match trivia.iter().position(|t| matches!(t, Trivia::Newline(_))) {
+            None => kept[i] = trivia,
+            Some(newline) => {
+                before[i] = trivia.split_off(newline + 1);
+                match i {
+                    // the line of the `[`
+                    0 => kept[0] = trivia,
+                    _ => after[i - 1] = trivia,
+                }
+            }
+        }
+    }
+
+    let old: Vec<Item<Value>> = array.items.clone();
+    let order = order.iter().copied().chain([n]);
+    let mut prev: Option<usize> = None;
+    for (slot, i) in order.enumerate() {
+        let mut leading = std::mem::take(&mut kept[slot]);
+        if let Some(prev) = prev {
+            leading.extend_from_slice(&after[prev]);
+        }
+        leading.append(&mut before[i]);
+        match array.items.get_mut(slot) {
+            Some(item) => {
+                item.leading = leading;
+                item.value = old[i].value.clone();
+                item.before_comma = old[i].before_comma.clone();
+            }
+            None => array.trailing = leading,
+        }
+        prev = Some(i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sort, SortOptions};
+
+    #[test]
+    fn keys() -> Result<(), String> {
+        let options = SortOptions::default();
+        let source = "b = 1\na = 2\n\n[dependencies]\n  c = '1'  # c\n# about b\n# more\nb.version = '2'\n\"a\" = '3'\n# loose\n\ny = 1\nx = 1\r\n[target.'cfg(unix)'.dependencies]\nb = 1\na = 1";
+        let expected = "b = 1\na = 2\n\n[dependencies]\n\"a\" = '3'\n# about b\n# more\nb.version = '2'\n  c = '1'  # c\n# loose\n\nx = 1\ny = 1\r\n[target.'cfg(unix)'.dependencies]\na = 1\nb = 1";
+        assert_eq!(sort(source, &options)?, expected);
+        assert_eq!(sort(expected, &options)?, expected);
+
+        let options = SortOptions {
+            tables: vec!["a".to_string()],
+            arrays: false,
+        };
+        let source = "[a]\nz = ['b', 'a']\ny = 1\n[dependencies]\nb = 1\na = 1\n";
+        let expected = "[a]\ny = 1\nz = ['b', 'a']\n[dependencies]\nb = 1\na = 1\n";
+        assert_eq!(sort(source, &options)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn arrays() -> Result<(), String> {
+        let options = SortOptions {
+            tables: vec!["features".to_string()],
+            arrays: true,
+        };
+        let source = "[features]\ndefault = [\"std\",  \"\\u0061lpha\" ,'alloc']\nmixed = ['b', 1]\nnested = [['b', 'a'], ['c']]\n";
+        let expected = "[features]\ndefault = ['alloc',  \"\\u0061lpha\" ,\"std\"]\nmixed = ['b', 1]\nnested = [['a', 'b'], ['c']]\n";
+        assert_eq!(sort(source, &options)?, expected);
+
+        let source = "[features]\nall = [ # all of them\n    # needed for x\n    \"x\",\n    \"c\", # why\n    \"b\"\n    # end\n]\n";
+        let expected = "[features]\nall = [ # all of them\n    \"b\",\n    \"c\", # why\n    # needed for x\n    \"x\"\n    # end\n]\n";
+        let sorted = sort(source, &options)?;
+        assert_eq!(sorted, expected);
+        assert_eq!(sort(&sorted, &options)?, sorted);
+        Ok(())
+    }
+}
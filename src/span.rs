@@ -0,0 +1,94 @@
+// stdlib imports
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+// my imports
+use super::lexer::{is_bare_key, quote_basic, TokenKind};
+
+/// A location within a TOML source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// The file the source was read from.
+    pub file: Option<Arc<str>>,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    /// Byte range within the source.
+    pub bytes: Range<usize>,
+}
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A scalar value as it was written in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Literal {
+    /// The lexeme, including any quotes, sign, prefix, or underscores.
+    pub raw: String,
+    /// One of the string kinds, [`TokenKind::Integer`], [`TokenKind::Float`],
+    /// [`TokenKind::Datetime`], or [`TokenKind::Boolean`].
+    pub kind: TokenKind,
+}
+impl Literal {
+    /// The radix an integer was written in.
+    pub fn radix(&self) -> Option<Radix> {
+        if self.kind != TokenKind::Integer {
+            return None;
+        }
+        let unsigned = self.raw.trim_start_matches(['+', '-']);
+        Some(match unsigned.get(..2) {
+            Some("0x") => Radix::Hexadecimal,
+            Some("0o") => Radix::Octal,
+            Some("0b") => Radix::Binary,
+            _ => Radix::Decimal,
+        })
+    }
+}
+
+/// The base of an integer literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Binary = 2,
+    Octal = 8,
+    Decimal = 10,
+    Hexadecimal = 16,
+}
+
+/// The spans of a key and of its value.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Located {
+    pub key: Option<Span>,
+    pub value: Option<Span>,
+    pub literal: Option<Literal>,
+}
+
+/// Maps `\0`-delimited key paths to where they were found. Array elements are keyed by index.
+pub(crate) type SpanMap = HashMap<String, Located>;
+
+/// Appends a key to a `\0`-delimited path.
+pub(crate) fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}\0{}", path, key)
+    }
+}
+
+/// Writes a key path as a TOML key (ex. `servers."127.0.0.1".port`), the form the path
+/// arguments of [`ParsedTOML::span_of`](crate::ParsedTOML::span_of) take.
+pub(crate) fn dotted_key<S: AsRef<str>>(segments: &[S]) -> String {
+    let segments: Vec<String> = segments
+        .iter()
+        .map(|seg| match seg.as_ref() {
+            seg if is_bare_key(seg) => seg.to_string(),
+            seg => quote_basic(seg),
+        })
+        .collect();
+    segments.join(".")
+}
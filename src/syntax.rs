@@ -0,0 +1,609 @@
+//! A lossless syntax tree, for tools that rewrite TOML (ex. formatters).
+//!
+//! A [`Document`] is a list of [`Line`]s, each holding a table header, a key/value pair, or
+//! nothing, along with the whitespace, comment, and line ending around it. Values that span
+//! lines (arrays, multi-line strings) belong to the line they start on. Every byte of the
+//! source is kept, so printing a document reproduces it exactly.
+//!
+//! The tree only checks the shape of the document. Use [`parse_str`](crate::parse_str) to
+//! check that it's valid TOML (ex. that no key is defined twice).
+//!
+//! ```
+//! use tomlp::syntax::{Content, Document};
+//!
+//! let src = "[server]  # the main one\nport = 8080\n";
+//! let mut doc = Document::parse(src)?;
+//! assert_eq!(doc.to_string(), src);
+//!
+//! if let Content::KeyValue(kv) = &mut doc.lines[1].content {
+//!     assert_eq!(kv.key.names()?, ["port"]);
+//!     kv.key.parts[0].after = String::new();
+//! }
+//! assert_eq!(doc.to_string(), "[server]  # the main one\nport= 8080\n");
+//! # Ok::<(), String>(())
+//! ```
+
+// stdlib imports
+use std::fmt;
+// my imports
+use super::lexer::{tokenize, Token, TokenKind};
+use super::tomlparse::TOMLParser;
+
+/// How deeply arrays and inline tables may nest, as for the parser's default
+/// [`ParseLimits`](crate::ParseLimits), which keeps hostile input from overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A whole document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub lines: Vec<Line>,
+}
+
+/// A line of the document, along with the rest of any value that continues past it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Line {
+    /// Whitespace before the content
+    pub indent: String,
+    pub content: Content,
+    /// Whitespace after the content
+    pub trailing: String,
+    /// Including the `#`
+    pub comment: Option<String>,
+    /// `\n`, `\r\n`, or empty on the last line
+    pub newline: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Content {
+    /// A blank or comment-only line
+    #[default]
+    Empty,
+    Header(Header),
+    KeyValue(KeyValue),
+}
+
+/// A table header (ex. `[server]`) or array of tables header (ex. `[[bin]]`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// `[[...]]` rather than `[...]`
+    pub array: bool,
+    pub key: Key,
+}
+
+/// A possibly dotted key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    pub parts: Vec<KeyPart>,
+}
+
+/// One segment of a key, and the whitespace between it and the dots around it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KeyPart {
+    pub before: String,
+    /// As written, quotes included
+    pub text: String,
+    pub after: String,
+}
+
+/// A key/value pair. Whitespace before the `=` is the `after` of the key's last part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyValue {
+    pub key: Key,
+    /// Whitespace after the `=`
+    pub after_eq: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A string, number, boolean, or datetime, as written
+    Scalar {
+        kind: TokenKind,
+        text: String,
+    },
+    Array(Array),
+    InlineTable(InlineTable),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Array {
+    pub items: Vec<Item<Value>>,
+    /// Whitespace, newlines, and comments before the `]`
+    pub trailing: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct InlineTable {
+    pub items: Vec<Item<KeyValue>>,
+    /// Whitespace (and in lenient documents, newlines and comments) before the `}`
+    pub trailing: Vec<Trivia>,
+}
+
+/// An element of an array or inline table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item<T> {
+    /// Whitespace, newlines, and comments before the element
+    pub leading: Vec<Trivia>,
+    pub value: T,
+    /// Whitespace, newlines, and comments between the element and its comma
+    pub before_comma: Vec<Trivia>,
+    pub comma: bool,
+}
+
+/// Text without meaning to the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trivia {
+    Whitespace(String),
+    Newline(String),
+    /// Including the `#`
+    Comment(String),
+}
+
+impl Document {
+    /// Parses the structure of a document. Fails if arrays and inline tables nest more than
+    /// 128 deep.
+    pub fn parse(source: &str) -> Result<Document, String> {
+        let tokens = tokenize(source).collect::<Result<Vec<Token>, String>>()?;
+        let mut builder = Builder {
+            source,
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let mut lines = Vec::new();
+        while builder.pos < builder.tokens.len() {
+            lines.push(builder.line()?);
+        }
+        Ok(Document { lines })
+    }
+}
+
+impl Line {
+    /// Whether the line has no content, though maybe a comment.
+    pub fn is_empty(&self) -> bool {
+        self.content == Content::Empty
+    }
+
+    /// Whether the line has nothing at all.
+    pub fn is_blank(&self) -> bool {
+        self.is_empty() && self.comment.is_none()
+    }
+}
+
+impl Key {
+    /// Builds a key from its segments, quoting them as needed.
+    pub fn new<S: AsRef<str>>(names: &[S]) -> Key {
+        let parts = names
+            .iter()
+            .map(|name| KeyPart {
+                text: crate::span::dotted_key(&[name]),
+                ..KeyPart::default()
+            })
+            .collect();
+        Key { parts }
+    }
+
+    /// The segments of the key, with quotes and escapes decoded.
+    pub fn names(&self) -> Result<Vec<String>, String> {
+        self.parts.iter().map(KeyPart::name).collect()
+    }
+}
+
+impl KeyPart {
+    /// The segment, with quotes and escapes decoded.
+    pub fn name(&self) -> Result<String, String> {
+        decode(&self.text)
+    }
+}
+
+impl Value {
+    /// The contents of a string, with quotes and escapes decoded, or `None` for any other
+    /// value.
+    pub fn string(&self) -> Result<Option<String>, String> {
+        let Value::Scalar { kind, text } = self else {
+            return Ok(None);
+        };
+        Ok(Some(match kind {
+            TokenKind::BasicStr | TokenKind::LitStr => decode(text)?,
+            TokenKind::MultiStr => TOMLParser::parse_multi_string(text, 0)?.into_owned(),
+            TokenKind::MultiLitStr => TOMLParser::parse_multi_litstr(text).into_owned(),
+            _ => return Ok(None),
+        }))
+    }
+}
+
+/// Decodes a bare key or single-line string.
+fn decode(text: &str) -> Result<String, String> {
+    Ok(match text.as_bytes().first() {
+        Some(b'"') => TOMLParser::parse_basic_string(text, 0)?.into_owned(),
+        Some(b'\'') => TOMLParser::parse_basic_litstr(text).into_owned(),
+        _ => text.to_string(),
+    })
+}
+
+////////////////////
+// Printing
+////////////////////
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.lines.iter().try_for_each(|line| write!(f, "{}", line))
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.indent)?;
+        match &self.content {
+            Content::Empty => (),
+            Content::Header(header) => write!(f, "{}", header)?,
+            Content::KeyValue(kv) => write!(f, "{}", kv)?,
+        }
+        f.write_str(&self.trailing)?;
+        if let Some(comment) = &self.comment {
+            f.write_str(comment)?;
+        }
+        f.write_str(&self.newline)
+    }
+}
+
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (open, close) = if self.array { ("[[", "]]") } else { ("[", "]") };
+        write!(f, "{}{}{}", open, self.key, close)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}{}{}", part.before, part.text, part.after)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KeyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}{}", self.key, self.after_eq, self.value)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Scalar { text, .. } => f.write_str(text),
+            Value::Array(array) => {
+                f.write_str("[")?;
+                write_items(f, &array.items, &array.trailing)?;
+                f.write_str("]")
+            }
+            Value::InlineTable(table) => {
+                f.write_str("{")?;
+                write_items(f, &table.items, &table.trailing)?;
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Trivia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trivia::Whitespace(text) | Trivia::Newline(text) | Trivia::Comment(text) => {
+                f.write_str(text)
+            }
+        }
+    }
+}
+
+fn write_items<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[Item<T>],
+    trailing: &[Trivia],
+) -> fmt::Result {
+    for item in items {
+        item.leading
+            .iter()
+            .try_for_each(|trivia| write!(f, "{}", trivia))?;
+        write!(f, "{}", item.value)?;
+        item.before_comma
+            .iter()
+            .try_for_each(|trivia| write!(f, "{}", trivia))?;
+        if item.comma {
+            f.write_str(",")?;
+        }
+    }
+    trailing
+        .iter()
+        .try_for_each(|trivia| write!(f, "{}", trivia))
+}
+
+////////////////////
+// Parsing
+////////////////////
+
+struct Builder<'s> {
+    source: &'s str,
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The arrays and inline tables the next token is within
+    depth: usize,
+}
+
+impl Builder<'_> {
+    fn peek(&self) -> Option<TokenKind> {
+        self.tokens.get(self.pos).map(|tok| tok.kind)
+    }
+
+    fn error(&self, expected: &str) -> String {
+        let line = match self.tokens.get(self.pos).or(self.tokens.last()) {
+            Some(tok) => tok.line,
+            None => 1,
+        };
+        format!("Line {}: Syntax Error: expected {}.", line, expected)
+    }
+
+    /// Consumes the next token if it's of `kind`, returning its text.
+    fn take(&mut self, kind: TokenKind) -> Option<String> {
+        let tok = self.tokens.get(self.pos).filter(|tok| tok.kind == kind)?;
+        self.pos += 1;
+        Some(tok.text(self.source).to_string())
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<String, String> {
+        self.take(kind).ok_or_else(|| self.error(expected))
+    }
+
+    fn ws(&mut self) -> String {
+        self.take(TokenKind::Whitespace).unwrap_or_default()
+    }
+
+    fn trivia(&mut self) -> Vec<Trivia> {
+        let mut trivia = Vec::new();
+        loop {
+            if let Some(text) = self.take(TokenKind::Whitespace) {
+                trivia.push(Trivia::Whitespace(text));
+            } else if let Some(text) = self.take(TokenKind::Newline) {
+                trivia.push(Trivia::Newline(text));
+            } else if let Some(text) = self.take(TokenKind::Comment) {
+                trivia.push(Trivia::Comment(text));
+            } else {
+                return trivia;
+            }
+        }
+    }
+
+    fn line(&mut self) -> Result<Line, String> {
+        let indent = self.ws();
+        let content = match self.peek() {
+            None | Some(TokenKind::Newline | TokenKind::Comment) => Content::Empty,
+            Some(TokenKind::BracketOpen | TokenKind::DoubleBracketOpen) => {
+                Content::Header(self.header()?)
+            }
+            Some(TokenKind::BareKey | TokenKind::QuotedKey) => Content::KeyValue(self.key_value()?),
+            Some(_) => return Err(self.error("a key or a table header")),
+        };
+        let trailing = self.ws();
+        let comment = self.take(TokenKind::Comment);
+        let newline = match self.take(TokenKind::Newline) {
+            Some(newline) => newline,
+            None if self.peek().is_none() => String::new(),
+            None => return Err(self.error("the end of the line")),
+        };
+        Ok(Line {
+            indent,
+            content,
+            trailing,
+            comment,
+            newline,
+        })
+    }
+
+    fn header(&mut self) -> Result<Header, String> {
+        let array = self.take(TokenKind::DoubleBracketOpen).is_some();
+        if !array {
+            self.expect(TokenKind::BracketOpen, "`[`")?;
+        }
+        let key = self.key()?;
+        if array {
+            self.expect(TokenKind::DoubleBracketClose, "`]]`")?;
+        } else {
+            self.expect(TokenKind::BracketClose, "`]`")?;
+        }
+        Ok(Header { array, key })
+    }
+
+    fn key(&mut self) -> Result<Key, String> {
+        let mut parts = Vec::new();
+        loop {
+            let before = self.ws();
+            let text = match self.peek() {
+                Some(TokenKind::BareKey) => self.take(TokenKind::BareKey),
+                Some(TokenKind::QuotedKey) => self.take(TokenKind::QuotedKey),
+                _ => None,
+            }
+            .ok_or_else(|| self.error("a key"))?;
+            let after = self.ws();
+            parts.push(KeyPart {
+                before,
+                text,
+                after,
+            });
+            if self.take(TokenKind::Dot).is_none() {
+                return Ok(Key { parts });
+            }
+        }
+    }
+
+    fn key_value(&mut self) -> Result<KeyValue, String> {
+        let key = self.key()?;
+        self.expect(TokenKind::Equals, "`=`")?;
+        let after_eq = self.ws();
+        let value = self.value()?;
+        Ok(KeyValue {
+            key,
+            after_eq,
+            value,
+        })
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some(TokenKind::BracketOpen) => {
+                self.descend()?;
+                let (items, trailing) = self.items(TokenKind::BracketClose, "`]`", Self::value)?;
+                self.depth -= 1;
+                Ok(Value::Array(Array { items, trailing }))
+            }
+            Some(TokenKind::BraceOpen) => {
+                self.descend()?;
+                let (items, trailing) =
+                    self.items(TokenKind::BraceClose, "`}`", Self::key_value)?;
+                self.depth -= 1;
+                Ok(Value::InlineTable(InlineTable { items, trailing }))
+            }
+            Some(
+                kind @ (TokenKind::BasicStr
+                | TokenKind::MultiStr
+                | TokenKind::LitStr
+                | TokenKind::MultiLitStr
+                | TokenKind::Integer
+                | TokenKind::Float
+                | TokenKind::Datetime
+                | TokenKind::Boolean),
+            ) => {
+                let text = self.take(kind).unwrap_or_default();
+                Ok(Value::Scalar { kind, text })
+            }
+            _ => Err(self.error("a value")),
+        }
+    }
+
+    /// Consumes the opening bracket or brace of a nested value.
+    fn descend(&mut self) -> Result<(), String> {
+        if self.depth == MAX_DEPTH {
+            let line = self.tokens[self.pos].line;
+            return Err(format!(
+                "Line {}: Nesting depth exceeds the limit of {}.",
+                line, MAX_DEPTH
+            ));
+        }
+        self.pos += 1;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Reads comma-separated elements up to and including `close`, returning them along with
+    /// the trivia before `close`.
+    fn items<T>(
+        &mut self,
+        close: TokenKind,
+        expected: &str,
+        element: fn(&mut Self) -> Result<T, String>,
+    ) -> Result<(Vec<Item<T>>, Vec<Trivia>), String> {
+        let mut items = Vec::new();
+        loop {
+            let leading = self.trivia();
+            if self.take(close).is_some() {
+                return Ok((items, leading));
+            }
+            let value = element(self)?;
+            let before_comma = self.trivia();
+            if self.take(TokenKind::Comma).is_some() {
+                items.push(Item {
+                    leading,
+                    value,
+                    before_comma,
+                    comma: true,
+                });
+                continue;
+            }
+            self.expect(close, &format!("`,` or {}", expected))?;
+            items.push(Item {
+                leading,
+                value,
+                before_comma: Vec::new(),
+                comma: false,
+            });
+            return Ok((items, before_comma));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Content, Document, Key, Value};
+
+    #[test]
+    fn lossless() -> Result<(), String> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tomlparse/test_resources");
+        let mut checked = 0;
+        for entry in std::fs::read_dir(dir).map_err(|err| err.to_string())? {
+            let path = entry.map_err(|err| err.to_string())?.path();
+            if path.extension().is_some_and(|ext| ext == "toml") {
+                let source = std::fs::read_to_string(&path).map_err(|err| err.to_string())?;
+                if crate::parse_str(&source).is_ok() {
+                    assert_eq!(Document::parse(&source)?.to_string(), source, "{:?}", path);
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 0);
+
+        let source = "a = [ # first\n  1 ,\n  [2,3],  # x\n]\r\nb={x=1, 'y z'.w = \"\"\"\nq\"\"\" }\n\t# end";
+        let doc = Document::parse(source)?;
+        assert_eq!(doc.to_string(), source);
+        assert_eq!(doc.lines.len(), 3);
+        match &doc.lines[0].content {
+            Content::KeyValue(kv) => match &kv.value {
+                Value::Array(array) => assert_eq!(array.items.len(), 2),
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        }
+        match &doc.lines[1].content {
+            Content::KeyValue(kv) => match &kv.value {
+                Value::InlineTable(table) => {
+                    assert_eq!(table.items[1].value.key.names()?, ["y z", "w"])
+                }
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(doc.lines[2].comment.as_deref(), Some("# end"));
+        Ok(())
+    }
+
+    #[test]
+    fn errors() -> Result<(), String> {
+        assert_eq!(
+            Document::parse("a = 1 2").unwrap_err(),
+            "Line 1: Syntax Error: expected the end of the line."
+        );
+        assert_eq!(
+            Document::parse("\n[a\n").unwrap_err(),
+            "Line 2: Syntax Error: expected `]`."
+        );
+        assert_eq!(
+            Document::parse("a = [1 2]").unwrap_err(),
+            "Line 1: Syntax Error: expected `,` or `]`."
+        );
+        assert_eq!(Key::new(&["a", "b c"]).to_string(), "a.\"b c\"");
+
+        // deep nesting is an error rather than a stack overflow
+        let deep = format!("a = {}1{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert_eq!(
+            Document::parse(&deep).unwrap_err(),
+            "Line 1: Nesting depth exceeds the limit of 128."
+        );
+        let deep = format!("a = {}\n", "{ b = ".repeat(200));
+        assert!(Document::parse(&deep).is_err());
+        let nested = format!("a = {}1{}", "[".repeat(128), "]".repeat(128));
+        assert_eq!(Document::parse(&nested)?.to_string(), nested);
+        Ok(())
+    }
+}
@@ -1,60 +1,158 @@
-#![allow(unused_mut, unused_imports, dead_code)]
-
 mod tests;
 
 // stdlib imports
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{prelude::*, BufReader};
+use std::io::prelude::*;
+use std::iter::Peekable;
+use std::ops::Range;
 use std::path::Path;
-// third-party imports
-use super::constants::{
-    COMMENT_TOKEN, INLINETAB_CLOSE_TOKEN, INLINETAB_OPEN_TOKEN, KEY_VAL_SEP, SEQUENCE_DELIM,
-    TABLE_CLOSE_TOKEN, TABLE_OPEN_TOKEN,
-};
-use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
-use unicode_segmentation::UnicodeSegmentation;
+use std::str::Chars;
+use std::sync::Arc;
 // my imports
-use super::constants::{LITERAL_STR_TOKEN, STR_TOKEN};
-use super::parsetools::{ParserLine, TOMLSeg, TPath};
-pub use super::tomltypes::{TOMLTable, TOMLType};
+use super::datetime::Datetime;
+use super::error::{ErrorKind, Note, ParseError, Warning, WarningKind};
+use super::lexer::{
+    Lexer, Mode, Token, TokenKind, COMMENT_TOKEN, TABLE_CLOSE_TOKEN, TABLE_OPEN_TOKEN,
+};
+use super::limits::ParseLimits;
+use super::parsetools::TPath;
+use super::span::{Literal, Span, SpanMap};
+use super::tomltypes::{TOMLTableRef, TOMLTypeRef};
 
 // Useful Types and Constants
 static EOF_ERROR: &str = "End of File during parsing operation.";
-type InnerParseResult<T> = Result<(T, ParserLine), String>; // return type alias to ensure
-pub struct KeyVal<'a>(pub TPath<'a>, pub TOMLType);
+pub struct KeyVal<'a>(pub Vec<Cow<'a, str>>, pub TOMLTypeRef<'a>);
+type KeySegments<'a> = (Vec<Cow<'a, str>>, Vec<(Range<usize>, usize)>);
+
+/// The header line of a table (`[path]`) or an array of tables (`[[path]]`).
+pub(crate) struct Header<'a> {
+    pub path: Vec<Cow<'a, str>>,
+    pub key_spans: Vec<(Range<usize>, usize)>,
+    pub is_aot: bool,
+    pub span: Range<usize>,
+    pub line: usize,
+}
 
 #[derive(Debug)]
-pub struct TOMLParser {
-    buffer: String,          // Contains a given line.
-    reader: BufReader<File>, // TOML File reader construct
-    line_num: usize,
+pub struct TOMLParser<'a> {
+    lexer: Lexer<'a>,                 // Token source for the document
     table_heads: Vec<TPath<'static>>, // Contains all top-level keys of form `[key]`
-    eof_flag: bool,
+    spans: Option<SpanMap>,           // Source locations of keys and values, if requested
+    path: Vec<String>,                // Key path of the value being parsed (for spans)
+    file: Option<Arc<str>>,           // Source file name (for spans)
+    limits: ParseLimits,              // Resource bounds enforced while parsing
+    depth: usize,                     // Nesting depth of the item being parsed
+    limit_exceeded: bool,             // Whether the last error was a limit violation
+    notes: Vec<Note>,                 // Sub-diagnostics of the last error
+    wide_integers: bool,              // Whether integers beyond `i64` are kept as `i128`
+    lenient: bool,                    // Whether common mistakes are accepted with a warning
+    warnings: Vec<Warning>,           // Mistakes accepted so far
 }
-impl TOMLParser {
+impl<'a> TOMLParser<'a> {
     // DEV Note: I think most functions will have to be made public for testing purposes.
     // Since I don't actually want a user to be able to use the functions, this will likely be a
     // private module that is then imported for a stand-alone public `parse` function.
     ////////////////////////
     // Creation/Modification
     ////////////////////////
-    pub fn init(file_path: &str) -> Result<Self, String> {
-        let fd = Self::validate_file(file_path)?;
-        Ok(Self {
-            buffer: String::with_capacity(100 * 4),
-            reader: BufReader::new(fd),
-            line_num: 0,
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            lexer: Lexer::new(source),
             table_heads: Vec::new(),
-            eof_flag: false,
-        })
+            spans: None,
+            path: Vec::new(),
+            file: None,
+            limits: ParseLimits::default(),
+            depth: 0,
+            limit_exceeded: false,
+            notes: Vec::new(),
+            wide_integers: false,
+            lenient: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Accept newlines and trailing commas in inline tables and booleans in any case (ex.
+    /// `True`), recording a warning for each instead of failing.
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Retrieves the warnings recorded so far.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Keep integers outside the range of `i64` (up to that of `i128`) instead of rejecting
+    /// them.
+    pub fn with_wide_integers(mut self, wide: bool) -> Self {
+        self.wide_integers = wide;
+        self
+    }
+
+    /// Enforce the given resource limits while parsing.
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Number lines starting from `line`, for parsing part of a larger document.
+    pub(crate) fn starting_at_line(mut self, line: usize) -> Self {
+        self.lexer = self.lexer.starting_at_line(line);
+        self
     }
 
-    fn validate_file(input: &str) -> Result<File, String> {
+    /// Record the location of every key and value while parsing.
+    /// `file` is reported as part of each span.
+    pub fn with_spans(mut self, file: Option<&str>) -> Self {
+        self.spans = Some(SpanMap::new());
+        self.file = file.map(Arc::from);
+        self
+    }
+
+    /// Retrieves the spans recorded during parsing, if any.
+    pub fn take_spans(&mut self) -> Option<SpanMap> {
+        self.spans.take()
+    }
+
+    /// Reads the TOML file at the given path into memory, refusing files larger than
+    /// `max_bytes` without reading past the limit.
+    /// Files without the `.toml` extension are refused if `require_extension` is set.
+    pub fn read_file_limited(
+        file_path: &str,
+        max_bytes: usize,
+        require_extension: bool,
+    ) -> Result<String, ParseError> {
+        let fd = match Self::validate_file(file_path, require_extension) {
+            Ok(fd) => fd,
+            Err(msg) => return Err(ParseError::new(ErrorKind::Io, msg)),
+        };
+        let mut source = String::new();
+        let cap = u64::try_from(max_bytes)
+            .unwrap_or(u64::MAX)
+            .saturating_add(1);
+        match fd.take(cap).read_to_string(&mut source) {
+            Ok(read) if read > max_bytes => Err(ParseError::new(
+                ErrorKind::LimitExceeded,
+                format!("Input exceeds the limit of {} bytes.", max_bytes),
+            )),
+            Ok(_) => Ok(source),
+            Err(err) => Err(ParseError::new(
+                ErrorKind::Io,
+                format!("File Read Error: {}", err.kind()),
+            )),
+        }
+    }
+
+    fn validate_file(input: &str, require_extension: bool) -> Result<File, String> {
         use std::ffi::OsStr;
 
         let toml_ext: &OsStr = OsStr::new("toml");
         let test = Path::new(input);
         match test.extension() {
+            _ if !require_extension => (),
             Some(ext) => {
                 if !test.exists() {
                     return Err("File does not exist.".to_string());
@@ -72,36 +170,179 @@ impl TOMLParser {
     }
 
     pub fn line_num(&self) -> usize {
-        self.line_num
-    }
-    /// returns false -> EoF
-    /// Won't check for EoF mid-value parsing.
-    /// I only plan to check in the outer loop.
-    pub fn next_line(&mut self) -> Result<bool, String> {
-        self.buffer.clear();
-        match self.reader.read_line(&mut self.buffer) {
-            Ok(0) => Ok(false),
-            Ok(_sz) => {
-                self.line_num += 1;
-                Ok(true)
+        self.lexer.line()
+    }
+
+    /// Categorizes an error message produced by this parser.
+    pub fn error(&self, message: String) -> ParseError {
+        let kind = if self.limit_exceeded {
+            ErrorKind::LimitExceeded
+        } else {
+            ErrorKind::Syntax
+        };
+        ParseError::new(kind, message).with_notes(self.notes.clone())
+    }
+
+    ////////////////////
+    // Limit Enforcement
+    ////////////////////
+
+    /// Marks `msg` as a limit violation.
+    fn limit_error(&mut self, msg: String) -> String {
+        trace!("{}", msg);
+        self.limit_exceeded = true;
+        msg
+    }
+
+    /// Increases the nesting depth by `levels`.
+    fn descend(&mut self, levels: usize) -> Result<(), String> {
+        self.depth += levels;
+        if self.depth > self.limits.max_depth {
+            let msg = format!(
+                "Line {}: Nesting depth exceeds the limit of {}.",
+                self.line_num(),
+                self.limits.max_depth
+            );
+            return Err(self.limit_error(msg));
+        }
+        Ok(())
+    }
+
+    fn check_array_len(&mut self, len: usize) -> Result<(), String> {
+        if len > self.limits.max_array_len {
+            let msg = format!(
+                "Line {}: Array exceeds the limit of {} elements.",
+                self.line_num(),
+                self.limits.max_array_len
+            );
+            return Err(self.limit_error(msg));
+        }
+        Ok(())
+    }
+
+    fn check_table_len(&mut self, len: usize, line_num: usize) -> Result<(), String> {
+        if len > self.limits.max_table_keys {
+            let msg = format!(
+                "Line {}: Table exceeds the limit of {} keys.",
+                line_num, self.limits.max_table_keys
+            );
+            return Err(self.limit_error(msg));
+        }
+        Ok(())
+    }
+
+    /// Checks that adding `key` to `table` keeps it within the key limit.
+    fn check_new_key(
+        &mut self,
+        table: &TOMLTableRef<'a>,
+        key: &str,
+        line_num: usize,
+    ) -> Result<(), String> {
+        let len = table.len() + usize::from(!table.contains_key(key));
+        self.check_table_len(len, line_num)
+    }
+
+    fn check_string_len(&mut self, tok: &Token) -> Result<(), String> {
+        if tok.span.len() > self.limits.max_string_len {
+            let msg = format!(
+                "Line {}: String exceeds the limit of {} bytes.",
+                tok.line, self.limits.max_string_len
+            );
+            return Err(self.limit_error(msg));
+        }
+        Ok(())
+    }
+
+    ////////////////////
+    // Token Management
+    ////////////////////
+
+    ////////////////////
+    // Span Recording
+    ////////////////////
+
+    fn make_span(&self, bytes: Range<usize>, line: usize) -> Span {
+        let before = self.lexer.slice(0..bytes.start);
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Span {
+            file: self.file.clone(),
+            line,
+            column: before[line_start..].chars().count() + 1,
+            bytes,
+        }
+    }
+
+    /// Records the span of the key at the current path. Keys defined more than once (ex. the
+    /// super-tables of dotted keys) keep their first location unless `overwrite` is set.
+    fn record_key(&mut self, bytes: Range<usize>, line: usize, overwrite: bool) {
+        if self.spans.is_none() {
+            return;
+        }
+        let span = self.make_span(bytes, line);
+        let path = self.path.join("\0");
+        if let Some(spans) = self.spans.as_mut() {
+            let located = spans.entry(path).or_default();
+            if overwrite || located.key.is_none() {
+                located.key = Some(span);
             }
-            Err(err) => Err(format!(
-                "Read error for line {1}: {0}",
-                err.kind(),
-                self.line_num + 1
-            )),
         }
     }
 
-    fn curr_parserline(&self) -> ParserLine {
-        ParserLine::new(self.buffer.clone(), self.line_num)
+    /// Records the span of the value at the current path.
+    fn record_value(&mut self, bytes: Range<usize>, line: usize, overwrite: bool) {
+        if self.spans.is_none() {
+            return;
+        }
+        let span = self.make_span(bytes, line);
+        let path = self.path.join("\0");
+        if let Some(spans) = self.spans.as_mut() {
+            let located = spans.entry(path).or_default();
+            if overwrite || located.value.is_none() {
+                located.value = Some(span);
+            }
+        }
+    }
+
+    /// Records the lexeme of the scalar value at the current path.
+    fn record_literal(&mut self, tok: &Token) {
+        if self.spans.is_none() {
+            return;
+        }
+        let literal = Literal {
+            raw: self.lexer.slice(tok.span.clone()).to_string(),
+            kind: tok.kind,
+        };
+        let path = self.path.join("\0");
+        if let Some(spans) = self.spans.as_mut() {
+            spans.entry(path).or_default().literal = Some(literal);
+        }
+    }
+
+    fn warn(&mut self, kind: WarningKind, line_num: usize, message: &str) {
+        trace!("warning on line {}: {}", line_num, message);
+        self.warnings
+            .push(Warning::new(kind, line_num, message.to_string()));
+    }
+
+    fn peek_token(&self, mode: Mode) -> Result<Option<Token>, String> {
+        self.lexer.peek_token(mode)
+    }
+
+    fn next_token(&mut self, mode: Mode) -> Result<Option<Token>, String> {
+        self.lexer.next_token(mode)
+    }
+
+    fn peek_kind(&self, mode: Mode) -> Result<Option<TokenKind>, String> {
+        Ok(self.peek_token(mode)?.map(|tok| tok.kind))
     }
 
-    pub fn next_parserline(&mut self) -> Result<ParserLine, String> {
-        if !self.next_line()? {
-            Err(String::from(EOF_ERROR))
+    /// Consumes the next token if it is of the given kind.
+    fn eat(&mut self, mode: Mode, kind: TokenKind) -> Result<bool, String> {
+        if self.peek_kind(mode)? == Some(kind) {
+            self.next_token(mode)?;
+            Ok(true)
         } else {
-            Ok(self.curr_parserline())
+            Ok(false)
         }
     }
 
@@ -109,97 +350,79 @@ impl TOMLParser {
     // Parsing Functions
     ////////////////////
 
-    pub fn parse_toml(&mut self) -> Result<TOMLTable, String> {
-        let mut table = TOMLTable::new();
+    pub fn parse_toml(&mut self) -> Result<TOMLTableRef<'a>, String> {
+        if self.lexer.source_len() > self.limits.max_input_bytes {
+            let msg = format!(
+                "Input exceeds the limit of {} bytes.",
+                self.limits.max_input_bytes
+            );
+            return Err(self.limit_error(msg));
+        }
+        trace!("parsing {} bytes", self.lexer.source_len());
+        let mut table = TOMLTableRef::new();
         let mut curr_table = &mut table;
-        loop {
-            let context = match self.fill_table(curr_table) {
-                Ok((_, pline)) => pline,
-                Err(msg) => {
-                    if self.eof_flag {
-                        break;
-                    } else {
-                        return Err(msg);
-                    }
-                }
-            };
-            // the received ParserLine is set to be positioned on `[`.
-            curr_table = self.parse_table_header(context, &mut table)?;
+        // `fill_table` stops on either the EoF or a table header's `[`.
+        while self.fill_table(curr_table)? {
+            curr_table = self.parse_table_header(&mut table)?;
         }
+        trace!("parsed {} lines", self.line_num());
         Ok(table)
     }
 
-    fn fill_table(&mut self, table_head: &mut TOMLTable) -> InnerParseResult<()> {
-        // start with a fresh ParserLine?
-        let mut context = {
-            if self.line_num == 0 {
-                ParserLine::default()
-            } else {
-                self.next_parserline()?
-            }
-        };
-        // Parse key_value pairs until the EoF or until the next table is reached.
+    /// Parse key_value pairs until the EoF or until the next table is reached.
+    /// Returns false on EoF.
+    fn fill_table(&mut self, table_head: &mut TOMLTableRef<'a>) -> Result<bool, String> {
         loop {
-            let (_, pline) = self.seek_nonws(context)?;
-
-            // If we return successfully from `seek_nonws`, we know there's a valid segment.
-            let mut seg = pline.peek().unwrap();
-            if let Some(&TABLE_OPEN_TOKEN) = seg.peek() {
-                return Ok(((), pline));
-            } else {
-                // find the key value pair and insert into the current table
-                let (kv, next_context) = self.parse_keyval(pline)?;
-                Self::insert(kv, table_head)?;
-                context = next_context;
+            if !self.seek_nonws()? {
+                return Ok(false);
+            }
+            if self.at_table_header() {
+                return Ok(true);
             }
+            // find the key value pair and insert into the current table
+            let line_num = self.line_num();
+            let kv = self.parse_keyval()?;
+            self.process_eol()?;
+            trace!("Line {}: key `{}`", line_num, kv.0.join("."));
+            self.insert_limited(kv, table_head, line_num)?;
         }
     }
 
+    /// Determines if the next item is a table header rather than a key-value pair.
+    pub(crate) fn at_table_header(&self) -> bool {
+        self.lexer.peek_byte() == Some(TABLE_OPEN_TOKEN)
+    }
+
     /// Processes table headers of form `[some_key_sequence.potentially.dotted]`
     /// Parses dotted key, performs validation, and descends the table beginning
     /// from the top-level, creating super-tables as necessary.
     /// Returns a mutable reference to the deepest table referred to by the (dotted) key
-    pub fn parse_table_header<'a>(
+    pub fn parse_table_header<'t>(
         &mut self,
-        mut context: ParserLine,
-        top_level_table: &'a mut TOMLTable,
-    ) -> Result<&'a mut TOMLTable, String> {
-        let line_num = context.line_num();
-        let mut seg = context.next_seg().unwrap();
-        // skip the first '['
-        seg.next();
+        top_level_table: &'t mut TOMLTableRef<'a>,
+    ) -> Result<&'t mut TOMLTableRef<'a>, String> {
+        let line_num = self.line_num();
+        let header = self.parse_header()?;
         // check to see if there is an array of tables first
-        if let Some(&TABLE_OPEN_TOKEN) = seg.peek() {
-            // Array of tables handling here
-            // TODO: Decide if I should change this overall function to assume *only* table header
-            // form (as in, we know it's not an AoT?)
-            seg.next();
-            let count = seg.count();
-            return self.parse_aot_header(ParserLine::freeze(context, count), top_level_table);
-        }
-
-        let count = seg.count();
-        let (path, pline) = self.parse_key(ParserLine::freeze(context, count))?;
-        context = pline;
-        seg = context.next_seg().unwrap(); // we know the parse key function exits on either
-                                           // '[' or '='
-        if seg.peek() != Some(&TABLE_CLOSE_TOKEN) {
-            return Err(format!(
-                "Line {}: Invalid Table Header; Must close with `{}`",
-                line_num, TABLE_CLOSE_TOKEN
-            ));
-        } else {
-            // iterate until end of segment
-            seg.next();
-            let count = seg.count();
-            Self::process_eol(ParserLine::freeze(context, count))?;
+        if header.is_aot {
+            return self.parse_aot_header(top_level_table, header);
         }
+        let Header {
+            path,
+            key_spans,
+            span: header_span,
+            ..
+        } = header;
 
         // Key Path Handling
-        if !self.is_unique_table_header(&path) {
+        let tpath = match TPath::new(path.iter().map(|seg| seg.to_string()).collect(), "\0") {
+            Some(tpath) => tpath,
+            None => return Err(format!("Line {}: No key found.", line_num)),
+        };
+        if !self.is_unique_table_header(&tpath) {
             return Err(format!(
                 "Line {}: Table header `{:?}` is already defined.",
-                line_num, &path
+                line_num, &tpath
             ));
         }
         /* Here, we know the path has not been used.
@@ -221,53 +444,62 @@ impl TOMLParser {
         // Iterate through the entire key path, polling the table structure beginning from the top-level.
         // Make the iterator peekable to determine when we are on the last path segment.
         let mut path_iter = path.into_iter().peekable();
-        let mut curr_table: &mut TOMLTable = top_level_table;
+        let mut curr_table: &mut TOMLTableRef<'a> = top_level_table;
         let mut pure_key_sequence = true; // do all key segments point to an HTable?
         let mut pathseg;
+        let mut key_span_iter = key_spans.into_iter();
+        self.path.clear();
         loop {
-            pathseg = path_iter.next().unwrap();
-            if let None = path_iter.peek() {
+            pathseg = path_iter.next().unwrap_or_default();
+            if path_iter.peek().is_none() {
                 break;
             }
-            //println!("{:?}", &path_iter);
-            // PERF: should I declare the key buffer outside of the loop and modify it instead of
-            // instantiating a new one every time?
-            let key = pathseg.to_string();
-            if curr_table.contains_key(&key) {
-                // Try to get the next table
-                match curr_table.get_mut(&key).unwrap() {
-                    TOMLType::HTable(ref mut htable) => {
-                        curr_table = htable;
-                    }
-                    TOMLType::DKTable(ref mut dktable) => {
-                        curr_table = dktable;
-                        pure_key_sequence = false;
+            if let Some((bytes, line)) = key_span_iter.next() {
+                self.path.push(pathseg.to_string());
+                self.record_key(bytes.clone(), line, false);
+                self.record_value(bytes, line, false);
+            }
+            // Try to get the next table, creating the super table if needed.
+            self.check_new_key(curr_table, &pathseg, line_num)?;
+            let next = curr_table
+                .entry(pathseg.clone())
+                .or_insert_with(|| TOMLTypeRef::HTable(TOMLTableRef::new()));
+            match next {
+                TOMLTypeRef::HTable(ref mut htable) => {
+                    curr_table = htable;
+                }
+                TOMLTypeRef::DKTable(ref mut dktable) => {
+                    curr_table = dktable;
+                    pure_key_sequence = false;
+                }
+                TOMLTypeRef::AoT(ref mut aotable) => {
+                    if self.spans.is_some() {
+                        self.path.push(aotable.len().saturating_sub(1).to_string());
                     }
-                    TOMLType::AoT(ref mut aotable) => {
-                        curr_table = aotable.last_mut().unwrap();  // get the latest table in the array
-                        pure_key_sequence = false;
+                    // get the latest table in the array
+                    match aotable.last_mut() {
+                        Some(table) => curr_table = table,
+                        None => {
+                            return Err(format!(
+                                "Line {}: Table header error: Array of Tables is empty.",
+                                line_num
+                            ))
+                        }
                     }
-                    _ => return Err(format!(
-                            "Line {}: Table header error: Dotted key component does not refer to a table.", line_num
-                        )),
+                    pure_key_sequence = false;
+                }
+                _ => {
+                    return Err(format!(
+                    "Line {}: Table header error: Dotted key component does not refer to a table.",
+                    line_num
+                ))
                 }
-            } else {
-                // create the super table
-                curr_table.insert(key.clone(), TOMLType::HTable(TOMLTable::new()));
-                curr_table = {
-                    if let TOMLType::HTable(ref mut new_table) = curr_table.get_mut(&key).unwrap() {
-                        new_table
-                    } else {
-                        panic!("TOMLParser::parse_table_header - mutable reference extraction from newly-created table should never fail.");
-                    }
-                };
             }
         }
         // Now: the path iterator is on the last portion of the key.
         // Ex:      some.dotted.key.sequence
         //                          --------  <-- we're on this part.
-        let key = pathseg.to_string();
-        assert_eq!(path_iter.next(), None);
+        let key = pathseg.clone();
 
         // NOTE: This is a deliberately-nested `if` instead of an `&&` boolean.
         // The two methods are not equivalent in this context.
@@ -282,73 +514,106 @@ impl TOMLParser {
                     "Line {}: Cannot redefine previously-defined table entry.",
                     line_num
                 ));
-            } else { // we've already checked if the entire sequence has been defined
-            }
-        } else {
-            /*  Source: https://toml.io/en/v1.0.0#table
-                The [table] form can, however, be used to define sub-tables within tables defined via dotted keys.
+            } // else: we've already checked if the entire sequence has been defined
+        }
+        /*  Source: https://toml.io/en/v1.0.0#table
+            The [table] form can, however, be used to define sub-tables within tables defined via dotted keys.
 
-                ```toml
-                [fruit]
-                apple.color = "red"
-                apple.taste.sweet = true
+            ```toml
+            [fruit]
+            apple.color = "red"
+            apple.taste.sweet = true
 
-                # [fruit.apple]  # INVALID
-                # [fruit.apple.taste]  # INVALID
+            # [fruit.apple]  # INVALID
+            # [fruit.apple.taste]  # INVALID
 
-                [fruit.apple.texture]  # you can add sub-tables
-                smooth = true
-                ```
-            */
-            curr_table.insert(key.clone(), TOMLType::HTable(TOMLTable::new()));
-        }
+            [fruit.apple.texture]  # you can add sub-tables
+            smooth = true
+            ```
+        */
         // Update the current_table reference variable
-        if let TOMLType::HTable(ref mut table) = curr_table.get_mut(&key).unwrap() {
+        self.check_new_key(curr_table, &key, line_num)?;
+        let entry = curr_table
+            .entry(key)
+            .or_insert_with(|| TOMLTypeRef::HTable(TOMLTableRef::new()));
+        if let TOMLTypeRef::HTable(ref mut table) = entry {
             curr_table = table;
         } else {
             return Err(format!("Line {}: Table Header Error; The last key segment must point to a table previously created as a supertable in a dotted header, or the segment must extend a table defined through either an array of tables or through a dotted key within a key-value pair.", line_num));
         }
 
+        if let Some((bytes, line)) = key_span_iter.next() {
+            self.path.push(pathseg.to_string());
+            self.record_key(bytes, line, true);
+            self.record_value(header_span, line_num, true);
+        }
+
         // Add the full path to the collection
-        self.table_heads.push(path);
+        self.table_heads.push(tpath);
 
         // Done!
         Ok(curr_table)
     }
 
-    fn parse_aot_header<'a>(
-        &mut self,
-        context: ParserLine,
-        top_level_table: &'a mut TOMLTable,
-    ) -> Result<&'a mut TOMLTable, String> {
-        // assume we are *within* the square bracket delimiters already.
-        let (path, mut context) = self.parse_key(context)?;
-        let line_num = context.line_num();
-        let err_msg = format!(
-            "Line {}: Invalid Array of Tables Declaration; Must close with `{}{}`",
-            line_num, TABLE_CLOSE_TOKEN, TABLE_CLOSE_TOKEN
-        );
+    /// Parses the header line of a table or an array of tables, without touching the table
+    /// structure.
+    pub(crate) fn parse_header(&mut self) -> Result<Header<'a>, String> {
+        let line_num = self.line_num();
+        let start = self.lexer.pos();
+        let is_aot = self.eat(Mode::Key, TokenKind::DoubleBracketOpen)?;
+        // skip the first '['
+        if !is_aot && !self.eat(Mode::Key, TokenKind::BracketOpen)? {
+            return Err(format!(
+                "Line {}: Table headers must begin with `{}`",
+                line_num, TABLE_OPEN_TOKEN as char
+            ));
+        }
 
-        // == HANDLING the rest of the line ==
-        let mut seg = context.next_seg().unwrap(); // we know the parse key function exits on either
-                                                   // '[' or '='
-                                                   // Handle closing delimiter
-        if let Some(&TABLE_CLOSE_TOKEN) = seg.peek() {
-            seg.next();
-            seg = match context.next_seg() {
-                Some(next) => next,
-                None => return Err(err_msg),
-            };
-            if seg.peek() != Some(&TABLE_CLOSE_TOKEN) {
-                return Err(err_msg);
-            } else {
-                seg.next();
-            }
-        } else {
-            return Err(err_msg);
+        let (path, key_spans) = self.parse_key_segments()?;
+        // Key-value pairs below the header nest within it.
+        self.depth = 0;
+        self.descend(path.len())?;
+        // The closing delimiter of an array of tables is two *adjacent* brackets.
+        if is_aot && !self.eat(Mode::Key, TokenKind::DoubleBracketClose)? {
+            return Err(format!(
+                "Line {}: Invalid Array of Tables Declaration; Must close with `{}{}`",
+                line_num, TABLE_CLOSE_TOKEN as char, TABLE_CLOSE_TOKEN as char
+            ));
+        } else if !is_aot && !self.eat(Mode::Key, TokenKind::BracketClose)? {
+            return Err(format!(
+                "Line {}: Invalid Table Header; Must close with `{}`",
+                line_num, TABLE_CLOSE_TOKEN as char
+            ));
         }
-        let count = seg.count();
-        Self::process_eol(ParserLine::freeze(context, count))?;
+        let span = start..self.lexer.pos();
+        self.process_eol()?;
+        trace!(
+            "Line {}: {} `{}`",
+            line_num,
+            if is_aot { "array of tables" } else { "table" },
+            path.join(".")
+        );
+        Ok(Header {
+            path,
+            key_spans,
+            is_aot,
+            span,
+            line: line_num,
+        })
+    }
+
+    fn parse_aot_header<'t>(
+        &mut self,
+        top_level_table: &'t mut TOMLTableRef<'a>,
+        header: Header<'a>,
+    ) -> Result<&'t mut TOMLTableRef<'a>, String> {
+        let Header {
+            path,
+            key_spans,
+            span: header_span,
+            line: line_num,
+            ..
+        } = header;
 
         // == Validating the AoT header ==
         /*
@@ -357,47 +622,45 @@ impl TOMLParser {
 
             In other words, in a dotted AoT key, each segment must point to an AoT if the key segment already has an associated value.
         */
-        let mut curr_table: &mut TOMLTable = top_level_table;
+        let nesting_err = format!("Line {}: Nested Arrays of Tables require each parent itself in the dotted key to point to an Array of Tables.", line_num);
+        let mut curr_table: &mut TOMLTableRef<'a> = top_level_table;
         let mut path_iter = path.into_iter().peekable();
-        let mut pathseg: &str;
-        loop {
-            pathseg = path_iter.next().unwrap();
-            if let None = path_iter.peek() {
-                break;
+        let mut key_span_iter = key_spans.into_iter();
+        self.path.clear();
+        while let Some(pathseg) = path_iter.next() {
+            let is_last = path_iter.peek().is_none();
+            let seg_span = key_span_iter.next();
+            let key = pathseg.clone();
+            let existed = curr_table.contains_key(&key);
+            self.check_new_key(curr_table, &key, line_num)?;
+            // A newly-created array already holds the table to fill.
+            let entry = curr_table
+                .entry(key)
+                .or_insert_with(|| TOMLTypeRef::AoT(vec![TOMLTableRef::new()]));
+            let aot = match entry {
+                TOMLTypeRef::AoT(aot) => aot,
+                _ => return Err(nesting_err),
+            };
+            if is_last && existed {
+                // Insert a new table at the end of the array
+                aot.push(TOMLTableRef::new());
+                self.check_array_len(aot.len())?;
             }
-            let key = pathseg.to_string();
-            if !curr_table.contains_key(&key) {
-                curr_table.insert(key.clone(), TOMLType::AoT(vec![TOMLTable::new()]));
-                if let TOMLType::AoT(ref mut aot) = curr_table.get_mut(&key).unwrap() {
-                    // get the latest table in the array
-                    curr_table = aot.last_mut().unwrap();
-                } else {
-                    panic!("TOMLParser::parse_aot_header should never fail to retrieve mutable reference to newly-created AoT table.");
+            if let Some((bytes, line)) = seg_span {
+                self.path.push(pathseg.to_string());
+                self.record_key(bytes.clone(), line, false);
+                self.record_value(bytes.clone(), line, false);
+                self.path.push(aot.len().saturating_sub(1).to_string());
+                if is_last {
+                    self.record_key(bytes, line, true);
+                    self.record_value(header_span.clone(), line_num, true);
                 }
-            } else if let TOMLType::AoT(ref mut aot) = curr_table.get_mut(&key).unwrap() {
-                // update reference to table
-                curr_table = aot.last_mut().unwrap();
-            } else {
-                return Err(format!("Line {}: Nested Arrays of Tables require each parent itself in the dotted key to point to an Array of Tables.", line_num));
             }
-        }
-        // on last segment of key
-        let key = pathseg.to_string();
-        if !curr_table.contains_key(&key) {
-            curr_table.insert(key.clone(), TOMLType::AoT(vec![TOMLTable::new()]));
-            if let TOMLType::AoT(ref mut aot) = curr_table.get_mut(&key).unwrap() {
-                // get the latest table in the array
-                curr_table = aot.last_mut().unwrap();
-            } else {
-                panic!("TOMLParser::parse_aot_header should never fail to retrieve mutable reference to newly-created AoT table.");
-            }
-        } else if let TOMLType::AoT(ref mut aot) = curr_table.get_mut(&key).unwrap() {
-            // Insert a new table at the end of the array
-            // Return the reference to said table
-            aot.push(TOMLTable::new());
-            curr_table = aot.last_mut().unwrap();
-        } else {
-            return Err(format!("Line {}: Nested Arrays of Tables require each parent itself in the dotted key to point to an Array of Tables.", line_num));
+            // get the latest table in the array
+            curr_table = match aot.last_mut() {
+                Some(table) => table,
+                None => return Err(nesting_err),
+            };
         }
 
         Ok(curr_table)
@@ -405,1299 +668,746 @@ impl TOMLParser {
 
     /// Determines if the given key path has already been used.
     fn is_unique_table_header(&self, path: &TPath<'_>) -> bool {
-        let mut answer = true;
-        for kp in &self.table_heads {
-            if path == kp {
-                answer = false;
-            }
-        }
-        answer
+        !self.table_heads.iter().any(|kp| kp == path)
     }
 
-    pub fn parse_keyval(&mut self, mut context: ParserLine) -> InnerParseResult<KeyVal> {
+    pub fn parse_keyval(&mut self) -> Result<KeyVal<'a>, String> {
         // Assume we begin on non-whitespace
-        let (key, mut context) = self.parse_key(context)?;
-
-        let mut seg = context.next_seg().unwrap();
-        // println!("EQUAL CHECK: {seg:?}");
-        if seg.next() != Some(KEY_VAL_SEP) {
+        let (key, key_spans) = self.parse_key_segments()?;
+        if !self.eat(Mode::Key, TokenKind::Equals)? {
             return Err(format!(
                 "Line {}: Equal sign must follow key in a key-value pair.",
-                context.line_num()
+                self.line_num()
             ));
-        } else {
-            seg = {
-                match context.next_seg() {
-                    Some(next) => next,
-                    None => return Err(EOF_ERROR.to_string()),
-                }
-            };
         }
-
-        let count = seg.count();
-        let (val, pline) = self.parse_value(ParserLine::freeze(context, count))?;
-
-        Ok((KeyVal(key, val), pline))
+        // Extend the current path by the key so the value (and anything within it) is recorded
+        // in the right place.
+        let depth = self.path.len();
+        let last = key_spans.len().saturating_sub(1);
+        for (i, (seg, (bytes, line))) in key.iter().zip(key_spans).enumerate() {
+            self.path.push(seg.to_string());
+            self.record_key(bytes.clone(), line, i == last);
+            if i != last {
+                // super-tables created by a dotted key are located at the key segment
+                self.record_value(bytes, line, false);
+            }
+        }
+        self.descend(key.len())?;
+        let val = self.parse_value();
+        self.depth -= key.len();
+        self.path.truncate(depth);
+        Ok(KeyVal(key, val?))
     }
+
     // == Key processing ==
     /// Given an identified key location, parse the key sequence. Should handle
     /// base keys, quoted keys, and dotted keys. Only concerned with producing the
     /// key sequence itself; querying the table structure and validating unique keys are done
-    pub fn parse_key(&mut self, mut context: ParserLine) -> InnerParseResult<TPath<'static>> {
-        // Assume we begin at some whitespace.
-        // Also assume the key itself is a dotted key since it is the most general form.
-
-        let mut key_segs: Vec<String> = Vec::new();
-        let mut temp_buf = String::new(); // for parsing bare keys
-        let mut seg = context.next_seg().unwrap();
-        seg.skip_ws();
-
-        /*  Try to parse a dotted key. The termination condition is when either:
-         *      - A `]` is found
-         *      - An `=` is found
-         */
-        let mut found_quoted_str = false;
+    /// elsewhere.
+    /// Leaves the parser on the token following the key (and any trailing whitespace).
+    pub fn parse_key(&mut self) -> Result<TPath<'static>, String> {
+        let segs = self.parse_key_segments()?.0;
+        match TPath::new(segs.into_iter().map(Cow::into_owned).collect(), "\0") {
+            Some(tpath) => Ok(tpath),
+            None => Err(format!("Line {}: No key found.", self.line_num())),
+        }
+    }
+
+    /// Parses a key, also returning the byte range and line of each segment when spans are
+    /// being recorded.
+    fn parse_key_segments(&mut self) -> Result<KeySegments<'a>, String> {
+        // Assume the key itself is a dotted key since it is the most general form.
+        let mut key_segs: Vec<Cow<'a, str>> = Vec::new();
+        let mut seg_spans: Vec<(Range<usize>, usize)> = Vec::new();
         loop {
-            match seg.peek() {
-                None => {
-                    // This means an equal sign should be the following character
-                    seg = {
-                        match context.next_seg() {
-                            None => {
-                                return Err(format!("Line {}: {}", context.line_num(), EOF_ERROR))
-                            }
-                            Some(new) => new,
-                        }
-                    }
+            self.lexer.skip_ws();
+            let line_num = self.line_num();
+            let tok = match self.next_token(Mode::Key)? {
+                Some(tok) => tok,
+                None => return Err(format!("Line {}: {}", line_num, EOF_ERROR)),
+            };
+            self.check_string_len(&tok)?;
+            let text = self.lexer.slice(tok.span.clone());
+            let seg = match tok.kind {
+                TokenKind::BareKey => Cow::Borrowed(text),
+                TokenKind::QuotedKey if text.starts_with('"') => {
+                    Self::parse_basic_string(text, line_num)?
                 }
-                Some(&c) => {
-                    match c {
-                        TABLE_CLOSE_TOKEN | KEY_VAL_SEP => {
-                            if !temp_buf.is_empty() {
-                                key_segs.push(temp_buf.clone());
-                                temp_buf.clear();
-                            }
-                            break;
-                        }
-
-                        "." => {
-                            seg.next();
-                            if temp_buf.is_empty() && !found_quoted_str {
-                                return Err(format!(
-                                    "Line {}: Bare keys cannot be empty.",
-                                    context.line_num()
-                                ));
-                            } else if found_quoted_str {
-                                found_quoted_str = false;
-                            } else {
-                                // push the bare key to storage
-                                key_segs.push(temp_buf.clone());
-                                temp_buf.clear();
-                            }
-                        }
-
-                        " " | "\t" => seg.skip_ws(),
-
-                        STR_TOKEN => {
-                            let count = seg.count();
-                            let (result, pline) =
-                                self.parse_basic_string(ParserLine::freeze(context, count))?;
-                            key_segs.push(result.str().unwrap().to_string());
-                            found_quoted_str = true;
-
-                            context = pline;
-                            seg = {
-                                match context.next_seg() {
-                                    None => return Err(format!("Line {}: Invalid format. Keys must be followed by either an equal sign or a closing square bracket.", context.line_num())),
-                                    Some(new) => new
-                                }
-                            }
-                        }
-
-                        LITERAL_STR_TOKEN => {
-                            let count = seg.count();
-                            let (result, pline) =
-                                self.parse_literal_string(ParserLine::freeze(context, count))?;
-                            key_segs.push(result.str().unwrap().to_string());
-                            found_quoted_str = true;
-
-                            context = pline;
-                            seg = {
-                                match context.next_seg() {
-                                    None => return Err(format!("Line {}: Invalid format. Keys must be followed by either an equal sign or a closing square bracket.", context.line_num())),
-                                    Some(new) => new
-                                }
-                            }
-                        }
-
-                        _ => {
-                            if !is_barekey_char(c) {
-                                let c = c.to_string();
-                                return Err(format!(
-                                    "Line {}: Invalid bare key character: {}.",
-                                    context.line_num(),
-                                    c
-                                ));
-                            } else {
-                                temp_buf.push_str(c);
-                                seg.next();
-                            }
-                        }
-                    }
+                TokenKind::QuotedKey => Self::parse_basic_litstr(text),
+                TokenKind::MultiStr | TokenKind::MultiLitStr => {
+                    return Err(format!(
+                        "Line {}: Multi-line strings cannot be used as keys.",
+                        line_num
+                    ))
+                }
+                TokenKind::Dot => {
+                    return Err(format!("Line {}: Bare keys cannot be empty.", line_num))
                 }
+                _ => {
+                    return Err(format!(
+                        "Line {}: Invalid bare key character: {}.",
+                        line_num, text
+                    ))
+                }
+            };
+            key_segs.push(seg);
+            if self.spans.is_some() {
+                seg_spans.push((tok.span, tok.line));
             }
-        }
 
-        // Done parsing keys
-        if key_segs.is_empty() {
-            return Err(format!("Line {}: No key found.", context.line_num()));
+            self.lexer.skip_ws();
+            if !self.eat(Mode::Key, TokenKind::Dot)? {
+                break;
+            }
         }
-
-        let tpath = TPath::new(key_segs, "\0").unwrap();
-        let count = seg.count();
-        Ok((tpath, ParserLine::freeze(context, count)))
+        // key_segs is never empty here; the loop only exits after a segment is pushed.
+        Ok((key_segs, seg_spans))
     }
 
     /// Parse the input into a valid TOML type.
-    pub fn parse_value(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
+    pub fn parse_value(&mut self) -> Result<TOMLTypeRef<'a>, String> {
         // Assume we begin on whitespace
-        let mut seg = match context.next_seg() {
-            Some(next) => next,
-            None => return Err(EOF_ERROR.to_string()),
-        };
-        seg.skip_ws();
+        self.lexer.skip_ws();
+        let line_num = self.line_num();
+        let start = self.lexer.pos();
+        let val = self.parse_value_kind(line_num)?;
+        self.record_value(start..self.lexer.pos(), line_num, true);
+        Ok(val)
+    }
 
-        let ch: String;
-        if let Some(&c) = seg.peek() {
-            ch = c.to_string();
-        } else {
-            panic!("TOMLSeg should never be empty in TOMLParser::parse_value.");
-        }
-        let count = seg.count();
-        context = ParserLine::freeze(context, count);
-        match ch.as_str() {
-            TABLE_OPEN_TOKEN => self.parse_array(context),
-            LITERAL_STR_TOKEN => self.parse_literal_string(context),
-            STR_TOKEN => self.parse_string(context),
-            "t" | "f" => Self::parse_bool(context),
-            INLINETAB_OPEN_TOKEN => self.parse_inline_table(context),
-            _ => Self::parse_numeric(context),
+    /// Dispatches on the next token to parse a value beginning on line `line_num`.
+    fn parse_value_kind(&mut self, line_num: usize) -> Result<TOMLTypeRef<'a>, String> {
+        let tok = match self.peek_token(Mode::Value)? {
+            Some(tok) => tok,
+            None => return Err(format!("Line {}: {}", line_num, EOF_ERROR)),
+        };
+        match tok.kind {
+            TokenKind::BracketOpen => self.parse_array(),
+            TokenKind::BraceOpen => self.parse_inline_table(),
+            TokenKind::BasicStr
+            | TokenKind::MultiStr
+            | TokenKind::LitStr
+            | TokenKind::MultiLitStr => {
+                self.next_token(Mode::Value)?;
+                self.check_string_len(&tok)?;
+                self.record_literal(&tok);
+                self.decode_string(tok)
+            }
+            TokenKind::Boolean => {
+                self.next_token(Mode::Value)?;
+                self.record_literal(&tok);
+                let text = self.lexer.slice(tok.span);
+                match Self::parse_bool(text, line_num) {
+                    Err(_) if self.lenient => {
+                        let message = format!("Boolean `{}` should be lowercase.", text);
+                        self.warn(WarningKind::BooleanCase, line_num, &message);
+                        Ok(TOMLTypeRef::Bool(text.eq_ignore_ascii_case("true")))
+                    }
+                    result => result,
+                }
+            }
+            // Integer- and datetime-shaped values can't be anything else, so report why they
+            // aren't valid.
+            TokenKind::Integer => {
+                self.next_token(Mode::Value)?;
+                self.record_literal(&tok);
+                let text = self.lexer.slice(tok.span);
+                Self::parse_integer_of_width(text, line_num, self.wide_integers)
+            }
+            TokenKind::Datetime => {
+                self.next_token(Mode::Value)?;
+                self.record_literal(&tok);
+                Self::parse_date(self.lexer.slice(tok.span), line_num)
+            }
+            TokenKind::Float => {
+                self.next_token(Mode::Value)?;
+                self.record_literal(&tok);
+                let text = self.lexer.slice(tok.span);
+                match Self::parse_numeric(text, line_num) {
+                    Ok(val) => Ok(val),
+                    Err(notes) => {
+                        self.notes = notes;
+                        Err(format!("Line {}: Could not parse numeric type. Tried integer, float, and date parsing.", line_num))
+                    }
+                }
+            }
+            _ => Err(format!("Line {}: Expected a value.", line_num)),
         }
     }
 
     // == Container Parsing ==
-    pub fn parse_array(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
+    pub fn parse_array(&mut self) -> Result<TOMLTypeRef<'a>, String> {
         // Assume: Beginning on `[` character.
-        let mut seg = context.next_seg().unwrap();
-        // throw away the `[`
-        seg.next();
-        // get to the first inner character
-        let count = seg.count();
-        let (_, mut context) = self.seek_nonws(ParserLine::freeze(context, count))?;
-        seg = context.next_seg().unwrap();
-
-        // begin parsing
-        let mut array: Vec<TOMLType> = Vec::new();
+        let line_num = self.line_num();
+        if !self.eat(Mode::Value, TokenKind::BracketOpen)? {
+            return Err(format!("Line {}: Arrays must begin with `[`.", line_num));
+        }
+        self.descend(1)?;
+
+        let mut array: Vec<TOMLTypeRef<'a>> = Vec::new();
         loop {
-            if let Some(&ch) = seg.peek() {
-                match ch {
-                    TABLE_CLOSE_TOKEN => {
-                        seg.next();
-                        break;
+            // process comments, whitespace, and newlines
+            if !self.seek_nonws()? {
+                return Err(format!("Line {}: {}", self.line_num(), EOF_ERROR));
+            }
+            match self.peek_kind(Mode::Value)? {
+                Some(TokenKind::BracketClose) => {
+                    self.next_token(Mode::Value)?;
+                    break;
+                }
+                Some(TokenKind::Comma) => return Err(format!("Line {}: Array Parsing Error: The Value Separator (comma) must immediately follow a value.", self.line_num())),
+                _ => {
+                    // array elements are located by their index
+                    let recording = self.spans.is_some();
+                    if recording {
+                        self.path.push(array.len().to_string());
                     }
-
-                    SEQUENCE_DELIM => return Err(format!("Line {}: Array Parsing Error: The Value Separator (comma) must immediately follow a value.", context.line_num())),
-
-                    _ => {
-                        let count = seg.count();
-                        let (val, pline) = self.parse_value(ParserLine::freeze(context, count))?;
-                        array.push(val);
-
-                        // check for comma immediately following value
-                        context = pline;
-                        seg = context.next_seg().expect("There should still be data after a value was parsed within an array.");
-                        if let Some(&SEQUENCE_DELIM) = seg.peek() {
-                            seg.next();
+                    let val = self.parse_value();
+                    if recording {
+                        self.path.pop();
+                    }
+                    array.push(val?);
+                    self.check_array_len(array.len())?;
+                    if !self.seek_nonws()? {
+                        return Err(format!("Line {}: {}", self.line_num(), EOF_ERROR));
+                    }
+                    match self.peek_kind(Mode::Value)? {
+                        Some(TokenKind::Comma) => {
+                            self.next_token(Mode::Value)?;
                         }
-
-                        // process comment, whitespace, and newline
-                        let count = seg.count();
-                        let (_, pline) = self.seek_nonws(ParserLine::freeze(context, count))?;
-                        context = pline;
-                        seg = context.next_seg().unwrap();
+                        Some(TokenKind::BracketClose) => {}
+                        _ => return Err(format!("Line {}: Array Parsing Error: Values must be separated by a comma.", self.line_num())),
                     }
                 }
             }
         }
 
-        let count = seg.count();
-        Ok((TOMLType::Array(array), ParserLine::freeze(context, count)))
+        self.depth -= 1;
+        Ok(TOMLTypeRef::Array(array))
     }
 
-    pub fn parse_inline_table(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        let mut table = TOMLTable::new();
+    pub fn parse_inline_table(&mut self) -> Result<TOMLTypeRef<'a>, String> {
+        let mut table = TOMLTableRef::new();
         // Assume we begin on `{`
-        let mut seg = context.next_seg().unwrap();
-        // Throw away delimiter
-        seg.next();
+        let line_num = self.line_num();
+        if !self.eat(Mode::Key, TokenKind::BraceOpen)? {
+            return Err(format!(
+                "Line {}: Inline tables must begin with `{{`.",
+                line_num
+            ));
+        }
+        self.descend(1)?;
         let mut trailing_comma = false;
         loop {
-            seg.skip_ws();
-            if let Some(&ch) = seg.peek() {
-                match ch {
-                    SEQUENCE_DELIM => return Err(format!("Line {}: Inline Table Parsing Error: The value separator (comma) must immediately follow a value.", context.line_num())),
+            self.skip_inline_ws()?;
+            match self.peek_kind(Mode::Key)? {
+                None => return Err(format!("Line {}: {}", self.line_num(), EOF_ERROR)),
 
-                    "\n" => return Err(format!("Line {}: Newlines are prohibited within an inline table (outside of a value that allows them)", context.line_num())),
+                Some(TokenKind::Comma) => return Err(format!("Line {}: Inline Table Parsing Error: The value separator (comma) must immediately follow a value.", self.line_num())),
 
-                    INLINETAB_CLOSE_TOKEN => {
-                        seg.next();
-                        break;
-                    }
+                Some(TokenKind::Newline | TokenKind::Comment) => return Err(format!("Line {}: Newlines are prohibited within an inline table (outside of a value that allows them)", self.line_num())),
 
-                    _ => {
-                        let count = seg.count();
-                        let (key_val, pline) = self.parse_keyval(ParserLine::freeze(context, count))?;
-                        trailing_comma = false;
-                        context = pline;
-                        seg = context.next_seg().expect("Inline Table should never terminate prematurely.");
-                        if let Err(msg) = Self::insert(key_val, &mut table) {
-                            return Err(format!("Line {}: {}", context.line_num(), msg))
-                        }
+                Some(TokenKind::BraceClose) => {
+                    self.next_token(Mode::Key)?;
+                    break;
+                }
 
-                        // check for comma
-                        if let Some(&SEQUENCE_DELIM) = seg.peek() {
-                            seg.next();
-                            trailing_comma = true;
-                        }
+                _ if !table.is_empty() && !trailing_comma => return Err(format!("Line {}: Inline Table Parsing Error: Key-value pairs must be separated by a comma.", self.line_num())),
 
-                    }
+                _ => {
+                    let key_val = self.parse_keyval()?;
+                    trailing_comma = false;
+                    self.insert_limited(key_val, &mut table, self.line_num())?;
 
+                    // check for comma
+                    self.skip_inline_ws()?;
+                    if self.eat(Mode::Key, TokenKind::Comma)? {
+                        trailing_comma = true;
+                    }
                 }
-            } else {
-                // get next segment
-                seg = match context.next_seg() {
-                    Some(next) => next,
-                    None => return Err(EOF_ERROR.to_string()),
-                };
             }
         }
-        if trailing_comma {
+        self.depth -= 1;
+        if trailing_comma && self.lenient {
+            let message = "Trailing comma in an inline table.";
+            self.warn(
+                WarningKind::InlineTableTrailingComma,
+                self.line_num(),
+                message,
+            );
+        } else if trailing_comma {
             return Err(format!(
                 "Line {}: Trailing comma prohibited in inline tables.",
-                context.line_num()
+                self.line_num()
             ));
-        } else {
-            let count = seg.count();
-            Ok((
-                TOMLType::InlineTable(table),
-                ParserLine::freeze(context, count),
-            ))
         }
+        Ok(TOMLTypeRef::InlineTable(table))
     }
 
-    pub fn insert(kv: KeyVal, table_head: &mut TOMLTable) -> Result<(), String> {
-        insert_keyval(kv, table_head)
+    /// Skips whitespace within an inline table. Lenient parsers also skip newlines and
+    /// comments, with a warning.
+    fn skip_inline_ws(&mut self) -> Result<(), String> {
+        self.lexer.skip_ws();
+        if self.lenient
+            && matches!(
+                self.peek_kind(Mode::Key)?,
+                Some(TokenKind::Newline | TokenKind::Comment)
+            )
+        {
+            let line_num = self.line_num();
+            self.seek_nonws()?;
+            let message = "Newline within an inline table.";
+            self.warn(WarningKind::InlineTableNewline, line_num, message);
+        }
+        Ok(())
     }
 
-    // == String parsing ==
+    pub fn insert(kv: KeyVal<'a>, table_head: &mut TOMLTableRef<'a>) -> Result<(), String> {
+        insert_keyval(kv, table_head).map(|_| ())
+    }
 
-    /// Parses TOML-style basic and multi-line quoted strings
-    pub fn parse_string(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        // determine if the multi-string delimiter is present.
-        // UNWRAP justification: a " character was found before calling this function, so we know the segment exists.
-        let mut seg = context.peek().unwrap();
-        for _ in 0..3 {
-            if let Some(&STR_TOKEN) = seg.peek() {
-                seg.next();
-                continue;
-            }
-            return self.parse_basic_string(context);
+    /// Inserts a key-value pair, enforcing the key limit on every table it passes through.
+    fn insert_limited(
+        &mut self,
+        kv: KeyVal<'a>,
+        table_head: &mut TOMLTableRef<'a>,
+        line_num: usize,
+    ) -> Result<(), String> {
+        match insert_keyval(kv, table_head) {
+            Ok(len) => self.check_table_len(len, line_num),
+            Err(msg) => Err(format!("Line {}: {}", line_num, msg)),
         }
-        self.parse_multi_string(context)
     }
 
-    fn parse_multi_string(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        let mut quote_count = 0;
-        let sz = self.buffer.capacity();
-        let mut grapheme_pool = String::with_capacity(sz);
+    // == String parsing ==
 
-        let mut seg = context.next_seg().unwrap();
-        // throw away first three characters (the delimiter)
-        for _ in 0..3 {
-            seg.next();
+    /// Converts a string token into its TOML value.
+    fn decode_string(&self, tok: Token) -> Result<TOMLTypeRef<'a>, String> {
+        let text = self.lexer.slice(tok.span);
+        match tok.kind {
+            TokenKind::BasicStr => Ok(TOMLTypeRef::BasicStr(Self::parse_basic_string(
+                text, tok.line,
+            )?)),
+            TokenKind::MultiStr => Ok(TOMLTypeRef::MultiStr(Self::parse_multi_string(
+                text, tok.line,
+            )?)),
+            TokenKind::LitStr => Ok(TOMLTypeRef::LitStr(Self::parse_basic_litstr(text))),
+            TokenKind::MultiLitStr => Ok(TOMLTypeRef::MultiLitStr(Self::parse_multi_litstr(text))),
+            _ => Err(format!("Line {}: Expected a string.", tok.line)),
         }
-        // trim immediate newline if present
-        if let Some(&"\n") = seg.peek() {
-            seg.next();
-        }
-        // entered multi-string context
-        let mut graphemes_added = 0;
-        // terminate when we've found the three consecutive quotation marks
-        while quote_count < 3 {
-            match seg.next() {
-                Some(ch) => {
-                    graphemes_added += 1;
-                    match ch {
-                        STR_TOKEN => {
-                            quote_count += 1;
-                            grapheme_pool.push_str(ch);
-                        }
-
-                        "\\" => {
-                            // escape sequence
-                            let count = seg.count();
-                            let (ch, pline, inc_delim) = self
-                                .parse_multi_escape_sequence(ParserLine::freeze(context, count))?;
-
-                            if inc_delim {
-                                quote_count += 1;
-                            } else {
-                                quote_count = 0;
-                            }
-
-                            grapheme_pool.push(ch);
-
-                            context = pline;
-                            if context.is_exhausted() {
-                                context = self.next_parserline()?;
-                            }
-                            seg = context.next_seg().unwrap();
-                        }
+    }
 
-                        _ => {
-                            quote_count = 0;
-                            grapheme_pool.push_str(ch);
-                        }
-                    }
-                }
-                None => {
-                    if context.is_exhausted() {
-                        context = self.next_parserline()?;
-                    }
-                    seg = context.next_seg().unwrap();
+    /// Decodes a multi-line basic string lexeme (delimiters included).
+    /// Borrows from the lexeme if there are no escapes to process.
+    pub(crate) fn parse_multi_string(
+        lexeme: &str,
+        line_num: usize,
+    ) -> Result<Cow<'_, str>, String> {
+        let body = &lexeme[3..lexeme.len() - 3];
+        // trim immediate newline if present
+        let body = trim_leading_newline(body);
+        if !body.contains('\\') {
+            return Ok(Cow::Borrowed(body));
+        }
+        let mut outstring = String::with_capacity(body.len());
+        let mut iter = body.chars().peekable();
+        while let Some(ch) = iter.next() {
+            if ch == '\\' {
+                if let Some(escaped) = Self::parse_multi_escape_sequence(&mut iter, line_num)? {
+                    outstring.push(escaped);
                 }
+            } else {
+                outstring.push(ch);
             }
-        } // Possibly found closing delimiter
-
-        // Check for extra quotation mark (this is a really annoying thing to allow)
-        // REFERENCE: https://toml.io/en/v1.0.0#string
-        if let Some(&"\"") = seg.peek() {
-            grapheme_pool.push_str(seg.next().unwrap());
-            graphemes_added += 1;
-        }
-
-        let outstring = grapheme_pool
-            .as_str()
-            .graphemes(true)
-            .take(graphemes_added - 3)
-            .collect::<String>();
-        let count = seg.count();
-        let context = ParserLine::freeze(context, count);
-        Ok((TOMLType::MultiStr(outstring), context))
-    }
-
-    fn parse_basic_string(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        // Throw away first character (delimiter)
-        let mut seg = context.next_seg().unwrap();
-        seg.next();
-        let mut grapheme_pool = String::with_capacity(self.buffer.capacity());
-        loop {
-            match seg.next() {
-                None => match context.next_seg() {
+        }
+        Ok(Cow::Owned(outstring))
+    }
+
+    /// Decodes a basic string lexeme (delimiters included).
+    /// Borrows from the lexeme if there are no escapes to process.
+    pub(crate) fn parse_basic_string(
+        lexeme: &str,
+        line_num: usize,
+    ) -> Result<Cow<'_, str>, String> {
+        let body = &lexeme[1..lexeme.len() - 1];
+        if !body.contains('\\') {
+            return Ok(Cow::Borrowed(body));
+        }
+        let mut outstring = String::with_capacity(body.len());
+        let mut iter = body.chars().peekable();
+        while let Some(ch) = iter.next() {
+            if ch == '\\' {
+                match Self::parse_basic_escape_sequence(&mut iter) {
+                    Some(escaped) => outstring.push(escaped),
                     None => {
-                        return Err(format!(
-                            "Err: Line {}: Non-terminating basic string.",
-                            self.line_num
-                        ))
-                    }
-                    Some(next) => {
-                        seg = next;
-                        continue;
+                        return Err(format!("Line {}: Invalid String Escape Sequence", line_num))
                     }
-                },
-                Some(ch) => match ch {
-                    "\"" => break,
-                    "\\" => {
-                        let count = seg.count();
-                        match Self::parse_basic_escape_sequence(ParserLine::freeze(context, count))
-                        {
-                            None => {
-                                return Err(format!(
-                                    "Err: Line {}: Invalid String Escape Sequence",
-                                    self.line_num
-                                ))
-                            }
-                            Some((ch, pline)) => {
-                                context = pline;
-                                seg = {
-                                    match context.next_seg() {
-                                        None => {
-                                            return Err(format!(
-                                                "Err: Line {}: Non-terminating basic string.",
-                                                self.line_num
-                                            ))
-                                        }
-                                        Some(next) => next,
-                                    }
-                                };
-                                grapheme_pool.push(ch);
-                            }
-                        }
-                    }
-                    _ => {
-                        if !is_valid_multstr_grapheme(ch) {
-                            return Err(format!(
-                                "Err: Line {}: Invalid Unicode Character U+{:X}",
-                                self.line_num,
-                                ch.chars().next().unwrap() as u32,
-                            ));
-                        } else {
-                            grapheme_pool.push_str(ch);
-                        }
-                    }
-                },
+                }
+            } else {
+                outstring.push(ch);
             }
         }
-        let count = seg.count();
-        Ok((
-            TOMLType::BasicStr(grapheme_pool),
-            ParserLine::freeze(context, count),
-        ))
+        Ok(Cow::Owned(outstring))
     }
 
     /// Produces a UTF8 escape literal from a given iterator.
-    /// Assumes Unix OS so the newline can fit into a char.
-    /// Returns tuple of (char, ParserLine, bool) where each
-    /// is (escaped_char, context, increment_delimiter)
-    /// `increment_delimiter` is a way to ensure `"` are
-    /// counted properly if if's the first character after a line escape.
-    /// Basically, it distinguishes directly-escaped quotation marks from
-    /// a quotation mark that follows a lone backslashed line break.
+    /// Returns `None` for a line-ending backslash, which escapes the newline and all whitespace
+    /// following it rather than producing a character.
     pub fn parse_multi_escape_sequence(
-        &mut self,
-        mut context: ParserLine,
-    ) -> Result<(char, ParserLine, bool), String> {
-        // PLATFORM SUPPORT: After the String is made, can we transform it such that \n -> \r\n?
-        // TODO: Check logic for this function
+        iter: &mut Peekable<Chars<'_>>,
+        line_num: usize,
+    ) -> Result<Option<char>, String> {
         // Assume we have identified and consumed a backslash
-        let mut seg = {
-            match context.next_seg() {
-                Some(next_seg) => next_seg,
-                None => return Err(String::from(EOF_ERROR)), // only condition in which a \ is followed by nothing.
-            }
-        };
-        // from here, we *know* there is at least one character left to read.
-        let outchar: char;
-        let c = seg.next().unwrap();
-        match c {
-            "b" => outchar = '\u{0008}',
-            "t" => outchar = '\t',
-            "n" => outchar = '\n',
-            "f" => outchar = '\u{000C}',
-            "r" => outchar = '\r',
-            "\"" => outchar = '\"',
-            "\\" => outchar = '\\',
-            "u" | "U" => match escape_utf8(&mut seg) {
-                Some(c) => outchar = c,
-                None => {
-                    return Err(format!(
-                    "Err: Line {}: Invalid UTF8 escape sequence. Format: \\uXXXX or \\uXXXXXXXX",
-                    context.line_num()
-                ))
+        match iter.peek() {
+            None => Err(format!("Line {}: Invalid escape sequence.", line_num)),
+            Some(' ' | '\t' | '\r' | '\n') => {
+                // The backslash must be the last non-whitespace character on the line.
+                while let Some(' ' | '\t' | '\r') = iter.peek() {
+                    iter.next();
                 }
-            },
-            _ => {
-                if !c.chars().next().unwrap().is_whitespace() {
-                    return Err(format!(
-                        "Error: Line {}: Invalid escape sequence.",
-                        context.line_num()
-                    ));
-                } else {
-                    // find next non-whitespace char
-                    let count = seg.count();
-                    match self.get_nonwhitespace(ParserLine::freeze(context, count)) {
-                        Ok((ch, context)) => {
-                            if ch == '\"' {
-                                return Ok((ch, context, true)); // important termination condition
-                            } else {
-                                return Ok((ch, context, false));
-                            }
-                        }
-                        Err(err) => return Err(err),
-                    }
+                if iter.next() != Some('\n') {
+                    return Err(format!("Line {}: Invalid escape sequence.", line_num));
                 }
+                // find next non-whitespace char
+                while let Some(' ' | '\t' | '\r' | '\n') = iter.peek() {
+                    iter.next();
+                }
+                Ok(None)
             }
+            Some(_) => match Self::parse_basic_escape_sequence(iter) {
+                Some(ch) => Ok(Some(ch)),
+                None => Err(format!(
+                    "Line {}: Invalid escape sequence. Unicode escapes must be formatted \\uXXXX or \\uXXXXXXXX",
+                    line_num
+                )),
+            },
         }
-        let count = seg.count();
-        Ok((outchar, ParserLine::freeze(context, count), false))
     }
 
-    fn parse_basic_escape_sequence(mut context: ParserLine) -> Option<(char, ParserLine)> {
+    fn parse_basic_escape_sequence(iter: &mut Peekable<Chars<'_>>) -> Option<char> {
         // Assume we have identified and consumed a backslash
-        let mut seg = {
-            match context.next_seg() {
-                None => return None,
-                Some(next) => next,
-            }
+        let outchar = match iter.next()? {
+            'b' => '\u{0008}',
+            't' => '\t',
+            'n' => '\n',
+            'f' => '\u{000C}',
+            'r' => '\r',
+            '"' => '\"',
+            '\\' => '\\',
+            'u' | 'U' => escape_utf8(iter)?,
+            _ => return None, // Whitespace escapes are only valid in multi-line strings
         };
-
-        let outchar: char;
-        match seg.next() {
-            Some(c) => {
-                match c {
-                    "b" => outchar = '\u{0008}',
-                    "t" => outchar = '\t',
-                    "n" => outchar = '\n',
-                    "f" => outchar = '\u{000C}',
-                    "r" => outchar = '\r',
-                    "\"" => outchar = '\"',
-                    "\\" => outchar = '\\',
-                    "u" | "U" => match escape_utf8(&mut seg) {
-                        None => return None,
-                        Some(c) => outchar = c,
-                    },
-                    _ => return None, // This is where you would check for non-whitespace in a multi-string context
-                }
-            }
-            None => return None,
-        }
-        let count = seg.count();
-        Some((outchar, ParserLine::freeze(context, count)))
-    }
-
-    /// Proceeds to the next non-whitespace character in the buffer.
-    /// Moves to next line if necessary.
-    /// Whitespace in *this* instance is defined as Unicode whitespace; it's a superset of TOML whitespace.
-    fn get_nonwhitespace(&mut self, mut context: ParserLine) -> InnerParseResult<char> {
-        // The last line may have ended if the whitespace character was a newline, so
-        // the next line is obtained in that instance.
-        if context.is_exhausted() {
-            context = self.next_parserline()?;
-        }
-        let mut seg = context.next_seg().unwrap();
-        // find the non-newline
-        loop {
-            match seg.next() {
-                Some(ch) => {
-                    let ch = ch.chars().next().unwrap();
-                    if !ch.is_whitespace() {
-                        let count = seg.count();
-                        return Ok((ch, ParserLine::freeze(context, count)));
-                    } else {
-                        continue;
-                    }
-                }
-                None => {
-                    // try to get the next segment
-                    match context.next_seg() {
-                        Some(next_seg) => {
-                            seg = next_seg;
-                            continue;
-                        }
-                        None => {
-                            // try to get the next line
-                            context = self.next_parserline()?;
-                            return self.get_nonwhitespace(context);
-                        }
-                    }
-                }
-            }
-        }
+        Some(outchar)
     }
 
-    /// Parses TOML-style basic and multi-line literal strings
-    pub fn parse_literal_string(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        // UNWRAP justification: a ' character was found before calling this function, so we know the segment exists.
-        let mut seg = context.peek().unwrap();
-        for _ in 0..3 {
-            if let Some(&LITERAL_STR_TOKEN) = seg.peek() {
-                seg.next();
-                continue;
-            }
-            return self.parse_basic_litstr(context);
-        }
-        self.parse_multi_litstr(context)
-    }
-
-    fn parse_basic_litstr(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        let mut seg = context.next_seg().unwrap();
-        seg.next(); // throw away delimiter.
-        let mut grapheme_pool = String::with_capacity(self.buffer.capacity());
-        loop {
-            match seg.next() {
-                None => match context.next_seg() {
-                    None => {
-                        return Err(format!(
-                            "Err: Line {}: Non-terminating literal string.",
-                            self.line_num
-                        ))
-                    }
-                    Some(next) => {
-                        seg = next;
-                        continue;
-                    }
-                },
-                Some(ch) => match ch {
-                    LITERAL_STR_TOKEN => break,
-                    _ => {
-                        if !is_valid_litstr_grapheme(ch) {
-                            return Err(format!(
-                                "Err: Line {}: Invalid Unicode Character U+{:X} in literal string.",
-                                self.line_num,
-                                ch.chars().next().unwrap() as u32,
-                            ));
-                        } else {
-                            grapheme_pool.push_str(ch);
-                        }
-                    }
-                },
-            }
-        }
-        let count = seg.count();
-        Ok((
-            TOMLType::LitStr(grapheme_pool),
-            ParserLine::freeze(context, count),
-        ))
+    /// Decodes a literal string lexeme (delimiters included).
+    /// The lexer has already validated the characters, and there are no escapes to process.
+    pub(crate) fn parse_basic_litstr(lexeme: &str) -> Cow<'_, str> {
+        Cow::Borrowed(&lexeme[1..lexeme.len() - 1])
     }
 
-    fn parse_multi_litstr(&mut self, mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        let mut apostrophe_count = 0;
-        let sz = self.buffer.capacity();
-        let mut grapheme_pool = String::with_capacity(sz);
-
-        let mut seg = context.next_seg().unwrap();
-        // throw away first three characters (the delimiter)
-        for _ in 0..3 {
-            seg.next();
-        }
+    /// Decodes a multi-line literal string lexeme (delimiters included).
+    pub(crate) fn parse_multi_litstr(lexeme: &str) -> Cow<'_, str> {
+        let body = &lexeme[3..lexeme.len() - 3];
         // trim immediate newline
-        if let Some(&"\n") = seg.peek() {
-            seg.next();
-        }
-        // enter multi-string context
-        let mut graphemes_added = 0;
-        while apostrophe_count < 3 {
-            match seg.next() {
-                Some(ch) => {
-                    graphemes_added += 1;
-                    match ch {
-                        LITERAL_STR_TOKEN => apostrophe_count += 1,
-                        _ => apostrophe_count = 0,
-                    }
-                    grapheme_pool.push_str(ch);
-                }
-
-                None => {
-                    // get a newline if necessary
-                    if context.is_exhausted() {
-                        context = self.next_parserline()?;
-                    }
-                    seg = context.next_seg().unwrap();
-                }
-            }
-        } // Found closing delimiter
-
-        // check for extra apostrophe (this is a really annoying thing to allow)
-        // REFERENCE: https://toml.io/en/v1.0.0#string
-        if let Some(&LITERAL_STR_TOKEN) = seg.peek() {
-            grapheme_pool.push_str(seg.next().unwrap());
-            graphemes_added += 1;
-        }
-
-        let outstring = grapheme_pool
-            .as_str()
-            .graphemes(true)
-            .take(graphemes_added - 3)
-            .collect::<String>();
-        let count = seg.count();
-        let context = ParserLine::freeze(context, count);
-        Ok((TOMLType::MultiLitStr(outstring), context))
+        Cow::Borrowed(trim_leading_newline(body))
     }
 
     // == Numeric Type Processing ==
     /// Parse the input into either an integer, a float, or a date.
-    /// We don't necessarily care which.
-    pub fn parse_numeric(context: ParserLine) -> InnerParseResult<TOMLType> {
-        // Append the error messages since we would lose them on each subsequent parsing
-        // function call..
-        let mut err_msg = String::new();
-        match Self::parse_integer(context.clone()) {
-            Ok(out) => Ok(out),
-
-            Err(int_msg) => {
-                err_msg.push_str(int_msg.as_str());
-                err_msg.push('\n');
-                match Self::parse_float(context.clone()) {
-                    Ok(out) => Ok(out),
-
-                    Err(float_msg) => {
-                        err_msg.push_str(float_msg.as_str());
-                        err_msg.push('\n');
-                        match Self::parse_date(context.clone()) {
-                            Ok(out) => Ok(out),
-
-                            Err(date_msg) => {
-                                println!(
-                                    "Numeric Parsing Error printout:\n{}",
-                                    err_msg + date_msg.as_str()
-                                );
-                                Err(format!("Line {}: Could not parse numeric type. Tried integer, float, and date parsing.", context.line_num()))
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    /// We don't necessarily care which. On failure, returns why each of the three failed.
+    pub fn parse_numeric(text: &str, line_num: usize) -> Result<TOMLTypeRef<'a>, Vec<Note>> {
+        let int_msg = match Self::parse_integer(text, line_num) {
+            Ok(out) => return Ok(out),
+            Err(msg) => msg,
+        };
+        let float_msg = match Self::parse_float(text, line_num) {
+            Ok(out) => return Ok(out),
+            Err(msg) => msg,
+        };
+        let date_msg = match Self::parse_date(text, line_num) {
+            Ok(out) => return Ok(out),
+            Err(msg) => msg,
+        };
+        trace!("Line {}: `{}` is not a numeric type", line_num, text);
+        let line_prefix = format!("Line {}: ", line_num);
+        let note = |topic, msg: String| match msg.strip_prefix(line_prefix.as_str()) {
+            Some(reason) => Note::new(topic, reason.to_string()),
+            None => Note::new(topic, msg),
+        };
+        Err(vec![
+            note("integer", int_msg),
+            note("float", float_msg),
+            note("date", date_msg),
+        ])
     }
 
     // Integer parsing
 
-    // This function doesn't need to take a mutable reference because there is no reason to
-    // modify the structure. Is that the correct thing to do?
-    pub fn parse_integer(mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        // Assume we know some character data exists.
-        let line_num = context.line_num();
-        let mut seg = context.next_seg().unwrap();
-        seg.peek();
-        seg.skip_ws();
+    /// Parses an integer that must fit in an `i64`.
+    pub fn parse_integer(text: &str, line_num: usize) -> Result<TOMLTypeRef<'a>, String> {
+        Self::parse_integer_of_width(text, line_num, false)
+    }
+
+    /// Parses an integer, allowing anything that fits in an `i128` when `wide` is set.
+    pub fn parse_integer_of_width(
+        text: &str,
+        line_num: usize,
+        wide: bool,
+    ) -> Result<TOMLTypeRef<'a>, String> {
+        let text = text.trim();
         let mut is_negative = false;
         let mut plus_found = false;
-
-        {
-            let ch = *seg.peek().unwrap(); // UNWRAP Justification: the first character is always present
-            match ch {
-                "+" => {
-                    plus_found = true;
-                    seg.next();
-                }
-                "-" => {
-                    is_negative = true;
-                    seg.next();
-                }
-                _ => (),
-            }
+        let mut digits = text;
+        if let Some(rest) = text.strip_prefix('+') {
+            plus_found = true;
+            digits = rest;
+        } else if let Some(rest) = text.strip_prefix('-') {
+            is_negative = true;
+            digits = rest;
         }
 
         // Check for a prefix directive.
-        let mut output: i64 = 0;
-        match seg.peek() {
-            Some(ch) => {
-                match *ch {
-                    "0" => {
-                        seg.next();
-                        // Try parsing non-decimal format
-                        match seg.next() {
-                            None => (),
-                            Some(prefix) => match prefix {
-                                " " | "\t" | "\n" => (),
-
-                                "b" | "o" | "x" => {
-                                    if is_negative || plus_found {
-                                        return Err(format!(
-                                            "Line {}: Integer Parsing Error: Invalid Prefix. Write '0[box]'", line_num
-                                        ));
-                                    }
-
-                                    let count = seg.count();
-                                    (output, context) = Self::nondec_parse(
-                                        prefix.to_string(),
-                                        ParserLine::freeze(context, count),
-                                    )?;
-                                    seg = context.next_seg().unwrap_or_default();
-                                }
-
-                                _ => {
-                                    return Err(format!(
-                                        "Line {}: Integer Parsing Error: No leading zeros.",
-                                        line_num
-                                    ))
-                                }
-                            },
-                        }
-                    }
-                    "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => {
-                        let count = seg.count();
-                        (output, context) = Self::dec_parse(ParserLine::freeze(context, count))?;
-                        seg = context.next_seg().unwrap_or_default();
-                    }
-                    _ => {
-                        return Err(format!(
-                            "Line {}: Invalid integer starting character '{}'.",
-                            line_num, ch
-                        ))
-                    }
+        let bytes = digits.as_bytes();
+        // Accumulate the magnitude, as the most negative value has no positive counterpart.
+        let magnitude = match bytes {
+            [] => return Err(format!("Line {}: Invalid integer format.", line_num)),
+            [b'0'] => 0,
+            [b'0', prefix @ (b'b' | b'o' | b'x'), ..] => {
+                if is_negative || plus_found {
+                    return Err(format!(
+                        "Line {}: Integer Parsing Error: Invalid Prefix. Write '0[box]'",
+                        line_num
+                    ));
                 }
+                Self::nondec_parse(*prefix, &digits[2..], line_num)?
             }
-            None => return Err(format!("Line {}: Invalid integer format.", line_num)),
-        }
+            [b'0', ..] => {
+                return Err(format!(
+                    "Line {}: Integer Parsing Error: No leading zeros.",
+                    line_num
+                ))
+            }
+            [b'1'..=b'9', ..] => Self::dec_parse(digits, line_num)?,
+            [ch, ..] => {
+                return Err(format!(
+                    "Line {}: Invalid integer starting character '{}'.",
+                    line_num, *ch as char
+                ))
+            }
+        };
 
-        if is_negative {
-            output = -output;
+        let value = if is_negative {
+            0_i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        };
+        match value {
+            Some(val) => match i64::try_from(val) {
+                Ok(val) => Ok(TOMLTypeRef::Int(val)),
+                Err(_) if wide => Ok(TOMLTypeRef::WideInt(val)),
+                Err(_) => Err(out_of_range(
+                    text,
+                    i64::MIN.into(),
+                    i64::MAX.into(),
+                    line_num,
+                )),
+            },
+            None if wide => Err(out_of_range(text, i128::MIN, i128::MAX, line_num)),
+            None => Err(out_of_range(
+                text,
+                i64::MIN.into(),
+                i64::MAX.into(),
+                line_num,
+            )),
         }
-        let count = seg.count();
-        context = ParserLine::freeze(context, count);
-        Ok((TOMLType::Int(output), context))
     }
 
-    fn dec_parse(mut context: ParserLine) -> InnerParseResult<i64> {
-        let line_num = context.line_num();
-        let mut seg = context.next_seg().unwrap();
-        let mut found_underscore = false;
-        let mut output: i64 = 0;
-
-        // parse the number, checking for overflow
-        loop {
-            let previous = output;
-            match seg.peek() {
-                None => {
-                    if found_underscore {
-                        return Err(format!(
-                            "Line {}: Integer Parsing Error: Underscore at end of integer.",
-                            line_num
-                        ));
-                    } else {
-                        break;
-                    }
-                }
-                Some(ch) => {
-                    match *ch {
-                        "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => {
-                            found_underscore = false; // is overwriting faster than doing a check every iteration?
-                            output = output
-                                .wrapping_mul(10)
-                                .wrapping_add(ch.parse::<i64>().unwrap());
-
-                            if previous > output {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Integer Overflow",
-                                    line_num
-                                ));
-                            }
-                        }
-                        "_" => {
-                            if found_underscore {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Underscore must be sandwiched between two digits (ex. `12_000`)", line_num
-                                ));
-                            } else {
-                                found_underscore = true;
-                            }
-                        }
-                        " " | "\t" | "\n" => {
-                            if found_underscore {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Underscore at end of integer.",
-                                    line_num
-                                ));
-                            } else {
-                                break;
-                            }
-                        }
-                        _ => {
-                            if found_underscore {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Underscore at end of integer.",
-                                    line_num
-                                ));
-                            } else {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Invalid digit value '{}'.",
-                                    line_num, ch
-                                ));
-                            }
-                        }
-                    }
-                    // advance iterator
-                    seg.next();
-                }
-            }
-        }
-
-        let count = seg.count();
-        Ok((output, ParserLine::freeze(context, count)))
+    fn dec_parse(digits: &str, line_num: usize) -> Result<u128, String> {
+        Self::radix_parse(digits, 10, line_num)
     }
 
-    // REFACTOR POT.: I could remove the three nondec functions and instead have three different const arrays of valid
-    // digit &strs. The mode would then determine which array to use and which scale factor to use (16, 8, or 2). The
-    // overall logic is the same for all three inner functions.
-    fn nondec_parse(mode: String, mut context: ParserLine) -> InnerParseResult<i64> {
-        // NOTE: Consider changing mode's type to char.
-        let mut seg = context.peek().unwrap();
+    fn nondec_parse(mode: u8, digits: &str, line_num: usize) -> Result<u128, String> {
         // preliminary check to see if the next value is some numeric.
-        if !is_hexdigit(seg.peek()) {
+        if !digits.starts_with(|c: char| c.is_ascii_hexdigit()) {
             return Err(format!(
                 "Line {}: Integer Parsing Error: Invalid integer format.",
-                context.line_num()
+                line_num
             ));
         }
-        match mode.as_str() {
-            "x" => Self::hex_parse(context),
-            "o" => Self::oct_parse(context),
-            "b" => Self::bin_parse(context),
+        match mode {
+            b'x' => Self::radix_parse(digits, 16, line_num),
+            b'o' => Self::radix_parse(digits, 8, line_num),
+            b'b' => Self::radix_parse(digits, 2, line_num),
             _ => Err("`TOMLParser::nondec_parse` should never get here internally.".to_string()),
         }
     }
 
-    /// Parses the input hexadecimal into a decimal value.
-    fn hex_parse(mut context: ParserLine) -> InnerParseResult<i64> {
-        let line_num = context.line_num();
-        let mut seg = context.next_seg().unwrap();
+    /// Parses a sequence of (potentially underscore-separated) digits of the given radix.
+    /// Saturates at `u128::MAX`, which is out of range for every integer width.
+    fn radix_parse(digits: &str, radix: u32, line_num: usize) -> Result<u128, String> {
         let mut found_underscore = false;
-        // parse the number, checking for overflow
-        let mut output: i64 = 0;
-        loop {
-            let previous = output;
-            match seg.peek() {
-                None => break,
-                Some(ch) => {
-                    match *ch {
-                        "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "a" | "b"
-                        | "c" | "d" | "e" | "f" | "A" | "B" | "C" | "D" | "E" | "F" => {
-                            let digit = i64::from_str_radix(ch, 16).unwrap();
-                            found_underscore = false; // is overwriting faster than doing a check every iteration?
-                            output = output.wrapping_mul(16).wrapping_add(digit);
-
-                            if previous > output {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Integer Overflow",
-                                    line_num
-                                ));
-                            }
-                        }
-                        "_" => {
-                            if found_underscore {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Underscore must be sandwiched between two digits (ex. `12_000`)", line_num
-                                ));
-                            } else {
-                                found_underscore = true;
-                            }
-                        }
-                        " " | "\t" | "\n" => break, // NOTE: Don't need to check for comment symbol '#' because it would appear in a separate line segment.
-                        _ => {
-                            return Err(format!(
-                                "Line {}: Integer Parsing Error: Invalid digit value '{}'.",
-                                line_num, ch
-                            ))
-                        }
-                    }
-                    // advance iterator
-                    seg.next();
+        let mut output: u128 = 0;
+        for ch in digits.chars() {
+            if ch == '_' {
+                if found_underscore {
+                    return Err(format!(
+                        "Line {}: Integer Parsing Error: Underscore must be sandwiched between two digits (ex. `12_000`)", line_num
+                    ));
                 }
+                found_underscore = true;
+                continue;
             }
-        }
-
-        let count = seg.count();
-        Ok((output, ParserLine::freeze(context, count)))
-    }
-
-    fn oct_parse(mut context: ParserLine) -> InnerParseResult<i64> {
-        let line_num = context.line_num();
-        let mut seg = context.next_seg().unwrap();
-        let mut found_underscore = false;
-        // parse the number, checking for overflow
-        let mut output: i64 = 0;
-        loop {
-            let previous = output;
-            match seg.peek() {
-                None => break,
-                Some(ch) => {
-                    match *ch {
-                        "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" => {
-                            found_underscore = false; // is overwriting faster than doing a check every iteration?
-                            output = output
-                                .wrapping_mul(8)
-                                .wrapping_add(ch.parse::<i64>().unwrap());
-
-                            if previous > output {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Integer Overflow",
-                                    line_num
-                                ));
-                            }
-                        }
-                        "_" => {
-                            if found_underscore {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Underscore must be sandwiched between two digits (ex. `12_000`)", line_num
-                                ));
-                            } else {
-                                found_underscore = true;
-                            }
-                        }
-                        " " | "\t" | "\n" => break, // NOTE: Don't need to check for comment symbol '#' because it would appear in a separate line segment.
-                        _ => {
-                            return Err(format!(
-                                "Line {}: Integer Parsing Error: Invalid digit value '{}'.",
-                                line_num, ch
-                            ))
-                        }
-                    }
-                    // advance iterator
-                    seg.next();
+            let digit = match ch.to_digit(radix) {
+                Some(digit) => digit,
+                None => {
+                    return Err(format!(
+                        "Line {}: Integer Parsing Error: Invalid digit value '{}'.",
+                        line_num, ch
+                    ))
                 }
-            }
+            };
+            found_underscore = false;
+            output = output
+                .saturating_mul(u128::from(radix))
+                .saturating_add(u128::from(digit));
         }
-
-        let count = seg.count();
-        Ok((output, ParserLine::freeze(context, count)))
-    }
-
-    fn bin_parse(mut context: ParserLine) -> InnerParseResult<i64> {
-        let line_num = context.line_num();
-        let mut seg = context.next_seg().unwrap();
-        let mut found_underscore = false;
-        // parse the number, checking for overflow
-        let mut output: i64 = 0;
-        loop {
-            let previous = output;
-            match seg.peek() {
-                None => break,
-                Some(ch) => {
-                    match *ch {
-                        "0" | "1" => {
-                            found_underscore = false; // is overwriting faster than doing a check every iteration?
-                            output = output
-                                .wrapping_mul(2)
-                                .wrapping_add(ch.parse::<i64>().unwrap());
-
-                            if previous > output {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Integer Overflow",
-                                    line_num
-                                ));
-                            }
-                        }
-                        "_" => {
-                            if found_underscore {
-                                return Err(format!(
-                                    "Line {}: Integer Parsing Error: Underscore must be sandwiched between two digits (ex. `12_000`)", line_num
-                                ));
-                            } else {
-                                found_underscore = true;
-                            }
-                        }
-                        " " | "\t" | "\n" => break, // NOTE: Don't need to check for comment symbol '#' because it would appear in a separate line segment.
-                        _ => {
-                            return Err(format!(
-                                "Line {}: Integer Parsing Error: Invalid digit value '{}'.",
-                                line_num, ch
-                            ))
-                        }
-                    }
-                    // advance iterator
-                    seg.next();
-                }
-            }
+        if found_underscore {
+            return Err(format!(
+                "Line {}: Integer Parsing Error: Underscore at end of integer.",
+                line_num
+            ));
         }
-
-        let count = seg.count();
-        Ok((output, ParserLine::freeze(context, count)))
+        Ok(output)
     }
 
     /// Parses TOML-valid float into f64
-    pub fn parse_float(mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        // Assume a non-empty context.
-
-        let is_keepable = |x: &&str| {
-            let x = *x;
-            !(x == " " || x == "\t" || x == "\n" || x == "_")
-        };
+    pub fn parse_float(text: &str, line_num: usize) -> Result<TOMLTypeRef<'a>, String> {
+        let text = text.trim();
+        let unsigned = text.trim_start_matches(['+', '-']);
+        if text.len() - unsigned.len() > 1 {
+            return Err(format!(
+                "Line {}: Float Parsing Error: Multiple signs.",
+                line_num
+            ));
+        }
+        if unsigned == "inf" || unsigned == "nan" {
+            return match text.parse::<f64>() {
+                Ok(val) => Ok(TOMLTypeRef::Float(val)),
+                Err(_) => Err(format!("Line {}: Float Parsing Error.", line_num)),
+            };
+        }
 
         // Check for basic formatting issues
-        let mut format_check_iter = context.peek().unwrap().filter(is_keepable);
-        if let Some(".") = format_check_iter.next() {
+        if unsigned.starts_with('.') {
             return Err(format!(
                 "Line {}: Float Parsing Error: Cannot begin float with decimal point `.`",
-                context.line_num()
+                line_num
             ));
-        } else {
-            let mut format_check_iter = context.peek().unwrap().filter(is_keepable).peekable();
-            while let Some(ch) = format_check_iter.next() {
-                if ch == "." {
-                    match format_check_iter.peek() {
-                        Some(&"0" | &"1"| &"2" | &"3" | &"4"| &"5" | &"6" | &"7" | &"8" | &"9") => {}
-                        _ => return Err(format!(
-                                "Line {}: Float Parsing Error: decimal point must be followed by a digit.",
-                                context.line_num()
-                            )),
-                    }
-                }
+        }
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(idx) => (&unsigned[..idx], Some(&unsigned[idx + 1..])),
+            None => (unsigned, None),
+        };
+        let (int_part, frac_part) = match mantissa.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (mantissa, None),
+        };
+        if frac_part.is_none() && exponent.is_none() {
+            return Err(format!(
+                "Line {}: Float Parsing Error: A float requires a fractional part or an exponent.",
+                line_num
+            ));
+        }
+        if int_part.len() > 1 && int_part.starts_with('0') {
+            return Err(format!(
+                "Line {}: Float Parsing Error: No leading zeros.",
+                line_num
+            ));
+        }
+        if !is_digit_sequence(int_part) {
+            return Err(format!("Line {}: Float Parsing Error.", line_num));
+        }
+        if let Some(frac_part) = frac_part {
+            if !is_digit_sequence(frac_part) {
+                return Err(format!(
+                    "Line {}: Float Parsing Error: decimal point must be followed by a digit.",
+                    line_num
+                ));
+            }
+        }
+        if let Some(exponent) = exponent {
+            if !is_digit_sequence(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)) {
+                return Err(format!(
+                    "Line {}: Float Parsing Error: Invalid exponent.",
+                    line_num
+                ));
             }
         }
 
         // Parse the float
-        let seg = context.next_seg().unwrap();
-        let result = seg.content().trim().replace("_", "").parse::<f64>();
-        match result {
-            Ok(val) => Ok((TOMLType::Float(val), ParserLine::freeze(context, 0))),
-            Err(_) => Err(format!("Line {}: Float Parsing Error.", context.line_num())),
+        match text.replace('_', "").parse::<f64>() {
+            Ok(val) => Ok(TOMLTypeRef::Float(val)),
+            Err(_) => Err(format!("Line {}: Float Parsing Error.", line_num)),
         }
     }
 
     // == DateTime Parsing ==
-    pub fn parse_date(mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        let mut seg = context.next_seg().unwrap();
-        let test_str = seg.content().trim();
-        match try_naive_dtparse(test_str) {
-            Some(date) => Ok((date, ParserLine::freeze(context, 0))),
-            None => Err(format!(
-                "Line {}: Could not parse a datetime",
-                context.line_num()
-            )),
+    pub fn parse_date(text: &str, line_num: usize) -> Result<TOMLTypeRef<'a>, String> {
+        match text.trim().parse::<Datetime>() {
+            Ok(dt) => Ok(TOMLTypeRef::Datetime(dt)),
+            Err(msg) => Err(format!("Line {}: {}", line_num, msg)),
         }
     }
 
     // == Boolean Parsing ==
-    pub fn parse_bool(mut context: ParserLine) -> InnerParseResult<TOMLType> {
-        let output: Option<bool>;
-        let mut seg = context.next_seg().unwrap();
-
-        let str_check = seg.content().trim();
-        output = str_check.parse::<bool>().ok();
-
-        match output {
-            Some(val) => {
-                // the segment is technically exhausted, so pass zero to freeze.
-                let context = ParserLine::freeze(context, 0);
-                Ok((TOMLType::Bool(val), context))
-            }
-            None => Err(format!(
-                "Line {}: Boolean Parsing Error.",
-                context.line_num()
-            )),
-        }
-    }
-
-    // === Comment Parsing ===
-    #[inline]
-    fn process_comment(mut context: ParserLine) -> InnerParseResult<()> {
-        // assume beginning immediately after `#`
-        let line_num = context.line_num();
-        let mut seg = {
-            match context.next_seg() {
-                Some(next) => next,
-                None => panic!(
-                    "Comment parse context should never be entered with an empty line segment."
-                ),
-            }
-        };
-
-        loop {
-            match seg.next() {
-                // drop the newline
-                Some("\n") => {}
-                Some(ch) => {
-                    if !is_valid_comment_grapheme(ch) {
-                        return Err(format!(
-                            "Invalid Comment Character: '{}' on Line {}",
-                            ch, line_num
-                        ));
-                    }
-                }
-                None => {
-                    if let Some(next_iter) = context.next_seg() {
-                        seg = next_iter;
-                    } else {
-                        break;
-                    }
-                }
+    pub fn parse_bool(text: &str, line_num: usize) -> Result<TOMLTypeRef<'a>, String> {
+        match text.trim() {
+            "true" => Ok(TOMLTypeRef::Bool(true)),
+            "false" => Ok(TOMLTypeRef::Bool(false)),
+            other if other.eq_ignore_ascii_case("true") || other.eq_ignore_ascii_case("false") => {
+                Err(format!(
+                    "Line {}: Boolean Parsing Error: `{}` must be lowercase.",
+                    line_num, other
+                ))
             }
+            _ => Err(format!("Line {}: Boolean Parsing Error.", line_num)),
         }
-
-        Ok(((), context))
     }
-    /// Processes the end of the given line
-    fn process_eol(mut context: ParserLine) -> Result<(), String> {
-        let line_num = context.line_num();
-        let mut iter = context.next_seg().unwrap();
-        iter.skip_ws();
 
-        match iter.next() {
-            Some("\n") => {}
-            Some(COMMENT_TOKEN) => {
-                let count = iter.count();
-                let (_, pline) = Self::process_comment(ParserLine::freeze(context, count))?;
-                context = pline;
-            }
-            Some(ch) => {
-                let ch = ch.to_string();
-                return Err(format!(
-                    "Line {}: Rogue non-whitespace character `{}`  (outside of comment).",
-                    line_num, ch
-                ));
-            }
-            None => {
-                if let Some(seg) = context.next_seg() {
-                    let count = seg.count();
-                    return Self::process_eol(ParserLine::freeze(context, count));
-                }
-            }
+    // === Comment/Whitespace Processing ===
+
+    /// Processes the end of the given line: optional whitespace, an optional comment, and then
+    /// either a newline or the EoF.
+    pub fn process_eol(&mut self) -> Result<(), String> {
+        self.lexer.skip_ws();
+        let line_num = self.line_num();
+        self.eat(Mode::Key, TokenKind::Comment)?;
+        match self.next_token(Mode::Key)? {
+            None => Ok(()),
+            Some(tok) if tok.kind == TokenKind::Newline => Ok(()),
+            Some(tok) => Err(format!(
+                "Line {}: Rogue non-whitespace character `{}`  (outside of comment).",
+                line_num,
+                self.lexer.slice(tok.span)
+            )),
         }
-        assert!(context.is_exhausted());
-        Ok(())
     }
 
     /// Processes whitespace, comments, and blank lines until a
-    /// non-whitespace/non-comment character is found
-    /// Also responsible for setting the terminating EoF flag
-    /// for the parser.
-    fn seek_nonws(&mut self, mut context: ParserLine) -> InnerParseResult<()> {
-        /*
-         * I can't make any assumption about the state of a given ParserLine when this function is
-         *  called. We could be:
-         *   - At the end of a line
-         *   - At the end of the *file*
-         *   - On whitespace
-         *   - On non-ws
-         */
-
-        // Initial State
-        let mut seg = {
-            match context.next_seg() {
-                Some(next) => next,
-                None => {
-                    context = match self.next_parserline() {
-                        Ok(pline) => pline,
-                        Err(msg) => {
-                            self.eof_flag = true; // sets the termination condition of the
-                                                  // Parser
-                            return Err(msg);
-                        }
-                    };
-                    context.next_seg().unwrap()
-                }
-            }
-        }; // Now here, we are guaranteed to have some valid segment.
-
+    /// non-whitespace/non-comment character is found.
+    /// Returns false if the EoF is reached instead.
+    pub fn seek_nonws(&mut self) -> Result<bool, String> {
         loop {
-            // Parse until we find a non-whitespace character that is
-            // neither a comment delimiter nor a newline character
-            seg.skip_ws();
-            if let Some(&ch) = seg.peek() {
-                match ch {
-                    "\n" => {
-                        seg.next();
-                    }
-                    COMMENT_TOKEN => {
-                        seg.next();
-                        let count = seg.count();
-                        let (_, pline) = Self::process_comment(ParserLine::freeze(context, count))?;
-                        return self.seek_nonws(pline);
-                    }
-                    _ => {
-                        // found some grapheme of interest
-                        break;
-                    }
+            self.lexer.skip_ws();
+            match self.lexer.peek_byte() {
+                Some(b'\n' | b'\r' | COMMENT_TOKEN) => {
+                    self.next_token(Mode::Key)?;
                 }
-            } else {
-                // try to get the next segment
-                return self.seek_nonws(context);
+                // Unexpected characters are reported by whoever consumes the next token.
+                _ => return Ok(!self.lexer.is_eof()),
             }
         }
-
-        let count = seg.count();
-        Ok(((), ParserLine::freeze(context, count)))
     }
 }
 
@@ -1709,167 +1419,83 @@ impl TOMLParser {
 
 /// Given a mutable table reference, insert the provided key-value pair into the
 /// structure, defining super tables as needed.
-/// Supertables are typed as dotted key tables (TOMLType::DKTable). This is the case
+/// Supertables are typed as dotted key tables (TOMLTypeRef::DKTable). This is the case
 /// whether the dotted key is within an inline table or a higher-level structure.
-fn insert_keyval(kv: KeyVal<'_>, table_head: &mut TOMLTable) -> Result<(), String> {
+/// Returns the number of keys in the largest table along the key path.
+fn insert_keyval<'a>(kv: KeyVal<'a>, table_head: &mut TOMLTableRef<'a>) -> Result<usize, String> {
     let KeyVal(key_path, val) = kv;
     let mut key_iter = key_path.into_iter().peekable();
-    let mut keyseg: &str;
     let mut curr_table = table_head;
     let mut partial_key: String = String::new(); // tracks the key segments that have been
                                                  // considered
-                                                 // Iterate through the preceding key segments,
-                                                 // creating DKTables as needed, and updating the table
-                                                 // pointer.
-    loop {
-        keyseg = key_iter.next().unwrap();
-        if let None = key_iter.peek() {
-            break;
-        }
-        partial_key.push_str(keyseg);
-        partial_key.push('|');
-        let key = keyseg.to_string();
-        if curr_table.contains_key(&key) {
-            if let TOMLType::DKTable(ref mut dktable) = curr_table.get_mut(&key).unwrap() {
-                curr_table = dktable;
-            } else {
-                return Err(format!(
+                                                 // Iterate through the preceding key segments, creating DKTables as needed, and updating the
+                                                 // table pointer.
+    let mut widest = 0;
+    while let Some(keyseg) = key_iter.next() {
+        partial_key.push_str(&keyseg);
+        if key_iter.peek().is_none() {
+            // insert the value
+            return match curr_table.entry(keyseg) {
+                std::collections::hash_map::Entry::Occupied(_) => Err(format!(
                     "Key `{}` is already defined at this table level.",
                     partial_key
-                ));
-            }
-        } else {
-            // create a dotted key table
-            curr_table.insert(key.clone(), TOMLType::DKTable(TOMLTable::new()));
-            // update curr_table
-            match curr_table.get_mut(&key).unwrap() {
-                TOMLType::DKTable(ref mut dktable) => curr_table = dktable,
-                _ => panic!(
-                    "`insert_keyval` should never reach this point. The table was just defined."
-                ),
-            }
+                )),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(val);
+                    Ok(widest.max(curr_table.len()))
+                }
+            };
         }
-    }
-    // insert the value
-    partial_key.push_str(keyseg);
-    let key = keyseg.to_string();
-    if curr_table.contains_key(&key) {
-        return Err(format!(
-            "Key `{}` is already defined at this table level.",
-            partial_key
-        ));
-    } else {
-        curr_table.insert(key, val);
-        Ok(())
-    }
-}
-
-// Dates
-
-fn try_naive_dtparse(s: &str) -> Option<TOMLType> {
-    if let Ok(val) = DateTime::parse_from_rfc3339(s) {
-        Some(TOMLType::TimeStamp(val))
-    } else if let Some(val) = try_naive_datetime(s) {
-        Some(TOMLType::NaiveDateTime(val))
-    } else if let Some(val) = try_naive_date(s) {
-        Some(TOMLType::Date(val))
-    } else if let Some(val) = try_naive_time(s) {
-        Some(TOMLType::Time(val))
-    } else {
-        None
-    }
-}
-
-fn try_naive_datetime(s: &str) -> Option<NaiveDateTime> {
-    const NAIVEDATETIME_FORMATS: [&str; 4] = [
-        "%Y-%m-%d %H:%M:%S",
-        "%Y-%m-%dT%H:%M:%S",
-        "%Y-%m-%d %H:%M:%S.%f",
-        "%Y-%m-%dT%H:%M:%S.%f",
-    ];
-
-    for format in NAIVEDATETIME_FORMATS {
-        if let Ok(val) = NaiveDateTime::parse_from_str(s, format) {
-            return Some(val);
+        partial_key.push('|');
+        // create a dotted key table if needed
+        widest = widest.max(curr_table.len() + usize::from(!curr_table.contains_key(&keyseg)));
+        let next = curr_table
+            .entry(keyseg)
+            .or_insert_with(|| TOMLTypeRef::DKTable(TOMLTableRef::new()));
+        if let TOMLTypeRef::DKTable(ref mut dktable) = next {
+            curr_table = dktable;
+        } else {
+            return Err(format!(
+                "Key `{}` is already defined at this table level.",
+                partial_key
+            ));
         }
     }
-    None
+    Err(String::from("No key found."))
 }
 
-fn try_naive_date(s: &str) -> Option<NaiveDate> {
-    const NAIVEDATE_FORMAT: &str = "%Y-%m-%d";
+// Integers
 
-    match NaiveDate::parse_from_str(s, NAIVEDATE_FORMAT) {
-        Ok(val) => Some(val),
-        Err(_) => None,
-    }
+fn out_of_range(text: &str, min: i128, max: i128, line_num: usize) -> String {
+    format!(
+        "Line {}: Integer Parsing Error: `{}` is out of range ({} to {}).",
+        line_num, text, min, max
+    )
 }
 
-fn try_naive_time(s: &str) -> Option<NaiveTime> {
-    const NAIVETIME_FORMATS: [&str; 2] = ["%H:%M:%S.%f", "%H:%M:%S"];
+// Strings
 
-    for format in NAIVETIME_FORMATS {
-        if let Ok(val) = NaiveTime::parse_from_str(s, format) {
-            return Some(val);
-        }
-    }
-    None
+/// Multi-line strings drop a newline immediately following the opening delimiter.
+fn trim_leading_newline(body: &str) -> &str {
+    body.strip_prefix('\n')
+        .or_else(|| body.strip_prefix("\r\n"))
+        .unwrap_or(body)
 }
 
-// Grapheme checks
-fn is_valid_str_grapheme(s: &str) -> bool {
-    const CHARS: [&str; 32] = [
-        "\0", "\u{1}", "\u{2}", "\u{3}", "\u{4}", "\u{5}", "\u{6}", "\u{7}", "\u{8}", "\n",
-        "\u{b}", "\u{c}", "\r", "\u{e}", "\u{f}", "\u{10}", "\u{11}", "\u{12}", "\u{13}", "\u{14}",
-        "\u{15}", "\u{16}", "\u{17}", "\u{18}", "\u{19}", "\u{1a}", "\u{1b}", "\u{1c}", "\u{1d}",
-        "\u{1e}", "\u{1f}", "\u{7f}",
-    ];
+// Character checks
 
-    for c in CHARS {
-        if s == c {
-            return false;
-        }
-    }
-    true
-}
-fn is_valid_multstr_grapheme(s: &str) -> bool {
-    if s == "\n" || s == "\r" {
-        true
-    } else {
-        is_valid_str_grapheme(s)
-    }
-}
-fn is_valid_litstr_grapheme(s: &str) -> bool {
-    if s == "\t" {
-        true
-    } else {
-        is_valid_str_grapheme(s)
-    }
-}
-fn is_valid_multi_litstr_grapheme(s: &str) -> bool {
-    if s == "\t" || s == "\n" {
-        true
-    } else {
-        is_valid_str_grapheme(s)
-    }
-}
-fn is_valid_comment_grapheme(s: &str) -> bool {
-    is_valid_str_grapheme(s)
-}
-/// Determines if the provided string is a valid bare key character.
-fn is_barekey_char(s: &str) -> bool {
-    match s.chars().next() {
-        None => false,
-        Some(c) => match c {
-            'A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '-' => true,
-            _ => false,
-        },
-    }
+/// Digits, optionally separated by single underscores (ex. `1_000`).
+fn is_digit_sequence(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with('_')
+        && !s.ends_with('_')
+        && !s.contains("__")
+        && s.bytes().all(|b| b.is_ascii_digit() || b == b'_')
 }
 
 /// Produces a character from a sequence of four or eight hexadecimal digits
 /// Ex. when called on the sequence `0001f525`, the output is 🔥.
-fn escape_utf8(iter: &mut TOMLSeg<'_>) -> Option<char> {
+fn escape_utf8(iter: &mut Peekable<Chars<'_>>) -> Option<char> {
     // try to find either 4 or 8 hexadecimal digits
     const SMALL_SEQ_LENGTH: i32 = 4;
     const LARGE_SEQ_LENGTH: i32 = 8;
@@ -1878,13 +1504,13 @@ fn escape_utf8(iter: &mut TOMLSeg<'_>) -> Option<char> {
     let mut digits_parsed = 0;
 
     while digits_parsed < LARGE_SEQ_LENGTH {
-        if is_hexdigit(iter.peek()) {
-            let digit = iter.next().unwrap();
-            hex_val = 16 * hex_val + u32::from_str_radix(digit, 16).unwrap(); // UNWRAP Justification: to reach this operation, the character must be a Hex digit.
-        } else if digits_parsed == SMALL_SEQ_LENGTH {
-            break;
-        } else {
-            return None;
+        match iter.peek().and_then(|c| c.to_digit(16)) {
+            Some(digit) => {
+                iter.next();
+                hex_val = 16 * hex_val + digit;
+            }
+            None if digits_parsed == SMALL_SEQ_LENGTH => break,
+            None => return None,
         }
         digits_parsed += 1;
     }
@@ -1892,59 +1518,30 @@ fn escape_utf8(iter: &mut TOMLSeg<'_>) -> Option<char> {
     std::char::from_u32(hex_val)
 }
 
-/// determines if the next entry of a UTF8Peek Iterator is a hexadecimal value
-fn is_hexdigit(query: Option<&&str>) -> bool {
-    match query {
-        Some(text) => {
-            let char_opt = text.chars().next();
-            match char_opt {
-                Some('0'..='9' | 'A'..='F' | 'a'..='f') => true,
-                _ => false, // was the empty string
-            }
-        }
-        None => false,
-    }
-}
-
-fn is_numeric(s: &str) -> bool {
-    match s {
-        "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => true,
-        _ => false,
-    }
-}
-
-fn is_octal(s: &str) -> bool {
-    if s == "8" || s == "9" {
-        false
-    } else {
-        is_numeric(s)
-    }
-}
-
 #[cfg(test)]
 mod helper_tests {
     use super::*;
 
     #[test]
-    fn test_is_hexdigit() {
-        assert_eq!(false, is_hexdigit(Some(&"")));
-        assert_eq!(false, is_hexdigit(Some(&"T")));
-        assert_eq!(false, is_hexdigit(Some(&"J")));
-        assert_eq!(false, is_hexdigit(None));
-
-        for i in 0..10 {
-            let s = i.to_string();
-            assert_eq!(true, is_hexdigit(Some(&s.as_str())));
-        }
-
-        for i in 'a'..'g' {
-            let s = i.to_string();
-            assert_eq!(true, is_hexdigit(Some(&s.as_str())));
-        }
+    fn test_escape_utf8() {
+        let escape = |s: &str| escape_utf8(&mut s.chars().peekable());
+        assert_eq!(None, escape(""));
+        assert_eq!(None, escape("T"));
+        assert_eq!(None, escape("00J"));
+        assert_eq!(Some('é'), escape("00E9"));
+        assert_eq!(Some('é'), escape("00e9\\n"));
+        assert_eq!(Some('🔥'), escape("0001f525"));
+        assert_eq!(None, escape("0001f5"));
+    }
 
-        for i in 'A'..'G' {
-            let s = i.to_string();
-            assert_eq!(true, is_hexdigit(Some(&s.as_str())));
-        }
+    #[test]
+    fn test_is_digit_sequence() {
+        assert!(is_digit_sequence("1_000"));
+        assert!(is_digit_sequence("0"));
+        assert!(!is_digit_sequence(""));
+        assert!(!is_digit_sequence("_1"));
+        assert!(!is_digit_sequence("1_"));
+        assert!(!is_digit_sequence("1__0"));
+        assert!(!is_digit_sequence("1a"));
     }
 }
@@ -1,9 +1,23 @@
 #![cfg(test)]
 use std::path::Path;
 
-use super::{KeyVal, ParserLine, TOMLParser, TOMLTable, TOMLType, TPath};
+use super::{KeyVal, TOMLParser, TOMLTableRef, TOMLTypeRef};
 type TestReturn = Result<(), String>;
 
+/// Reads a file from the `test_resources` directory.
+fn read_resource(file_str: &str) -> Result<String, String> {
+    let source_dir = match Path::new(file!()).canonicalize() {
+        Ok(s) => s,
+        _ => return Err(String::from("File error.")),
+    };
+    let file = source_dir.parent().unwrap().join(file_str);
+    Ok(TOMLParser::read_file_limited(
+        file.to_str().unwrap(),
+        usize::MAX,
+        true,
+    )?)
+}
+
 // TESTS
 #[test]
 fn test_parser() -> TestReturn {
@@ -16,13 +30,9 @@ fn test_parser() -> TestReturn {
     //const FILES: [&str; 1] = [ "test_resources/blank.toml" ];
 
     for file_str in FILES {
-        let source_dir = match Path::new(file!()).canonicalize() {
-            Ok(s) => s,
-            _ => return Err(String::from("File error.")),
-        };
-        let file = source_dir.parent().unwrap().join(file_str);
-        println!("File: {}", file.to_str().unwrap());
-        let mut parser = TOMLParser::init(file.to_str().unwrap())?;
+        println!("File: {}", file_str);
+        let source = read_resource(file_str)?;
+        let mut parser = TOMLParser::new(&source);
         let table = parser.parse_toml()?;
         println!("Parsed TOML:\n{:?}\n", table);
     }
@@ -31,21 +41,12 @@ fn test_parser() -> TestReturn {
 
 #[test]
 fn parse_tableheads() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/table_heads.toml");
+    let source = read_resource("test_resources/table_heads.toml")?;
+    let mut parser = TOMLParser::new(&source);
 
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
-
-    let mut table = TOMLTable::new();
-    while let Ok(pline) = parser.next_parserline() {
-        parser.parse_table_header(pline, &mut table)?;
-        // println!("Table:\n{:?}", parser.view_table());
+    let mut table = TOMLTableRef::new();
+    while parser.seek_nonws()? {
+        parser.parse_table_header(&mut table)?;
     }
     println!("Table:\n{:?}", &table);
     Ok(())
@@ -53,19 +54,13 @@ fn parse_tableheads() -> TestReturn {
 
 #[test]
 fn test_insertion() -> TestReturn {
-    let mut table = TOMLTable::new();
+    let mut table = TOMLTableRef::new();
     let table_head = &mut table;
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/keyvals.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
-    while let Ok(pline) = parser.next_parserline() {
-        let (key_val, _) = parser.parse_keyval(pline)?;
+    let source = read_resource("test_resources/keyvals.toml")?;
+    let mut parser = TOMLParser::new(&source);
+    while parser.seek_nonws()? {
+        let key_val = parser.parse_keyval()?;
+        parser.process_eol()?;
         TOMLParser::insert(key_val, table_head)?;
     }
     println!("Parsed Table: {:?}", table);
@@ -74,18 +69,11 @@ fn test_insertion() -> TestReturn {
 
 #[test]
 fn key_vals() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/keyvals.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
+    let source = read_resource("test_resources/keyvals.toml")?;
+    let mut parser = TOMLParser::new(&source);
     let mut successes = 0;
-    while let Ok(pline) = parser.next_parserline() {
-        let (KeyVal(key, val), _) = parser.parse_keyval(pline)?;
+    while parser.seek_nonws()? {
+        let KeyVal(key, val) = parser.parse_keyval()?;
         println!("Key: {key:?}\nVal: {val:?}\n");
         successes += 1;
     }
@@ -95,17 +83,10 @@ fn key_vals() -> TestReturn {
 
 #[test]
 fn parse_keys() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/keys.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
-    while let Ok(pline) = parser.next_parserline() {
-        let (path, _) = parser.parse_key(pline)?;
+    let source = read_resource("test_resources/keys.toml")?;
+    for line in source.lines() {
+        let mut parser = TOMLParser::new(line);
+        let path = parser.parse_key()?;
         println!("Parsed Key: {:?}", path);
     }
     Ok(())
@@ -113,17 +94,10 @@ fn parse_keys() -> TestReturn {
 
 #[test]
 fn arrays() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/arrays.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
-    while let Ok(pline) = parser.next_parserline() {
-        let (array, _) = parser.parse_array(pline)?;
+    let source = read_resource("test_resources/arrays.toml")?;
+    let mut parser = TOMLParser::new(&source);
+    while parser.seek_nonws()? {
+        let array = parser.parse_array()?;
         println!("Array: {:?}", array);
     }
     Ok(())
@@ -131,17 +105,10 @@ fn arrays() -> TestReturn {
 
 #[test]
 fn inline_tables() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/inline_tables.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
-    while let Ok(pline) = parser.next_parserline() {
-        let (table, _pline) = parser.parse_inline_table(pline)?;
+    let source = read_resource("test_resources/inline_tables.toml")?;
+    let mut parser = TOMLParser::new(&source);
+    while parser.seek_nonws()? {
+        let table = parser.parse_inline_table()?;
         println!("Parsed Inline Table: {:?}", table);
     }
     Ok(())
@@ -150,19 +117,12 @@ fn inline_tables() -> TestReturn {
 #[test]
 fn atomic_values() -> TestReturn {
     const NUM_VALUES: i32 = 33; // total number of values in test file.
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/values.toml");
+    let source = read_resource("test_resources/values.toml")?;
 
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
+    let mut parser = TOMLParser::new(&source);
     let mut successes = 0;
-    while let Ok(pline) = parser.next_parserline() {
-        parser.parse_value(pline)?;
+    while parser.seek_nonws()? {
+        parser.parse_value()?;
         successes += 1;
     }
     assert_eq!(successes, NUM_VALUES);
@@ -171,20 +131,12 @@ fn atomic_values() -> TestReturn {
 
 #[test]
 fn bools() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/bool.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
+    let source = read_resource("test_resources/bool.toml")?;
     let mut test = false;
     // Invariant: `test == !parsed_value`
-    while let Ok(pline) = parser.next_parserline() {
-        let (boolean, _pline) = TOMLParser::parse_bool(pline)?;
-        if let TOMLType::Bool(val) = boolean {
+    for (line_num, line) in source.lines().enumerate() {
+        let boolean = TOMLParser::parse_bool(line, line_num)?;
+        if let TOMLTypeRef::Bool(val) = boolean {
             assert_eq!(val, !test);
             test = val;
         }
@@ -194,37 +146,92 @@ fn bools() -> TestReturn {
 
 #[test]
 fn numerics() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/numerics.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
-
-    while let Ok(pline) = parser.next_parserline() {
-        TOMLParser::parse_numeric(pline)?;
+    let source = read_resource("test_resources/numerics.toml")?;
+    for (line_num, line) in source.lines().enumerate() {
+        if let Err(notes) = TOMLParser::parse_numeric(line, line_num) {
+            return Err(format!("`{}`: {:?}", line, notes));
+        }
     }
     Ok(())
 }
 
 #[test]
-fn datetime() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/dates.toml");
+fn numeric_notes() {
+    let mut parser = TOMLParser::new("a = 1__2.0\n");
+    let msg = parser.parse_toml().unwrap_err();
+    let err = parser.error(msg);
+    assert!(err
+        .message
+        .starts_with("Line 1: Could not parse numeric type."));
+    let topics: Vec<_> = err.notes.iter().map(|note| note.topic).collect();
+    assert_eq!(topics, ["integer", "float", "date"]);
+    // the reasons don't repeat the line number
+    assert!(err.notes[0]
+        .message
+        .starts_with("Integer Parsing Error: Underscore"));
+    assert!(err.to_string().contains("\n  float: "));
+    // errors turned into `String`s by `?` keep the notes
+    let err: String = crate::parse_str("a = 1__2.0\n").unwrap_err().into();
+    assert!(err.starts_with("Line 1: Could not parse numeric type."));
+    assert!(err.contains("\n  float: "));
+}
 
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
+#[test]
+fn integer_range() -> TestReturn {
+    let parse = |text: &str, wide: bool| TOMLParser::parse_integer_of_width(text, 1, wide);
+    // the extremes of i64 are both representable
+    for (text, expected) in [
+        ("-9223372036854775808", i64::MIN),
+        ("9223372036854775807", i64::MAX),
+        ("0x7fff_ffff_ffff_ffff", i64::MAX),
+    ] {
+        assert_eq!(parse(text, false)?.i64(), Some(expected), "{}", text);
+    }
+    for text in [
+        "9223372036854775808",
+        "-9223372036854775809",
+        "0xffff_ffff_ffff_ffff",
+    ] {
+        let err = parse(text, false).unwrap_err();
+        assert_eq!(
+            err,
+            format!(
+                "Line 1: Integer Parsing Error: `{}` is out of range (-9223372036854775808 to 9223372036854775807).",
+                text
+            )
+        );
+    }
+
+    // wide integers
+    assert!(matches!(parse("12", true)?, TOMLTypeRef::Int(12)));
+    let hash = parse("0xffff_ffff_ffff_ffff", true)?;
+    assert!(matches!(hash, TOMLTypeRef::WideInt(_)));
+    assert_eq!(hash.u64(), Some(u64::MAX));
+    let min = "-170141183460469231731687303715884105728";
+    assert_eq!(parse(min, true)?.i128(), Some(i128::MIN));
+    let err = parse("170141183460469231731687303715884105728", true).unwrap_err();
+    assert!(err.ends_with(&format!("({} to {}).", i128::MIN, i128::MAX)));
+    // digits beyond even u128 saturate rather than wrap
+    assert!(parse(&"9".repeat(60), true).is_err());
+
+    // the parser reports the range error rather than a generic one
+    let msg = TOMLParser::new("a = 9223372036854775808\n")
+        .parse_toml()
+        .unwrap_err();
+    assert!(msg.contains("out of range"), "{}", msg);
+    let table = TOMLParser::new("a = 9223372036854775808\n")
+        .with_wide_integers(true)
+        .parse_toml()?;
+    assert_eq!(table["a"].i128(), Some(i128::from(i64::MAX) + 1));
+    Ok(())
+}
+
+#[test]
+fn datetime() -> TestReturn {
+    let source = read_resource("test_resources/dates.toml")?;
     println!("\nDate Parsing:");
-    while let Ok(pline) = parser.next_parserline() {
-        let (date, _pline) = TOMLParser::parse_date(pline)?;
+    for (line_num, line) in source.lines().enumerate() {
+        let date = TOMLParser::parse_date(line, line_num)?;
         println!("{:?}", date);
     }
     Ok(())
@@ -236,6 +243,7 @@ fn floats() -> TestReturn {
     nan_test()?;
     invalid_format()
 }
+#[allow(clippy::approx_constant)]
 fn test_float() -> TestReturn {
     const FLOAT_STRS: [&str; 13] = [
         "224_627.445_991_228",
@@ -268,25 +276,21 @@ fn test_float() -> TestReturn {
         6.626e-34,
     ];
 
-    let mut i = 0;
-    for s in FLOAT_STRS {
-        let pline = ParserLine::new(s.to_string(), i);
-        let result = TOMLParser::parse_float(pline)?.0;
+    for (i, s) in FLOAT_STRS.iter().enumerate() {
+        let result = TOMLParser::parse_float(s, i)?;
         match result {
-            TOMLType::Float(val) => assert_eq!(FLOATS[i], val),
+            TOMLTypeRef::Float(val) => assert_eq!(FLOATS[i], val),
             _ => return Err(String::from("Will never reach here.")),
         }
-        i += 1;
     }
     Ok(())
 }
 fn nan_test() -> TestReturn {
     const NAN_STRS: [&str; 3] = ["nan", "+nan", "-nan"];
     for s in NAN_STRS {
-        let pline = ParserLine::new(s.to_string(), 0);
-        let result = TOMLParser::parse_float(pline)?.0;
+        let result = TOMLParser::parse_float(s, 0)?;
         match result {
-            TOMLType::Float(val) => assert_eq!(true, val.is_nan()),
+            TOMLTypeRef::Float(val) => assert!(val.is_nan()),
             _ => return Err(String::from("Will never reach here.")),
         }
     }
@@ -295,8 +299,7 @@ fn nan_test() -> TestReturn {
 fn invalid_format() -> TestReturn {
     const BAD_F64: [&str; 3] = [".12", "3.e+20", "12."];
     for s in BAD_F64 {
-        let pline = ParserLine::new(s.to_string(), 0);
-        let result = TOMLParser::parse_float(pline);
+        let result = TOMLParser::parse_float(s, 0);
         match result {
             Err(_msg) => {
                 // println!("{_msg}");
@@ -326,10 +329,9 @@ fn hundred_twenty_three() -> TestReturn {
         "0b111_1011",
     ];
     for form in &HUNDRED_TWENTY_THREES {
-        let pline = ParserLine::new(form.to_string(), 0);
-        let parsed_val = TOMLParser::parse_integer(pline)?.0;
+        let parsed_val = TOMLParser::parse_integer(form, 0)?;
         match parsed_val {
-            TOMLType::Int(val) => assert_eq!(123, val),
+            TOMLTypeRef::Int(val) => assert_eq!(123, val),
             _ => return Err("Should never happen.".to_string()),
         }
     }
@@ -340,157 +342,415 @@ fn negatives() -> TestReturn {
     const NEGATIVES: [&str; SIZE] = ["-0", "-123", "-27", "-3567", "-562"];
     const NEGATIVE_INTS: [i64; SIZE] = [0, -123, -27, -3567, -562];
 
-    let mut i = 0;
-    for s in &NEGATIVES {
-        let pline = ParserLine::new(s.to_string(), i);
-        let parsed_val = TOMLParser::parse_integer(pline)?.0;
+    for (i, s) in NEGATIVES.iter().enumerate() {
+        let parsed_val = TOMLParser::parse_integer(s, i)?;
         match parsed_val {
-            TOMLType::Int(val) => assert_eq!(NEGATIVE_INTS[i], val),
+            TOMLTypeRef::Int(val) => assert_eq!(NEGATIVE_INTS[i], val),
             _ => return Err("Should never happen.".to_string()),
         }
-        i += 1;
     }
     Ok(())
 }
 
 #[test]
 fn strings() -> TestReturn {
-    use super::{LITERAL_STR_TOKEN, STR_TOKEN};
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/strings.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
-    let mut pline = parser.next_parserline()?;
-
-    // Basic Strings
-    println!("\nBasic Strings");
-    while let Some(&STR_TOKEN) = pline.peek().unwrap().peek() {
-        print!("\nLine {}: ", pline.line_num());
-        let (outstring, context) = parser.parse_string(pline)?;
-        if let TOMLType::BasicStr(str) = outstring {
-            let outstring = str;
-            println!("Basic String\n{}", outstring);
-        } else {
-            return Err(format!("Line {}", context.line_num()));
+    let source = read_resource("test_resources/strings.toml")?;
+    let mut parser = TOMLParser::new(&source);
+
+    // The file groups the string kinds in blocks, in this order.
+    const EXPECTED: [(&str, usize); 4] = [
+        ("Basic Strings", 1),
+        ("Multi-line Strings", 2),
+        ("Literal Strings", 4),
+        ("Multi-line Literal Strings", 4),
+    ];
+    for (i, (label, count)) in EXPECTED.iter().enumerate() {
+        println!("\n{}", label);
+        for _ in 0..*count {
+            assert!(parser.seek_nonws()?);
+            print!("Line {}: ", parser.line_num());
+            let outstring = parser.parse_value()?;
+            match (i, &outstring) {
+                (0, TOMLTypeRef::BasicStr(s))
+                | (1, TOMLTypeRef::MultiStr(s))
+                | (2, TOMLTypeRef::LitStr(s))
+                | (3, TOMLTypeRef::MultiLitStr(s)) => println!("{}", s),
+                _ => return Err(format!("Line {}: {:?}", parser.line_num(), outstring)),
+            }
         }
-        pline = parser.next_parserline()?;
     }
+    assert!(!parser.seek_nonws()?);
 
-    // Multi-Strings
-    println!("\nMulti-line Strings");
-    pline = parser.next_parserline()?;
-    while let Some(&STR_TOKEN) = pline.peek().unwrap().peek() {
-        print!("Line {}: ", pline.line_num());
-        let (outstring, context) = parser.parse_string(pline)?;
-        if let TOMLType::MultiStr(str) = outstring {
-            let outstring = str;
-            println!("{}", outstring);
-        } else {
-            return Err(format!("Line {}", context.line_num()));
-        }
-        pline = parser.next_parserline()?;
+    Ok(())
+}
+
+#[test]
+fn escape_sequences() -> TestReturn {
+    let escape = |s: &str| TOMLParser::parse_multi_escape_sequence(&mut s.chars().peekable(), 0);
+
+    let eof = "";
+    let fire = "u0001f525";
+    let next_nonws = "    \n\n\nt";
+
+    assert_eq!(Some('🔥'), escape(fire)?);
+    // A line-ending backslash produces no character; the next one is the first non-whitespace.
+    let mut iter = next_nonws.chars().peekable();
+    assert_eq!(None, TOMLParser::parse_multi_escape_sequence(&mut iter, 0)?);
+    assert_eq!(Some('t'), iter.next());
+    assert!(escape(eof).is_err());
+    assert!(escape(" x").is_err());
+    Ok(())
+}
+
+#[test]
+fn invalid_documents() {
+    const INVALID: [&str; 12] = [
+        "a = 1 2",
+        "a = [1 2]",
+        "a = [,]",
+        "a = { b = 1, }",
+        "a = { b = 1\n}",
+        "a = { b = 1 c = 2 }",
+        "a = 1\na = 2",
+        "[a]\n[a]",
+        "[[a] ]",
+        "a = \"\"\"unterminated",
+        "a = 0x",
+        "a = 1__0",
+    ];
+    for doc in INVALID {
+        let result = TOMLParser::new(doc).parse_toml();
+        assert!(result.is_err(), "accepted invalid document {:?}", doc);
     }
+    // datetime errors give the rule that was broken
+    let msg = TOMLParser::new("\nd = 1979-02-30")
+        .parse_toml()
+        .unwrap_err();
+    assert_eq!(
+        msg,
+        "Line 2: Invalid datetime `1979-02-30`: the day does not exist in that month."
+    );
+    let table = TOMLParser::new("d = 1979-05-27 07:32:00z\n")
+        .parse_toml()
+        .unwrap();
+    assert_eq!(
+        table["d"].datetime().map(|dt| dt.to_string()).as_deref(),
+        Some("1979-05-27T07:32:00Z")
+    );
+}
 
-    // Literal Strings
-    println!("\nLiteral Strings");
-    pline = parser.next_parserline()?;
-    while let Some(&LITERAL_STR_TOKEN) = pline.peek().unwrap().peek() {
-        print!("Line {}: ", pline.line_num());
-        let (outstring, context) = parser.parse_literal_string(pline)?;
-        if let TOMLType::LitStr(str) = outstring {
-            let outstring = str;
-            println!("{}", outstring);
-        } else {
-            return Err(format!("Line {}", context.line_num()));
-        }
-        pline = parser.next_parserline()?;
+#[test]
+fn spans() -> TestReturn {
+    use crate::parsedtoml::ParsedTOML;
+
+    let source = "title = 'x'\n\n[server]\n  timeout = -5\nlimits.max = [1, { \"é\" = 2 }]\n\n[[bin]]\n[[bin]]\nname = \"b\"\n";
+    let mut parser = TOMLParser::new(source).with_spans(Some("config.toml"));
+    let table = parser.parse_toml()?;
+    let parsed = ParsedTOML::with_spans(table, parser.take_spans());
+
+    let span = parsed.span_of("server.timeout").unwrap();
+    assert_eq!(span.to_string(), "config.toml:4:13");
+    assert_eq!(&source[span.bytes.clone()], "-5");
+    let key = parsed.key_span_of("server . \"timeout\"").unwrap();
+    assert_eq!((key.line, key.column), (4, 3));
+
+    let expected = [
+        ("title", "'x'"),
+        ("server", "[server]"),
+        ("server.limits", "limits"),
+        ("server.limits.max", "[1, { \"é\" = 2 }]"),
+        ("server.limits.max.1", "{ \"é\" = 2 }"),
+        ("server.limits.max.1.\"é\"", "2"),
+        ("bin.1", "[[bin]]"),
+        ("bin.1.name", "\"b\""),
+    ];
+    for (path, text) in expected {
+        let span = parsed
+            .span_of(path)
+            .ok_or(format!("No span for {}", path))?;
+        assert_eq!(&source[span.bytes.clone()], text);
     }
+    assert_eq!(parsed.span_of("bin.1").unwrap().line, 8);
+    assert_eq!(
+        parsed.span_of("server.limits.max.1.\"é\"").unwrap().column,
+        26
+    );
+    assert!(parsed.key_span_of("server.limits.max.0").is_none());
+    assert!(parsed.span_of("server.missing").is_none());
+    assert!(parsed.span_of("server timeout").is_none());
+    Ok(())
+}
 
-    // Multi-Literal Strings
-    println!("\nMulti-line Literal Strings");
-    pline = parser.next_parserline()?;
-    while let Some(&LITERAL_STR_TOKEN) = pline.peek().unwrap().peek() {
-        print!("Line {}: ", pline.line_num());
-        let (outstring, context) = parser.parse_literal_string(pline)?;
-        if let TOMLType::MultiLitStr(str) = outstring {
-            let outstring = str;
-            println!("{}", outstring);
-        } else {
-            return Err(format!("Line {}", context.line_num()));
-        }
-        pline = parser.next_parserline()?;
+#[test]
+fn key_order() -> Result<(), crate::ParseError> {
+    use crate::ParseOptions;
+
+    let source = "zeta = 1\nalpha.y = 2\nalpha.b = 3\n[mid.inner]\nz = 4\na = 5\n[[list]]\nq = 6\np = [{ d = 7, c = 8 }]\n[early]\n";
+    let options = ParseOptions::new().preserve_order(true);
+    let parsed = options.parse_str(source)?;
+    let expected: [(&str, &[&str]); 6] = [
+        ("", &["zeta", "alpha", "mid", "list", "early"]),
+        ("alpha", &["y", "b"]),
+        ("mid.inner", &["z", "a"]),
+        ("list.0", &["q", "p"]),
+        ("list.0.p.0", &["d", "c"]),
+        ("early", &[]),
+    ];
+    for (path, keys) in expected {
+        assert_eq!(parsed.keys(path).as_deref(), Some(keys), "{}", path);
     }
+    assert!(parsed.keys("zeta").is_none());
+    assert!(parsed.keys("list.1").is_none());
+    assert!(parsed.span_of("zeta").is_some());
 
+    let tree = parsed.to_string();
+    let position = |key: &str| tree.find(key).unwrap();
+    assert!(position("zeta") < position("alpha") && position("alpha") < position("mid"));
+
+    let unordered = ParseOptions::new().parse_str(source)?;
+    assert_eq!(unordered.keys("mid.inner").map(|keys| keys.len()), Some(2));
+    assert!(unordered.span_of("zeta").is_none());
     Ok(())
 }
 
 #[test]
-fn escape_sequences() -> TestReturn {
-    let source_dir = match Path::new(file!()).canonicalize() {
-        Ok(s) => s,
-        _ => return Err(String::from("File error.")),
-    };
-    let file = source_dir
-        .parent()
-        .unwrap()
-        .join("test_resources/blank.toml");
-    let mut parser = TOMLParser::init(file.to_str().unwrap())?;
+fn toml_extension() {
+    use crate::{ErrorKind, ParseOptions};
+
+    let readme = concat!(env!("CARGO_MANIFEST_DIR"), "/README.md");
+    let err = ParseOptions::new().parse(readme).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Io);
+    assert_eq!(err.message, "Incorrect file extension.");
+    let err = ParseOptions::new()
+        .require_toml_extension(false)
+        .parse(readme)
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Syntax);
+}
 
-    let eof = "\n".to_string();
-    let fire = "u0001f525".to_string();
-    let next_nonws = "    \n\n\nt".to_string();
+#[test]
+fn lenient() -> TestReturn {
+    use crate::error::WarningKind::{self, *};
+
+    let cases: [(&str, &[WarningKind]); 5] = [
+        ("a = { b = 1, }", &[InlineTableTrailingComma]),
+        (
+            "a = { b = 1,\n  c = 2 # two\n}",
+            &[InlineTableNewline, InlineTableNewline],
+        ),
+        ("a = [True, FALSE]", &[BooleanCase, BooleanCase]),
+        ("a = {\n}\nb = true", &[InlineTableNewline]),
+        (
+            "a = { b = { c = tRUE, }, }",
+            &[
+                BooleanCase,
+                InlineTableTrailingComma,
+                InlineTableTrailingComma,
+            ],
+        ),
+    ];
+    for (source, expected) in cases {
+        assert!(TOMLParser::new(source).parse_toml().is_err(), "{}", source);
+        let mut parser = TOMLParser::new(source).with_lenient(true);
+        parser.parse_toml()?;
+        let kinds: Vec<_> = parser.take_warnings().iter().map(|w| w.kind).collect();
+        assert_eq!(kinds, expected, "{}", source);
+    }
 
+    let mut parser = TOMLParser::new("x = 1\na = { b = 1,\n\n c = True }").with_lenient(true);
+    let table = parser.parse_toml()?;
+    assert!(matches!(
+        table.get("a"),
+        Some(TOMLTypeRef::InlineTable(inner)) if inner.len() == 2
+    ));
+    let warnings = parser.take_warnings();
     assert_eq!(
-        '🔥',
-        parser
-            .parse_multi_escape_sequence(ParserLine::new(fire, 0))?
-            .0
+        warnings[0].to_string(),
+        "Line 2: Newline within an inline table."
     );
+    assert_eq!(warnings[1].line, 4);
+
+    for source in [
+        "a = { b = 1,, }",
+        "a = { , }",
+        "a = { b = \n 1 }",
+        "a = yes",
+        "a = {",
+    ] {
+        let mut parser = TOMLParser::new(source).with_lenient(true);
+        assert!(parser.parse_toml().is_err(), "{}", source);
+    }
+    let err = TOMLParser::new("a = True").parse_toml().unwrap_err();
     assert_eq!(
-        't',
-        parser
-            .parse_multi_escape_sequence(ParserLine::new(next_nonws, 0))?
-            .0
+        err,
+        "Line 1: Boolean Parsing Error: `True` must be lowercase."
     );
-    assert_eq!(
-        true,
+    Ok(())
+}
+
+#[test]
+fn literals() -> TestReturn {
+    use crate::lexer::TokenKind;
+    use crate::parsedtoml::ParsedTOML;
+    use crate::span::Radix;
+
+    let source = "mode = 0o755\nmask = 0xDEAD_BEEF\ncount = 1_000\nh = 6.626e-34\nt = 07:32:00.999999999\ns = '''x'''\n";
+    let mut parser = TOMLParser::new(source).with_spans(None);
+    let table = parser.parse_toml()?;
+    let parsed = ParsedTOML::with_spans(table, parser.take_spans());
+
+    let expected = [
+        ("mode", "0o755", TokenKind::Integer, Some(Radix::Octal)),
+        (
+            "mask",
+            "0xDEAD_BEEF",
+            TokenKind::Integer,
+            Some(Radix::Hexadecimal),
+        ),
+        ("count", "1_000", TokenKind::Integer, Some(Radix::Decimal)),
+        ("h", "6.626e-34", TokenKind::Float, None),
+        ("t", "07:32:00.999999999", TokenKind::Datetime, None),
+        ("s", "'''x'''", TokenKind::MultiLitStr, None),
+    ];
+    for (path, raw, kind, radix) in expected {
+        let literal = parsed.literal_of(path).unwrap();
+        assert_eq!((literal.raw.as_str(), literal.kind), (raw, kind));
+        assert_eq!(literal.radix(), radix);
+    }
+    Ok(())
+}
+
+#[test]
+fn borrowed_strings() -> TestReturn {
+    use std::borrow::Cow;
+
+    let source = "plain = \"abc\"\nescaped = \"a\\tb\"\n\"quoted\" = '''\nraw\\n'''\n\"k\\u00e9y\" = \"\"\"\nx\"\"\"\n";
+    let table = TOMLParser::new(source).parse_toml()?;
+    let expected = [
+        ("plain", "abc", true),
+        ("escaped", "a\tb", false),
+        ("quoted", "raw\\n", true),
+        ("k\u{e9}y", "x", true),
+    ];
+    for (key, text, borrowed) in expected {
+        let (stored_key, val) = table.get_key_value(key).unwrap();
+        let s = match val {
+            TOMLTypeRef::BasicStr(s)
+            | TOMLTypeRef::MultiStr(s)
+            | TOMLTypeRef::LitStr(s)
+            | TOMLTypeRef::MultiLitStr(s) => s,
+            _ => return Err(format!("`{}` is not a string", key)),
+        };
+        assert_eq!(s, text);
+        assert_eq!(matches!(s, Cow::Borrowed(_)), borrowed, "{}", key);
+        // only keys with escapes are copied
+        assert_eq!(
+            matches!(stored_key, Cow::Borrowed(_)),
+            key.is_ascii(),
+            "{}",
+            key
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn limits() {
+    use crate::{ErrorKind, ParseLimits};
+
+    let limited = |source: &str, limits: ParseLimits| {
+        let mut parser = TOMLParser::new(source).with_limits(limits);
         parser
-            .parse_multi_escape_sequence(ParserLine::new(eof, 0))
-            .is_err()
-    );
+            .parse_toml()
+            .map(|_| ())
+            .map_err(|msg| parser.error(msg))
+    };
+    type SetLimit = fn(&mut ParseLimits);
+    const NONE: ParseLimits = ParseLimits::none();
+    let exceeding: [(&str, SetLimit); 7] = [
+        ("a = 1\n", |lim| lim.max_input_bytes = 5),
+        ("a.b.c = 1\n", |lim| lim.max_depth = 2),
+        ("[a.b]\nc = [1]\n", |lim| lim.max_depth = 3),
+        ("a = [1, 2, 3]\n", |lim| lim.max_array_len = 2),
+        ("[[a]]\n[[a]]\n[[a]]\n", |lim| lim.max_array_len = 2),
+        ("a = 1\nb.c = 2\n[d]\n", |lim| lim.max_table_keys = 2),
+        ("a = 'four'\n", |lim| lim.max_string_len = 5),
+    ];
+    for (doc, set_limit) in exceeding {
+        let mut limits = NONE;
+        set_limit(&mut limits);
+        let err = limited(doc, limits).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::LimitExceeded, "{:?}: {}", doc, err);
+        // each document is just within the limit when it is raised by one
+        assert!(limited(doc, raise(limits)).is_ok(), "{:?}", doc);
+    }
+    // other errors are syntax errors
+    let err = limited("a = 1\na = 2\n", NONE).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Syntax);
+
+    // deep nesting is rejected by default instead of overflowing the stack
+    let deep = format!("a = {}{}\n", "[".repeat(100_000), "]".repeat(100_000));
+    let err = limited(&deep, ParseLimits::default()).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::LimitExceeded);
+    assert!(limited("a = [[[{ b = [1] }]]]\n", ParseLimits::default()).is_ok());
+}
+
+/// Inputs that once crashed (or were written to probe for crashes) must produce a result.
+/// The same files seed the fuzz targets in `fuzz/`.
+#[test]
+fn fuzz_regressions() -> TestReturn {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/regressions");
+    let entries = std::fs::read_dir(dir).map_err(|err| err.to_string())?;
+    let mut count = 0;
+    for entry in entries {
+        let path = entry.map_err(|err| err.to_string())?.path();
+        let data = std::fs::read(&path).map_err(|err| err.to_string())?;
+        if let Ok(source) = std::str::from_utf8(&data) {
+            if let Ok(parsed) = crate::parse_str(source) {
+                let _ = parsed.to_string();
+            }
+            crate::lexer::tokenize(source).for_each(drop);
+        }
+        crate::events(data.as_slice()).for_each(drop);
+        count += 1;
+    }
+    assert!(count > 0);
     Ok(())
 }
 
+/// Raises every finite limit by one.
+fn raise(limits: crate::ParseLimits) -> crate::ParseLimits {
+    let up = |max: usize| max.saturating_add(1);
+    crate::ParseLimits {
+        max_input_bytes: up(limits.max_input_bytes),
+        max_depth: up(limits.max_depth),
+        max_array_len: up(limits.max_array_len),
+        max_table_keys: up(limits.max_table_keys),
+        max_string_len: up(limits.max_string_len),
+    }
+}
+
 /// Prints all invalid chars for TOML strings.
 /// Needed for instantiating a const array.
 /// Placing it here as I don't know where else to put it.
 /// Uncomment the test directive and run it with `--show-output`.
 //#[test]
+#[allow(dead_code)]
 fn invalid_strs() {
-    print_invalid_str_graphemes()
+    print_invalid_str_chars()
 }
 
-fn get_graphemes(s: &str) -> Vec<&str> {
-    unicode_segmentation::UnicodeSegmentation::graphemes(s, true).collect::<Vec<_>>()
-}
-/// Use this to print an array of invalid graphemes. Make the resulting array via Copy/Paste.
-pub fn print_invalid_str_graphemes() {
-    println!("Invalid TOML str grapheme Report");
-    let inval_string = get_invalid_str_graphemes();
-    let invalids = get_graphemes(inval_string.as_str());
+/// Use this to print an array of invalid chars. Make the resulting array via Copy/Paste.
+pub fn print_invalid_str_chars() {
+    println!("Invalid TOML str char Report");
+    let invalids = get_invalid_str_chars();
     println!("Total Num of Invalids: {}", invalids.len());
-    println!("Invalid str graphemes:\n{:?}\n", invalids);
+    println!("Invalid str chars:\n{:?}\n", invalids);
 }
 
-fn get_invalid_str_graphemes() -> String {
+fn get_invalid_str_chars() -> Vec<char> {
     let range1 = 0_u8..=8_u8;
     let range2 = u8::from_str_radix("A", 16).unwrap()..=u8::from_str_radix("1F", 16).unwrap();
     let range3 = u8::from_str_radix("7F", 16).unwrap()..u8::from_str_radix("80", 16).unwrap();
-    let graphemes = range1.chain(range2.chain(range3)).collect::<Vec<u8>>();
-    String::from_utf8(graphemes).unwrap()
+    range1.chain(range2.chain(range3)).map(char::from).collect()
 }
@@ -1,9 +1,14 @@
 #![allow(dead_code)]
 
 // Imports
-use chrono::{offset::FixedOffset, DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
+use super::datetime::Datetime;
+
+#[cfg(feature = "serde")]
+mod with_serde;
+
 // Implementation
 
 /// Retrieve a view into the value of a given key-value pair
@@ -11,7 +16,7 @@ use std::collections::HashMap;
 ///
 /// - `key_sequence`: the (potentially multi-part) key
 /// - `delimiter`: the substring suded to separate key parts (equivalent to the `.` in a
-///              dotted-key)
+///   dotted-key)
 ///
 /// Arrays and Arrays of Tables cannot be queried into because they require an index.
 /// It is better to retrieve the entire array structure and subsequently index them.
@@ -24,7 +29,7 @@ use std::collections::HashMap;
 ///  [my_table]
 ///  key.is_dotted.example = true
 /// ```
-/// ```
+/// ```no_run
 /// use tomlp::{parse, ValFromTOMLKey, TOMLType};
 /// let parsed = parse("sample.toml")?;
 /// let test = parsed.retrieve("my_table\0key\0is_dotted\0example", "\0");
@@ -33,6 +38,7 @@ use std::collections::HashMap;
 /// } else {
 ///     panic!("This won't happen.");
 /// }
+/// # Ok::<(), String>(())
 /// ```
 ///
 pub trait ValFromTOMLKey {
@@ -42,11 +48,13 @@ pub trait ValFromTOMLKey {
 /// Alias for the table type.
 pub type TOMLTable = HashMap<String, TOMLType>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 /// The Rust representation of TOML value types.
 pub enum TOMLType {
     Bool(bool),
     Int(i64),
+    /// Integer outside the range of `i64`, only produced when parsing with wide integers
+    WideInt(i128),
     Float(f64),
     // Strings
     /// Basic String
@@ -57,11 +65,8 @@ pub enum TOMLType {
     LitStr(String),
     /// Multi-line literal string
     MultiLitStr(String),
-    // Dates
-    Date(NaiveDate),
-    Time(NaiveTime),
-    NaiveDateTime(NaiveDateTime),
-    TimeStamp(DateTime<FixedOffset>),
+    /// Offset date-time, local date-time, local date, or local time
+    Datetime(Datetime),
     // Collections
     Array(Vec<Self>),
     /// Table defined via table header syntax `[table]`
@@ -92,6 +97,20 @@ impl TOMLType {
         }
     }
 
+    /// Gets an integer of any width
+    pub fn i128(&self) -> Option<i128> {
+        match *self {
+            Self::Int(n) => Some(i128::from(n)),
+            Self::WideInt(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Gets a non-negative integer that fits in a `u64` (ex. a hash or an ID)
+    pub fn u64(&self) -> Option<u64> {
+        self.i128().and_then(|n| u64::try_from(n).ok())
+    }
+
     pub fn f64(&self) -> Option<f64> {
         if let Self::Float(n) = *self {
             Some(n)
@@ -100,6 +119,14 @@ impl TOMLType {
         }
     }
 
+    pub fn datetime(&self) -> Option<&Datetime> {
+        if let Self::Datetime(dt) = self {
+            Some(dt)
+        } else {
+            None
+        }
+    }
+
     pub fn array(&self) -> Option<&Vec<Self>> {
         if let Self::Array(arr) = self {
             Some(arr)
@@ -122,7 +149,7 @@ impl ValFromTOMLKey for TOMLTable {
             // traverse tables
             key = key_iter.next().unwrap(); // even the empty string results in at least one
                                             // iteration.
-            if let None = key_iter.peek() {
+            if key_iter.peek().is_none() {
                 break;
             }
 
@@ -147,3 +174,145 @@ impl ValFromTOMLKey for TOMLTable {
         }
     }
 }
+
+/// Alias for the table type of [`TOMLTypeRef`].
+pub type TOMLTableRef<'src> = HashMap<Cow<'src, str>, TOMLTypeRef<'src>>;
+
+#[derive(Debug)]
+/// [`TOMLType`] with keys and strings borrowed from the source whenever no escape processing
+/// was needed.
+pub enum TOMLTypeRef<'src> {
+    Bool(bool),
+    Int(i64),
+    /// Integer outside the range of `i64`, only produced when parsing with wide integers
+    WideInt(i128),
+    Float(f64),
+    // Strings
+    /// Basic String
+    BasicStr(Cow<'src, str>),
+    /// Multi-line string
+    MultiStr(Cow<'src, str>),
+    /// Basic literal string
+    LitStr(Cow<'src, str>),
+    /// Multi-line literal string
+    MultiLitStr(Cow<'src, str>),
+    /// Offset date-time, local date-time, local date, or local time
+    Datetime(Datetime),
+    // Collections
+    Array(Vec<Self>),
+    /// Table defined via table header syntax `[table]`
+    HTable(TOMLTableRef<'src>),
+    /// Table defined via dotted key (ex. `apple.color = "red"`)
+    DKTable(TOMLTableRef<'src>),
+    InlineTable(TOMLTableRef<'src>),
+    /// Array of Tables
+    AoT(Vec<TOMLTableRef<'src>>),
+}
+impl<'src> TOMLTypeRef<'src> {
+    /// Gets a reference to the underlying string
+    pub fn str(&self) -> Option<&str> {
+        match self {
+            Self::BasicStr(s) | Self::MultiStr(s) | Self::LitStr(s) | Self::MultiLitStr(s) => {
+                Some(s)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn i64(&self) -> Option<i64> {
+        if let Self::Int(n) = *self {
+            Some(n)
+        } else {
+            None
+        }
+    }
+
+    /// Gets an integer of any width
+    pub fn i128(&self) -> Option<i128> {
+        match *self {
+            Self::Int(n) => Some(i128::from(n)),
+            Self::WideInt(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Gets a non-negative integer that fits in a `u64` (ex. a hash or an ID)
+    pub fn u64(&self) -> Option<u64> {
+        self.i128().and_then(|n| u64::try_from(n).ok())
+    }
+
+    pub fn f64(&self) -> Option<f64> {
+        if let Self::Float(n) = *self {
+            Some(n)
+        } else {
+            None
+        }
+    }
+
+    pub fn datetime(&self) -> Option<&Datetime> {
+        if let Self::Datetime(dt) = self {
+            Some(dt)
+        } else {
+            None
+        }
+    }
+
+    pub fn array(&self) -> Option<&Vec<Self>> {
+        if let Self::Array(arr) = self {
+            Some(arr)
+        } else {
+            None
+        }
+    }
+
+    /// Copies any borrowed strings, detaching the value from the source.
+    pub fn into_owned(self) -> TOMLType {
+        match self {
+            Self::Bool(b) => TOMLType::Bool(b),
+            Self::Int(n) => TOMLType::Int(n),
+            Self::WideInt(n) => TOMLType::WideInt(n),
+            Self::Float(n) => TOMLType::Float(n),
+            Self::BasicStr(s) => TOMLType::BasicStr(s.into_owned()),
+            Self::MultiStr(s) => TOMLType::MultiStr(s.into_owned()),
+            Self::LitStr(s) => TOMLType::LitStr(s.into_owned()),
+            Self::MultiLitStr(s) => TOMLType::MultiLitStr(s.into_owned()),
+            Self::Datetime(dt) => TOMLType::Datetime(dt),
+            Self::Array(arr) => TOMLType::Array(arr.into_iter().map(Self::into_owned).collect()),
+            Self::HTable(table) => TOMLType::HTable(table_into_owned(table)),
+            Self::DKTable(table) => TOMLType::DKTable(table_into_owned(table)),
+            Self::InlineTable(table) => TOMLType::InlineTable(table_into_owned(table)),
+            Self::AoT(aot) => TOMLType::AoT(aot.into_iter().map(table_into_owned).collect()),
+        }
+    }
+}
+
+/// Copies any borrowed keys and strings of the table.
+pub(crate) fn table_into_owned(table: TOMLTableRef<'_>) -> TOMLTable {
+    table
+        .into_iter()
+        .map(|(key, val)| (key.into_owned(), val.into_owned()))
+        .collect()
+}
+
+/// Retrieves a value from a borrowed table. See [`ValFromTOMLKey`].
+pub(crate) fn retrieve_ref<'t, 'src>(
+    table: &'t TOMLTableRef<'src>,
+    key_sequence: &str,
+    delimiter: &str,
+) -> Option<&'t TOMLTypeRef<'src>> {
+    let mut curr_table = table;
+    let mut key_iter = key_sequence.split(delimiter).peekable();
+    while let Some(key) = key_iter.next() {
+        let val = curr_table.get(key)?;
+        if key_iter.peek().is_none() {
+            return Some(val);
+        }
+        match val {
+            TOMLTypeRef::HTable(table)
+            | TOMLTypeRef::DKTable(table)
+            | TOMLTypeRef::InlineTable(table) => curr_table = table,
+            _ => return None,
+        }
+    }
+    None
+}
@@ -0,0 +1,197 @@
+//! `serde` support for [`TOMLType`], so that values of any type can be kept in structs (ex. the
+//! ones written by `tomlp codegen` for mixed-type arrays).
+//!
+//! Strings are deserialized as basic strings, tables as inline tables, and integers outside the
+//! range of `i64` as wide integers. Datetimes are serialized as their TOML strings.
+
+// stdlib imports
+use std::fmt;
+// third-party imports
+use serde::de::{Error, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+// my imports
+use super::{TOMLTable, TOMLType};
+
+/// Serializes a table with its keys in order, so the output doesn't depend on hashing.
+fn serialize_table<S: Serializer>(table: &TOMLTable, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut entries: Vec<_> = table.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    let mut map = serializer.serialize_map(Some(entries.len()))?;
+    for (key, val) in entries {
+        map.serialize_entry(key, val)?;
+    }
+    map.end()
+}
+
+/// A table of an array of tables.
+struct Table<'a>(&'a TOMLTable);
+
+impl Serialize for Table<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_table(self.0, serializer)
+    }
+}
+
+impl Serialize for TOMLType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Int(n) => serializer.serialize_i64(*n),
+            Self::WideInt(n) => serializer.serialize_i128(*n),
+            Self::Float(n) => serializer.serialize_f64(*n),
+            Self::BasicStr(s) | Self::MultiStr(s) | Self::LitStr(s) | Self::MultiLitStr(s) => {
+                serializer.serialize_str(s)
+            }
+            Self::Datetime(dt) => dt.serialize(serializer),
+            Self::Array(arr) => serializer.collect_seq(arr),
+            Self::HTable(table) | Self::DKTable(table) | Self::InlineTable(table) => {
+                serialize_table(table, serializer)
+            }
+            Self::AoT(tables) => {
+                let mut seq = serializer.serialize_seq(Some(tables.len()))?;
+                for table in tables {
+                    seq.serialize_element(&Table(table))?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+struct TOMLTypeVisitor;
+
+impl<'de> Visitor<'de> for TOMLTypeVisitor {
+    type Value = TOMLType;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a TOML value")
+    }
+
+    fn visit_bool<E: Error>(self, b: bool) -> Result<Self::Value, E> {
+        Ok(TOMLType::Bool(b))
+    }
+
+    fn visit_i64<E: Error>(self, n: i64) -> Result<Self::Value, E> {
+        Ok(TOMLType::Int(n))
+    }
+
+    fn visit_i128<E: Error>(self, n: i128) -> Result<Self::Value, E> {
+        Ok(match i64::try_from(n) {
+            Ok(n) => TOMLType::Int(n),
+            Err(_) => TOMLType::WideInt(n),
+        })
+    }
+
+    fn visit_u64<E: Error>(self, n: u64) -> Result<Self::Value, E> {
+        self.visit_i128(n.into())
+    }
+
+    fn visit_u128<E: Error>(self, n: u128) -> Result<Self::Value, E> {
+        let n = i128::try_from(n)
+            .map_err(|_| E::custom(format!("`{}` is too large for a TOML integer.", n)))?;
+        self.visit_i128(n)
+    }
+
+    fn visit_f64<E: Error>(self, n: f64) -> Result<Self::Value, E> {
+        Ok(TOMLType::Float(n))
+    }
+
+    fn visit_str<E: Error>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(TOMLType::BasicStr(s.to_string()))
+    }
+
+    fn visit_string<E: Error>(self, s: String) -> Result<Self::Value, E> {
+        Ok(TOMLType::BasicStr(s))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        TOMLType::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut arr = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(val) = seq.next_element()? {
+            arr.push(val);
+        }
+        Ok(TOMLType::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut table = TOMLTable::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, val)) = map.next_entry()? {
+            table.insert(key, val);
+        }
+        Ok(TOMLType::InlineTable(table))
+    }
+}
+
+impl<'de> Deserialize<'de> for TOMLType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TOMLTypeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TOMLTable, TOMLType};
+    use serde_test::{assert_de_tokens, assert_ser_tokens, assert_tokens, Token};
+
+    #[test]
+    fn round_trip() {
+        assert_tokens(&TOMLType::Bool(true), &[Token::Bool(true)]);
+        assert_tokens(&TOMLType::Int(-1), &[Token::I64(-1)]);
+        assert_tokens(&TOMLType::Float(0.5), &[Token::F64(0.5)]);
+        assert_tokens(&TOMLType::BasicStr("a".to_string()), &[Token::Str("a")]);
+        let arr = TOMLType::Array(vec![TOMLType::Int(1), TOMLType::BasicStr("a".to_string())]);
+        assert_tokens(
+            &arr,
+            &[
+                Token::Seq { len: Some(2) },
+                Token::I64(1),
+                Token::Str("a"),
+                Token::SeqEnd,
+            ],
+        );
+        let table = TOMLTable::from([
+            ("b".to_string(), TOMLType::Int(2)),
+            ("a".to_string(), TOMLType::Int(1)),
+        ]);
+        assert_tokens(
+            &TOMLType::InlineTable(table),
+            &[
+                Token::Map { len: Some(2) },
+                Token::Str("a"),
+                Token::I64(1),
+                Token::Str("b"),
+                Token::I64(2),
+                Token::MapEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn conversions() -> Result<(), String> {
+        // integers are narrowed to `Int` where they fit
+        assert_de_tokens(&TOMLType::Int(1), &[Token::U64(1)]);
+        let wide = TOMLType::WideInt(u64::MAX.into());
+        assert_de_tokens(&wide, &[Token::U64(u64::MAX)]);
+        assert_de_tokens(&TOMLType::Int(1), &[Token::Some, Token::I64(1)]);
+
+        let lit = TOMLType::LitStr("a".to_string());
+        assert_ser_tokens(&lit, &[Token::Str("a")]);
+        let dt = TOMLType::Datetime("1979-05-27".parse()?);
+        assert_ser_tokens(&dt, &[Token::Str("1979-05-27")]);
+        let aot = TOMLType::AoT(vec![TOMLTable::new()]);
+        assert_ser_tokens(
+            &aot,
+            &[
+                Token::Seq { len: Some(1) },
+                Token::Map { len: Some(0) },
+                Token::MapEnd,
+                Token::SeqEnd,
+            ],
+        );
+        Ok(())
+    }
+}
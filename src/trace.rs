@@ -0,0 +1,16 @@
+//! Debug tracing of the parse, sent to `log` or `tracing` when the feature of the same name is
+//! enabled. Without either feature, `trace!` compiles to nothing and its arguments are never
+//! evaluated.
+
+macro_rules! trace {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::trace!(target: "tomlp", $($arg)*);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(target: "tomlp", $($arg)*);
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
@@ -0,0 +1,97 @@
+//! Checks that the code written by `tomlp codegen` compiles and can be filled in, by including
+//! the code for `tests/codegen/example.toml` as modules. The checked-in code must match what's
+//! written now.
+
+// my imports
+use tomlp::schema::{RustOptions, Schema};
+
+/// Types derived only from std traits, which always compile.
+mod plain {
+    include!("codegen/plain.rs");
+}
+
+/// Types with the default derives, which need the `serde` feature.
+#[cfg(feature = "serde")]
+mod with_serde {
+    include!("codegen/serde.rs");
+}
+
+fn generate(derives: Option<&[&str]>) -> String {
+    let example = tomlp::parse(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/codegen/example.toml"
+    ))
+    .unwrap();
+    let mut options = RustOptions::default();
+    if let Some(derives) = derives {
+        options.derives = derives.iter().map(|derive| derive.to_string()).collect();
+    }
+    Schema::infer([example.table()]).to_rust(&options)
+}
+
+#[test]
+fn up_to_date() {
+    let plain = generate(Some(&["Debug", "Clone", "PartialEq"]));
+    assert_eq!(plain, include_str!("codegen/plain.rs"));
+    assert_eq!(generate(None), include_str!("codegen/serde.rs"));
+}
+
+#[test]
+fn plain() {
+    let server = plain::Server {
+        host: "localhost".to_string(),
+        ports: vec![8080],
+    };
+    let bin = plain::Bin {
+        path: "src/main.rs".to_string(),
+        required: None,
+    };
+    let config = plain::Config {
+        bin: vec![bin],
+        extra: tomlp::TOMLTable::new(),
+        local: "2024-05-01T12:30:00".parse().unwrap(),
+        mixed: vec![tomlp::TOMLType::Int(1), tomlp::TOMLType::Float(3.0)],
+        name: "web".to_string(),
+        odd_key: true,
+        ratio: 0.5,
+        released: "2024-05-01".parse().unwrap(),
+        retries: 3,
+        server,
+        start: "07:32:00".parse().unwrap(),
+        updated: "2024-05-01T12:30:00Z".parse().unwrap(),
+        version: "1.0.0".to_string(),
+    };
+    assert_eq!(config.clone(), config);
+    assert!(format!("{:?}", config).contains("localhost"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn with_serde() {
+    let example = tomlp::parse(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/codegen/example.toml"
+    ))
+    .unwrap();
+    let config: with_serde::Config = example.deserialize().unwrap();
+    assert_eq!(config.name, "web");
+    assert!(config.odd_key);
+    assert_eq!(config.ratio, 0.5);
+    assert_eq!(config.retries, 3);
+    assert_eq!(config.released.to_string(), "2024-05-01");
+    assert_eq!(config.updated.to_string(), "2024-05-01T12:30:00Z");
+    assert_eq!(config.local.to_string(), "2024-05-01T12:30:00");
+    assert_eq!(config.start.to_string(), "07:32:00");
+    assert_eq!(config.mixed[1].str(), Some("two"));
+    assert!(config.extra.is_empty());
+    assert_eq!(config.server.ports, [8080, 8081]);
+    assert_eq!(config.bin.len(), 2);
+    assert_eq!(config.bin[0].required, Some(true));
+    assert_eq!(config.bin[1].path, "src/other.rs");
+    assert_eq!(config.bin[1].required, None);
+    assert_eq!(config.clone(), config);
+
+    // and back again
+    fn assert_serialize<T: serde::Serialize>(_: &T) {}
+    assert_serialize(&config);
+}
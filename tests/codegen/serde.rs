@@ -0,0 +1,32 @@
+/// The whole document.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Config {
+    pub bin: Vec<Bin>,
+    pub extra: tomlp::TOMLTable,
+    pub local: tomlp::Datetime,
+    pub mixed: Vec<tomlp::TOMLType>,
+    pub name: String,
+    #[serde(rename = "odd-key")]
+    pub odd_key: bool,
+    pub ratio: f64,
+    pub released: tomlp::Date,
+    pub retries: i64,
+    pub server: Server,
+    pub start: tomlp::Time,
+    pub updated: tomlp::Datetime,
+    pub version: String,
+}
+
+/// An item of `bin`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Bin {
+    pub path: String,
+    pub required: Option<bool>,
+}
+
+/// The `server` table.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Server {
+    pub host: String,
+    pub ports: Vec<i64>,
+}